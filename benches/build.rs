@@ -0,0 +1,34 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use hribovje::{Point, data::synthetic_dem, qtree::QuadTree};
+
+fn build(c: &mut Criterion) {
+    let origin = Point {
+        x: 400_000.0,
+        y: 100_000.0,
+        data: (),
+    };
+    let points = synthetic_dem(&origin, 1000, 1000, 5.0, 1);
+
+    let mut group = c.benchmark_group("build");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| {
+        b.iter(|| QuadTree::from_points(points.clone()))
+    });
+    #[cfg(feature = "rayon")]
+    group.bench_function("parallel", |b| {
+        b.iter(|| QuadTree::from_points_parallel(points.clone()))
+    });
+    group.bench_function("insert", |b| {
+        b.iter(|| {
+            let mut tree = QuadTree::new(hribovje::Area::from_points(&points));
+            for p in &points {
+                tree.insert(p.clone()).unwrap();
+            }
+            tree
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, build);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+use std::{fs, io::BufWriter};
+
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use hribovje::{
+    Point,
+    data::{XyzFormat, import_data, synthetic_dem, write_xyz},
+};
+
+fn import(c: &mut Criterion) {
+    let origin = Point {
+        x: 400_000.0,
+        y: 100_000.0,
+        data: (),
+    };
+    let points = synthetic_dem(&origin, 500, 500, 5.0, 1);
+
+    let dir = std::env::temp_dir().join(format!("hribovje-bench-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("dem.xyz");
+    let file = BufWriter::new(fs::File::create(&path).unwrap());
+    write_xyz(file, &points, &XyzFormat::default()).unwrap();
+
+    let mut group = c.benchmark_group("import");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(fs::metadata(&path).unwrap().len()));
+    group.bench_function("xyz", |b| {
+        b.iter(|| {
+            let mut out = Vec::with_capacity(points.len() * 20);
+            import_data(&dir, &mut out).unwrap();
+            out
+        })
+    });
+    group.finish();
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, import);
+criterion_main!(benches);
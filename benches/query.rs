@@ -0,0 +1,61 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use hribovje::{Area, Point, Terrain, data::synthetic_dem, qtree::QuadTree};
+
+/// Locations spread over the synthetic DEM, between the grid points.
+fn locations(count: usize) -> Vec<Point> {
+    (0..count)
+        .map(|i| Point {
+            x: 400_000.0 + (i * 7919 % 4990) as f64 + 0.3,
+            y: 100_000.0 + (i * 104_729 % 4990) as f64 + 0.7,
+            data: (),
+        })
+        .collect()
+}
+
+fn query(c: &mut Criterion) {
+    let origin = Point {
+        x: 400_000.0,
+        y: 100_000.0,
+        data: (),
+    };
+    let tree = QuadTree::from_points(synthetic_dem(&origin, 1000, 1000, 5.0, 1));
+    let locations = locations(1000);
+
+    c.bench_function("query/area", |b| {
+        b.iter(|| {
+            let mut count = 0;
+            for p in &locations {
+                let area = Area::square(p.clone(), 50.0);
+                tree.query_with(&area, |_| count += 1).unwrap();
+            }
+            count
+        })
+    });
+
+    c.bench_function("query/nearest", |b| {
+        b.iter(|| locations.iter().filter_map(|p| tree.nearest(p)).count())
+    });
+
+    let terrain = Terrain::new(tree);
+    c.bench_function("query/elevation", |b| {
+        b.iter(|| {
+            locations
+                .iter()
+                .filter_map(|p| terrain.elevation_at(p.x, p.y))
+                .sum::<f32>()
+        })
+    });
+
+    c.bench_function("query/elevations", |b| {
+        b.iter(|| {
+            terrain
+                .elevations(&locations)
+                .into_iter()
+                .flatten()
+                .sum::<f32>()
+        })
+    });
+}
+
+criterion_group!(benches, query);
+criterion_main!(benches);
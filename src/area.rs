@@ -1,17 +1,86 @@
 use crate::Point;
 
-/// Square on map with `width = height = 2 * radius`.
-#[derive(Debug)]
+/// Number of segments of each edge of the WGS84 bounding box,
+/// which is enough for sub meter precision over the whole country.
+const WGS84_EDGE_SAMPLES: usize = 32;
+
+/// Bounds of Slovenia in D96/TM as `(min_x, min_y, max_x, max_y)`. The extreme
+/// points of the country project to about 374 km to 624 km east and 31 km to 194 km
+/// north, which is rounded outwards to 10 km.
+const SLOVENIA_BOUNDS: (f64, f64, f64, f64) = (370_000.0, 30_000.0, 630_000.0, 200_000.0);
+
+/// One of the four regions of the `DEM 0050` data.
+///
+/// Extents of the regions are not included, because the published extents of
+/// the blocks are not known to the crate. Use the extent of the points of
+/// the region's file instead, for example with [`Area::from_points`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Region {
+    NW,
+    NE,
+    SW,
+    SE,
+}
+
+impl Region {
+    pub const ALL: [Region; 4] = [Region::NW, Region::NE, Region::SW, Region::SE];
+
+    /// Returns the lowercase name of the region, such as `nw`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Region::NW => "nw",
+            Region::NE => "ne",
+            Region::SW => "sw",
+            Region::SE => "se",
+        }
+    }
+
+    /// Parses the name of the region, ignoring the case.
+    pub fn from_name(name: &str) -> Option<Region> {
+        Self::ALL
+            .into_iter()
+            .find(|region| region.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Axis aligned rectangle on map with `width = 2 * half_width`
+/// and `height = 2 * half_height`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Area {
     pub center: Point,
-    pub radius: f32,
+    pub half_width: f64,
+    pub half_height: f64,
 }
 
 impl Area {
-    /// Creates the minimum area that contains all the points
+    /// Creates a square with `width = height = 2 * radius`.
+    pub fn square(center: Point, radius: f64) -> Self {
+        Self {
+            center,
+            half_width: radius,
+            half_height: radius,
+        }
+    }
+
+    /// Creates a rectangle from its lower and upper bounds.
+    pub fn from_bounds(min_x: f64, min_y: f64, max_x: f64, max_y: f64) -> Self {
+        Self {
+            center: Point {
+                x: (min_x + max_x) / 2.0,
+                y: (min_y + max_y) / 2.0,
+                data: (),
+            },
+            half_width: (max_x - min_x) / 2.0,
+            half_height: (max_y - min_y) / 2.0,
+        }
+    }
+
+    /// Creates the minimum square that contains all the points
     pub fn from_points<T>(points: &[Point<T>]) -> Self {
         let (min_x, min_y, max_x, max_y) = points.iter().fold(
-            (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
             |(min_x, min_y, max_x, max_y), p| {
                 (
                     min_x.min(p.x),
@@ -25,22 +94,66 @@ impl Area {
         let width = max_x - min_x;
         let height = max_y - min_y;
 
-        Area {
-            center: Point {
+        Area::square(
+            Point {
                 x: width / 2.0 + min_x,
                 y: height / 2.0 + min_y,
                 data: (),
             },
-            radius: width.max(height) / 2.0,
+            width.max(height) / 2.0,
+        )
+    }
+
+    /// Returns the area, which covers all of Slovenia.
+    pub fn slovenia() -> Self {
+        let (min_x, min_y, max_x, max_y) = SLOVENIA_BOUNDS;
+        Self::from_bounds(min_x, min_y, max_x, max_y)
+    }
+
+    /// Creates the smallest area that covers the WGS84 bounding box in degrees.
+    ///
+    /// Edges of the box are curved after the projection to D96/TM,
+    /// so they are sampled densely instead of projecting only the corners.
+    pub fn from_wgs84_bbox(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        let mut edges = Vec::with_capacity(4 * (WGS84_EDGE_SAMPLES + 1));
+        for i in 0..=WGS84_EDGE_SAMPLES {
+            let t = i as f64 / WGS84_EDGE_SAMPLES as f64;
+            let lat = min_lat + (max_lat - min_lat) * t;
+            let lon = min_lon + (max_lon - min_lon) * t;
+            edges.extend([
+                Point::from_wgs84(min_lat, lon),
+                Point::from_wgs84(max_lat, lon),
+                Point::from_wgs84(lat, min_lon),
+                Point::from_wgs84(lat, max_lon),
+            ]);
         }
+
+        let init = Self::from_bounds(edges[0].x, edges[0].y, edges[0].x, edges[0].y);
+        edges.iter().fold(init, |area, p| {
+            area.union(&Self::from_bounds(p.x, p.y, p.x, p.y))
+        })
+    }
+
+    pub fn min_x(&self) -> f64 {
+        self.center.x - self.half_width
+    }
+
+    pub fn min_y(&self) -> f64 {
+        self.center.y - self.half_height
+    }
+
+    pub fn max_x(&self) -> f64 {
+        self.center.x + self.half_width
+    }
+
+    pub fn max_y(&self) -> f64 {
+        self.center.y + self.half_height
     }
 
     /// Returns weather the point is inside the area.
     pub fn is_point_inside<T>(&self, point: &Point<T>) -> bool {
-        let x_inside =
-            point.x >= self.center.x - self.radius && point.x <= self.center.x + self.radius;
-        let y_inside =
-            point.y >= self.center.y - self.radius && point.y <= self.center.y + self.radius;
+        let x_inside = point.x >= self.min_x() && point.x <= self.max_x();
+        let y_inside = point.y >= self.min_y() && point.y <= self.max_y();
 
         x_inside && y_inside
     }
@@ -50,10 +163,84 @@ impl Area {
         let dx = (self.center.x - other.center.x).abs();
         let dy = (self.center.y - other.center.y).abs();
 
-        let x_inter = dx <= self.radius + other.radius;
-        let y_inter = dy <= self.radius + other.radius;
+        let x_inter = dx <= self.half_width + other.half_width;
+        let y_inter = dy <= self.half_height + other.half_height;
         x_inter && y_inter
     }
+
+    /// Returns squared distance between the point and the closest point of the area.
+    ///
+    /// If the point is inside the area, the distance is zero.
+    pub fn distance_sq<T>(&self, point: &Point<T>) -> f64 {
+        let dx = ((point.x - self.center.x).abs() - self.half_width).max(0.0);
+        let dy = ((point.y - self.center.y).abs() - self.half_height).max(0.0);
+
+        dx * dx + dy * dy
+    }
+
+    /// Returns weather the other area is completely inside this area.
+    pub fn contains_area(&self, other: &Self) -> bool {
+        other.min_x() >= self.min_x()
+            && other.max_x() <= self.max_x()
+            && other.min_y() >= self.min_y()
+            && other.max_y() <= self.max_y()
+    }
+
+    /// Returns the smallest area that contains both areas.
+    pub fn union(&self, other: &Self) -> Self {
+        Self::from_bounds(
+            self.min_x().min(other.min_x()),
+            self.min_y().min(other.min_y()),
+            self.max_x().max(other.max_x()),
+            self.max_y().max(other.max_y()),
+        )
+    }
+
+    /// Returns the area covered by both areas, None if they don't intersect.
+    ///
+    /// Areas that only touch have an intersection with zero width or height.
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.intersects(other) {
+            return None;
+        }
+
+        Some(Self::from_bounds(
+            self.min_x().max(other.min_x()),
+            self.min_y().max(other.min_y()),
+            self.max_x().min(other.max_x()),
+            self.max_y().min(other.max_y()),
+        ))
+    }
+
+    /// Returns the area grown by `margin` meters on every side.
+    ///
+    /// Negative margin shrinks the area, but not below zero size.
+    pub fn expand(&self, margin: f64) -> Self {
+        Self {
+            center: self.center.clone(),
+            half_width: (self.half_width + margin).max(0.0),
+            half_height: (self.half_height + margin).max(0.0),
+        }
+    }
+
+    /// Splits the area into four equal quadrants.
+    ///
+    /// Quadrants are ordered by the lower bounds, first by `y` and then by `x`:
+    /// `[(min_x, min_y), (max_x, min_y), (min_x, max_y), (max_x, max_y)]`.
+    pub fn quadrants(&self) -> [Self; 4] {
+        let w = self.half_width / 2.0;
+        let h = self.half_height / 2.0;
+
+        [(-w, -h), (w, -h), (-w, h), (w, h)].map(|(dx, dy)| Self {
+            center: Point {
+                x: self.center.x + dx,
+                y: self.center.y + dy,
+                data: (),
+            },
+            half_width: w,
+            half_height: h,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -66,60 +253,60 @@ mod tests {
     fn area_intersects() {
         let cases = [
             (
-                Area {
-                    center: Point {
+                Area::square(
+                    Point {
                         x: 0.0,
                         y: 0.0,
                         data: (),
                     },
-                    radius: 1.0,
-                },
-                Area {
-                    center: Point {
+                    1.0,
+                ),
+                Area::square(
+                    Point {
                         x: 0.0,
                         y: 0.0,
                         data: (),
                     },
-                    radius: 1.0,
-                },
+                    1.0,
+                ),
                 true,
             ),
             (
-                Area {
-                    center: Point {
+                Area::square(
+                    Point {
                         x: 0.0,
                         y: 0.0,
                         data: (),
                     },
-                    radius: 1.0,
-                },
-                Area {
-                    center: Point {
+                    1.0,
+                ),
+                Area::square(
+                    Point {
                         x: 2.0,
                         y: 2.0,
                         data: (),
                     },
-                    radius: 1.0,
-                },
+                    1.0,
+                ),
                 true,
             ),
             (
-                Area {
-                    center: Point {
+                Area::square(
+                    Point {
                         x: 0.0,
                         y: 0.0,
                         data: (),
                     },
-                    radius: 1.0,
-                },
-                Area {
-                    center: Point {
+                    1.0,
+                ),
+                Area::square(
+                    Point {
                         x: 2.0,
                         y: 2.0,
                         data: (),
                     },
-                    radius: 0.9,
-                },
+                    0.9,
+                ),
                 false,
             ),
         ];
@@ -129,4 +316,66 @@ mod tests {
             assert_eq!(a2.intersects(a1), *expected);
         }
     }
+
+    #[test]
+    fn area_arithmetic() {
+        let a = Area::from_bounds(0.0, 0.0, 10.0, 4.0);
+        let b = Area::from_bounds(6.0, 2.0, 12.0, 8.0);
+
+        assert_eq!(a.union(&b), Area::from_bounds(0.0, 0.0, 12.0, 8.0));
+        assert_eq!(
+            a.intersection(&b),
+            Some(Area::from_bounds(6.0, 2.0, 10.0, 4.0))
+        );
+        assert_eq!(
+            a.intersection(&Area::from_bounds(20.0, 0.0, 30.0, 4.0)),
+            None
+        );
+
+        assert_eq!(a.expand(1.0), Area::from_bounds(-1.0, -1.0, 11.0, 5.0));
+        assert_eq!(a.expand(-3.0).half_height, 0.0);
+        assert!(a.expand(1.0).contains_area(&a));
+        assert!(!a.contains_area(&b));
+
+        let quadrants = a.quadrants();
+        assert_eq!(quadrants[1], Area::from_bounds(5.0, 0.0, 10.0, 2.0));
+        assert_eq!(quadrants[2], Area::from_bounds(0.0, 2.0, 5.0, 4.0));
+        let union = quadrants
+            .iter()
+            .fold(quadrants[0].clone(), |u, q| u.union(q));
+        assert_eq!(union, a);
+    }
+
+    #[test]
+    fn wgs84_bbox_covers_corners_and_edges() {
+        let area = Area::from_wgs84_bbox(46.0, 14.0, 46.5, 16.0);
+        for (lat, lon) in [(46.0, 14.0), (46.5, 16.0), (46.0, 15.0), (46.5, 15.0)] {
+            let p = Point::from_wgs84(lat, lon);
+            assert!(area.expand(1e-6).is_point_inside(&p), "{lat} {lon}");
+        }
+
+        // Parallels sag towards the equator at the central meridian,
+        // so projecting only the corners would miss the bottom edge.
+        let corners = Point::from_wgs84(46.0, 14.0)
+            .y
+            .min(Point::from_wgs84(46.0, 16.0).y);
+        assert!(area.min_y() < corners - 100.0);
+    }
+
+    #[test]
+    fn slovenia_covers_extreme_points() {
+        // Extreme points of the country: Budinci, Damelj, Breginj and Benica.
+        for (lat, lon) in [
+            (46.877, 16.008),
+            (45.421, 15.171),
+            (46.261, 13.375),
+            (46.478, 16.610),
+        ] {
+            let point = Point::from_wgs84(lat, lon);
+            assert!(
+                Area::slovenia().expand(-1000.0).is_point_inside(&point),
+                "{lat} {lon}"
+            );
+        }
+    }
 }
@@ -0,0 +1,309 @@
+//! Command line interface for the most common data pipelines.
+//!
+//! Points are stored in the binary point format of the library. Coordinates
+//! are in D96/TM, except for `elevation`, which takes a WGS84 location.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::{Parser, Subcommand};
+use hribovje::{
+    Area, Point, Terrain,
+    data::{self, Dedup, ImportOptions, XyzFormat},
+    grid::{
+        Raster,
+        render::{RenderOptions, write_png},
+    },
+    qtree::QuadTree,
+};
+
+#[derive(Parser)]
+#[command(version, about = "Slovenian digital elevation model tools")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Download the archives listed in the manifest.
+    #[cfg(feature = "download")]
+    Download {
+        /// Manifest with the archives, see the documentation of `hribovje::download`.
+        #[arg(short, long)]
+        manifest: PathBuf,
+        /// Download only the archives intersecting `min_x,min_y,max_x,max_y`.
+        #[arg(long, value_parser = parse_bbox)]
+        bbox: Option<Area>,
+        /// Download only the archive of the region, one of `nw`, `ne`, `sw` or `se`.
+        #[arg(long, value_parser = parse_region, conflicts_with = "bbox")]
+        region: Option<hribovje::Region>,
+        /// Output directory.
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Import raw data files into a point file.
+    Import {
+        /// Directory or file with the raw data.
+        input: PathBuf,
+        /// Output point file.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Import only the points inside `min_x,min_y,max_x,max_y`.
+        #[arg(long, value_parser = parse_bbox)]
+        bbox: Option<Area>,
+        /// Drop points with the same coordinates.
+        #[arg(long)]
+        dedup: bool,
+    },
+    /// Print points inside the bounding box in `.xyz` format.
+    Query {
+        /// Point file.
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Bounding box as `min_x,min_y,max_x,max_y`.
+        #[arg(long, value_parser = parse_bbox)]
+        bbox: Area,
+        /// Output file, standard output if not set.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Print the interpolated height at the WGS84 location.
+    Elevation {
+        /// Point file.
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(long, allow_hyphen_values = true)]
+        lat: f64,
+        #[arg(long, allow_hyphen_values = true)]
+        lon: f64,
+    },
+    /// Print the elevation profile of a GPX track as CSV.
+    Profile {
+        /// Point file.
+        #[arg(short, long)]
+        input: PathBuf,
+        /// GPX file with the track.
+        #[arg(long)]
+        gpx: PathBuf,
+        /// Maximum distance between samples in meters.
+        #[arg(long, default_value_t = 10.0, value_parser = parse_positive)]
+        step: f64,
+    },
+    /// Render the shaded relief of the bounding box as PNG image.
+    Hillshade {
+        /// Point file.
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Bounding box as `min_x,min_y,max_x,max_y`.
+        #[arg(long, value_parser = parse_bbox)]
+        bbox: Area,
+        /// Output image.
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Spacing of the points in meters.
+        #[arg(long, default_value_t = 5.0, value_parser = parse_positive)]
+        cell_size: f64,
+        /// Direction of the light in degrees clockwise from north.
+        #[arg(long, default_value_t = 315.0)]
+        azimuth: f64,
+        /// Angle of the light above the horizon in degrees.
+        #[arg(long, default_value_t = 45.0)]
+        altitude: f64,
+    },
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Cli::parse().command {
+        #[cfg(feature = "download")]
+        Command::Download {
+            manifest,
+            bbox,
+            region,
+            output,
+        } => {
+            let manifest =
+                hribovje::download::parse_manifest(BufReader::new(File::open(manifest)?))?;
+            let progress =
+                |entry: &hribovje::download::ManifestEntry, done: u64, total: Option<u64>| {
+                    match total {
+                        Some(total) => {
+                            eprint!("\r{}: {} / {} MB", entry.name, done >> 20, total >> 20)
+                        }
+                        None => eprint!("\r{}: {} MB", entry.name, done >> 20),
+                    }
+                };
+            match region {
+                Some(region) => {
+                    hribovje::download::download_region(&manifest, region, output, progress)?
+                }
+                None => {
+                    hribovje::download::download_area(&manifest, bbox.as_ref(), output, progress)?
+                }
+            };
+            eprintln!();
+        }
+        Command::Import {
+            input,
+            output,
+            bbox,
+            dedup,
+        } => {
+            let options = ImportOptions {
+                area: bbox,
+                dedup: if dedup { Dedup::Exact } else { Dedup::Off },
+                ..Default::default()
+            };
+            let writer = BufWriter::new(File::create(output)?);
+            let report = data::import_data_with(input, writer, &options, |progress| {
+                eprint!(
+                    "\rimported {}/{} files, {} points",
+                    progress.files_done, progress.files_total, progress.points_written
+                );
+            })?;
+            eprintln!();
+            if report.duplicates > 0 {
+                eprintln!("dropped {} duplicates", report.duplicates);
+            }
+        }
+        Command::Query {
+            input,
+            bbox,
+            output,
+        } => {
+            let points = read_area(&input, &bbox)?;
+            let writer: Box<dyn Write> = match output {
+                Some(path) => Box::new(File::create(path)?),
+                None => Box::new(io::stdout().lock()),
+            };
+            data::write_xyz(BufWriter::new(writer), &points, &XyzFormat::default())?;
+        }
+        Command::Elevation { input, lat, lon } => {
+            let point = Point::from_wgs84(lat, lon);
+            let terrain = load_terrain(&input, std::slice::from_ref(&point))?;
+            match terrain.elevation_at(point.x, point.y) {
+                Some(elevation) => println!("{elevation:.2}"),
+                None => return Err("no data at the location".into()),
+            }
+        }
+        Command::Profile { input, gpx, step } => {
+            let segments = data::read_gpx(BufReader::new(File::open(gpx)?))?;
+            let track: Vec<_> = segments.into_iter().flatten().collect();
+            let terrain = load_terrain(&input, &track)?;
+
+            let mut out = BufWriter::new(io::stdout().lock());
+            writeln!(out, "distance,x,y,elevation,grade")?;
+            for sample in terrain.profile(&track, step) {
+                let elevation = sample.elevation.map(|e| format!("{e:.2}"));
+                let grade = sample.grade.map(|g| format!("{g:.4}"));
+                writeln!(
+                    out,
+                    "{:.1},{:.2},{:.2},{},{}",
+                    sample.distance,
+                    sample.point.x,
+                    sample.point.y,
+                    elevation.unwrap_or_default(),
+                    grade.unwrap_or_default()
+                )?;
+            }
+        }
+        Command::Hillshade {
+            input,
+            bbox,
+            output,
+            cell_size,
+            azimuth,
+            altitude,
+        } => {
+            let points = read_area(&input, &bbox)?;
+            let shade = Raster::from_points(&points, cell_size).hillshade(azimuth, altitude);
+            let writer = BufWriter::new(File::create(output)?);
+            write_png(writer, &shade, &RenderOptions::default())?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_bbox(s: &str) -> Result<Area, String> {
+    let values = s
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| err.to_string())?;
+    let [min_x, min_y, max_x, max_y] = values[..] else {
+        return Err("expected min_x,min_y,max_x,max_y".to_string());
+    };
+    if min_x > max_x || min_y > max_y {
+        return Err("minimum is larger than maximum".to_string());
+    }
+
+    Ok(Area::from_bounds(min_x, min_y, max_x, max_y))
+}
+
+#[cfg(feature = "download")]
+fn parse_region(s: &str) -> Result<hribovje::Region, String> {
+    hribovje::Region::from_name(s.trim())
+        .ok_or_else(|| "expected one of nw, ne, sw or se".to_string())
+}
+
+fn parse_positive(s: &str) -> Result<f64, String> {
+    let value = s.trim().parse::<f64>().map_err(|err| err.to_string())?;
+    if value > 0.0 && value.is_finite() {
+        Ok(value)
+    } else {
+        Err("expected a positive number".to_string())
+    }
+}
+
+fn read_area(input: &Path, area: &Area) -> Result<Vec<Point<f32>>, io::Error> {
+    data::read_points_in_area(BufReader::new(File::open(input)?), area)
+}
+
+/// Loads terrain around the locations, so that the whole file doesn't have to be read.
+fn load_terrain<T>(input: &Path, locations: &[Point<T>]) -> Result<Terrain, Box<dyn Error>> {
+    if locations.is_empty() {
+        return Err("no locations".into());
+    }
+
+    let area = Area::from_points(locations).expand(2.0 * Terrain::DEFAULT_MAX_DISTANCE);
+
+    let points = read_area(input, &area)?;
+    Ok(Terrain::new(QuadTree::from_points(points)))
+}
+
+#[cfg(test)]
+mod tests {
+    use hribovje::Area;
+
+    use super::{parse_bbox, parse_positive};
+
+    #[test]
+    fn parse_arguments() {
+        assert_eq!(
+            parse_bbox("462000, 101000,463000,102000.5"),
+            Ok(Area::from_bounds(462000.0, 101000.0, 463000.0, 102000.5))
+        );
+        assert!(parse_bbox("462000,101000,463000").is_err());
+        assert!(parse_bbox("462000,101000,463000,102000,0").is_err());
+        assert!(parse_bbox("463000,101000,462000,102000").is_err());
+        assert!(parse_bbox("a,101000,463000,102000").is_err());
+
+        assert_eq!(parse_positive("2.5"), Ok(2.5));
+        assert!(parse_positive("0").is_err());
+        assert!(parse_positive("-1").is_err());
+        assert!(parse_positive("NaN").is_err());
+    }
+
+    #[cfg(feature = "download")]
+    #[test]
+    fn parse_region_names() {
+        assert_eq!(super::parse_region("NW"), Ok(hribovje::Region::NW));
+        assert_eq!(super::parse_region("se"), Ok(hribovje::Region::SE));
+        assert!(super::parse_region("north").is_err());
+    }
+}
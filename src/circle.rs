@@ -0,0 +1,63 @@
+use crate::{Area, Point};
+
+/// Circle on map with the given `radius` around the `center`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Circle {
+    pub center: Point,
+    pub radius: f64,
+}
+
+impl Circle {
+    /// Returns weather the point is inside the circle.
+    pub fn contains<T>(&self, point: &Point<T>) -> bool {
+        self.center.distance_sq(point) <= self.radius * self.radius
+    }
+
+    /// Returns weather the circle intersects the area.
+    pub fn intersects_area(&self, area: &Area) -> bool {
+        area.distance_sq(&self.center) <= self.radius * self.radius
+    }
+
+    /// Returns the smallest area that contains the circle.
+    pub fn bounding_area(&self) -> Area {
+        Area::square(self.center.clone(), self.radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point};
+
+    use super::Circle;
+
+    #[test]
+    fn circle_intersects_area() {
+        let circle = Circle {
+            center: Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            radius: 1.0,
+        };
+        assert!(circle.contains(&Point {
+            x: 0.6,
+            y: 0.6,
+            data: (),
+        }));
+        assert!(!circle.contains(&Point {
+            x: 0.8,
+            y: 0.8,
+            data: (),
+        }));
+
+        // Corner of the area is inside the bounding square, but not in the circle.
+        let corner = Area::from_bounds(0.8, 0.8, 2.0, 2.0);
+        assert!(circle.bounding_area().intersects(&corner));
+        assert!(!circle.intersects_area(&corner));
+
+        let side = Area::from_bounds(0.9, -0.1, 2.0, 0.1);
+        assert!(circle.intersects_area(&side));
+    }
+}
@@ -0,0 +1,326 @@
+//! Conversions between coordinate reference systems.
+//!
+//! Points in the crate are in [D96/TM](https://epsg.io/3794), which is a transverse
+//! Mercator projection of the D96 datum (Slovenian realization of ETRS89).
+//! ETRS89 and WGS84 differ for less than a meter in Slovenia, so geographic
+//! coordinates of D96 are used as WGS84 without a datum shift.
+//! For most use cases, [`Point::to_wgs84`](crate::Point::to_wgs84) and
+//! [`Point::from_wgs84`](crate::Point::from_wgs84) are all you need.
+//!
+//! Older datasets use the legacy [D48/GK](https://epsg.io/3912) system, which can be
+//! converted with [`d48_gk_to_d96_tm`] and [`d96_tm_to_d48_gk`].
+
+/// Reference ellipsoid.
+#[derive(Debug, Clone, Copy)]
+pub struct Ellipsoid {
+    /// Semi-major axis in meters.
+    pub a: f64,
+    /// Flattening.
+    pub f: f64,
+}
+
+impl Ellipsoid {
+    pub const GRS80: Self = Self {
+        a: 6378137.0,
+        f: 1.0 / 298.257222101,
+    };
+
+    /// Ellipsoid of the D48 datum.
+    pub const BESSEL_1841: Self = Self {
+        a: 6377397.155,
+        f: 1.0 / 299.1528128,
+    };
+
+    /// Returns the first eccentricity squared.
+    fn e2(&self) -> f64 {
+        self.f * (2.0 - self.f)
+    }
+
+    /// Converts `(lat, lon)` in degrees on the surface of the ellipsoid
+    /// to geocentric cartesian coordinates in meters.
+    pub fn to_geocentric(&self, lat: f64, lon: f64) -> [f64; 3] {
+        let e2 = self.e2();
+        let (phi, lambda) = (lat.to_radians(), lon.to_radians());
+        let nu = self.a / (1.0 - e2 * phi.sin() * phi.sin()).sqrt();
+
+        [
+            nu * phi.cos() * lambda.cos(),
+            nu * phi.cos() * lambda.sin(),
+            nu * (1.0 - e2) * phi.sin(),
+        ]
+    }
+
+    /// Converts geocentric cartesian coordinates in meters to `(lat, lon)` in degrees.
+    ///
+    /// Ellipsoidal height is discarded.
+    pub fn from_geocentric(&self, [x, y, z]: [f64; 3]) -> (f64, f64) {
+        let e2 = self.e2();
+        let p = x.hypot(y);
+
+        // Latitude converges to sub millimeter precision in a few iterations.
+        let mut phi = z.atan2(p * (1.0 - e2));
+        for _ in 0..5 {
+            let nu = self.a / (1.0 - e2 * phi.sin() * phi.sin()).sqrt();
+            let h = p / phi.cos() - nu;
+            phi = z.atan2(p * (1.0 - e2 * nu / (nu + h)));
+        }
+
+        (phi.to_degrees(), y.atan2(x).to_degrees())
+    }
+}
+
+/// Seven parameter Helmert transformation of geocentric coordinates,
+/// using the position vector convention.
+#[derive(Debug, Clone, Copy)]
+pub struct Helmert {
+    /// Translation in meters.
+    pub tx: f64,
+    pub ty: f64,
+    pub tz: f64,
+    /// Rotation in arc seconds.
+    pub rx: f64,
+    pub ry: f64,
+    pub rz: f64,
+    /// Scale difference in parts per million.
+    pub scale: f64,
+}
+
+impl Helmert {
+    /// Transformation from D48 to D96 for the whole Slovenia.
+    ///
+    /// Accuracy of the transformation is around a meter. Distortions of the old
+    /// triangulation network are larger than that, and can only be modelled with
+    /// the national triangle-based transformation, which is not implemented.
+    pub const D48_TO_D96: Self = Self {
+        tx: 409.545,
+        ty: 72.164,
+        tz: 486.872,
+        rx: 3.085957,
+        ry: 5.469110,
+        rz: -11.020289,
+        scale: 17.919665,
+    };
+
+    /// Transforms geocentric coordinates.
+    pub fn apply(&self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let (rx, ry, rz) = self.rotation();
+        let m = 1.0 + self.scale * 1e-6;
+
+        [
+            self.tx + m * (x - rz * y + ry * z),
+            self.ty + m * (rz * x + y - rx * z),
+            self.tz + m * (-ry * x + rx * y + z),
+        ]
+    }
+
+    /// Transforms geocentric coordinates in the opposite direction of [`Helmert::apply`].
+    pub fn apply_inverse(&self, [x, y, z]: [f64; 3]) -> [f64; 3] {
+        let (rx, ry, rz) = self.rotation();
+        let m = 1.0 + self.scale * 1e-6;
+        let [x, y, z] = [(x - self.tx) / m, (y - self.ty) / m, (z - self.tz) / m];
+
+        // Rotation is tiny, so the fixed point iteration converges in a few steps.
+        let mut p = [x, y, z];
+        for _ in 0..4 {
+            p = [
+                x + rz * p[1] - ry * p[2],
+                y - rz * p[0] + rx * p[2],
+                z + ry * p[0] - rx * p[1],
+            ];
+        }
+
+        p
+    }
+
+    /// Returns rotations in radians.
+    fn rotation(&self) -> (f64, f64, f64) {
+        let to_rad = |arcsec: f64| (arcsec / 3600.0).to_radians();
+        (to_rad(self.rx), to_rad(self.ry), to_rad(self.rz))
+    }
+}
+
+/// Transverse Mercator projection with origin on the equator.
+#[derive(Debug, Clone, Copy)]
+pub struct TransverseMercator {
+    pub ellipsoid: Ellipsoid,
+    /// Central meridian in degrees.
+    pub lon0: f64,
+    /// Scale factor on the central meridian.
+    pub k0: f64,
+    pub false_easting: f64,
+    pub false_northing: f64,
+}
+
+impl TransverseMercator {
+    /// D96/TM projection, also known as EPSG:3794.
+    pub const D96_TM: Self = Self {
+        ellipsoid: Ellipsoid::GRS80,
+        lon0: 15.0,
+        k0: 0.9999,
+        false_easting: 500000.0,
+        false_northing: -5000000.0,
+    };
+
+    /// D48/GK projection, also known as EPSG:3912.
+    ///
+    /// It has the same parameters as D96/TM, but uses the Bessel ellipsoid.
+    pub const D48_GK: Self = Self {
+        ellipsoid: Ellipsoid::BESSEL_1841,
+        ..Self::D96_TM
+    };
+
+    /// Projects geographic coordinates in degrees to `(easting, northing)` in meters.
+    pub fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let a = self.ellipsoid.a;
+        let e2 = self.ellipsoid.e2();
+        let ep2 = e2 / (1.0 - e2);
+
+        let phi = lat.to_radians();
+        let (sin, cos, tan) = (phi.sin(), phi.cos(), phi.tan());
+
+        let nu = a / (1.0 - e2 * sin * sin).sqrt();
+        let t = tan * tan;
+        let c = ep2 * cos * cos;
+        let aa = (lon - self.lon0).to_radians() * cos;
+
+        let easting = self.false_easting
+            + self.k0
+                * nu
+                * (aa
+                    + (1.0 - t + c) * aa.powi(3) / 6.0
+                    + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * aa.powi(5) / 120.0);
+        let northing = self.false_northing
+            + self.k0
+                * (self.meridian_arc(phi)
+                    + nu * tan
+                        * (aa * aa / 2.0
+                            + (5.0 - t + 9.0 * c + 4.0 * c * c) * aa.powi(4) / 24.0
+                            + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * aa.powi(6)
+                                / 720.0));
+
+        (easting, northing)
+    }
+
+    /// Converts projected `(easting, northing)` in meters to `(lat, lon)` in degrees.
+    pub fn inverse(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let a = self.ellipsoid.a;
+        let e2 = self.ellipsoid.e2();
+        let ep2 = e2 / (1.0 - e2);
+
+        // Footpoint latitude
+        let m = (northing - self.false_northing) / self.k0;
+        let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+        let phi1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+
+        let (sin, cos, tan) = (phi1.sin(), phi1.cos(), phi1.tan());
+        let nu = a / (1.0 - e2 * sin * sin).sqrt();
+        let rho = a * (1.0 - e2) / (1.0 - e2 * sin * sin).powf(1.5);
+        let t = tan * tan;
+        let c = ep2 * cos * cos;
+        let d = (easting - self.false_easting) / (nu * self.k0);
+
+        let phi = phi1
+            - (nu * tan / rho)
+                * (d * d / 2.0
+                    - (5.0 + 3.0 * t + 10.0 * c - 4.0 * c * c - 9.0 * ep2) * d.powi(4) / 24.0
+                    + (61.0 + 90.0 * t + 298.0 * c + 45.0 * t * t - 252.0 * ep2 - 3.0 * c * c)
+                        * d.powi(6)
+                        / 720.0);
+        let lambda = (d - (1.0 + 2.0 * t + c) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c + 28.0 * t - 3.0 * c * c + 8.0 * ep2 + 24.0 * t * t) * d.powi(5)
+                / 120.0)
+            / cos;
+
+        (phi.to_degrees(), self.lon0 + lambda.to_degrees())
+    }
+
+    /// Returns the length of the meridian arc from the equator to the latitude.
+    fn meridian_arc(&self, phi: f64) -> f64 {
+        let e2 = self.ellipsoid.e2();
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+
+        self.ellipsoid.a
+            * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * phi
+                - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * phi).sin()
+                + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * phi).sin()
+                - (35.0 * e6 / 3072.0) * (6.0 * phi).sin())
+    }
+}
+
+/// Converts D48/GK `(easting, northing)` to D96/TM `(easting, northing)`.
+///
+/// Heights are not transformed. See [`Helmert::D48_TO_D96`] for the accuracy.
+pub fn d48_gk_to_d96_tm(easting: f64, northing: f64) -> (f64, f64) {
+    let (lat, lon) = TransverseMercator::D48_GK.inverse(easting, northing);
+    let geocentric = Helmert::D48_TO_D96.apply(Ellipsoid::BESSEL_1841.to_geocentric(lat, lon));
+    let (lat, lon) = Ellipsoid::GRS80.from_geocentric(geocentric);
+
+    TransverseMercator::D96_TM.forward(lat, lon)
+}
+
+/// Converts D96/TM `(easting, northing)` to D48/GK `(easting, northing)`.
+///
+/// Heights are not transformed. See [`Helmert::D48_TO_D96`] for the accuracy.
+pub fn d96_tm_to_d48_gk(easting: f64, northing: f64) -> (f64, f64) {
+    let (lat, lon) = TransverseMercator::D96_TM.inverse(easting, northing);
+    let geocentric = Helmert::D48_TO_D96.apply_inverse(Ellipsoid::GRS80.to_geocentric(lat, lon));
+    let (lat, lon) = Ellipsoid::BESSEL_1841.from_geocentric(geocentric);
+
+    TransverseMercator::D48_GK.forward(lat, lon)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::{TransverseMercator, d48_gk_to_d96_tm, d96_tm_to_d48_gk};
+
+    #[test]
+    fn d96_tm_forward_inverse() {
+        // Reference values computed with Krüger series.
+        let cases = [
+            (46.0, 15.0, 500000.0, 95576.318),
+            (46.3783, 13.8366, 410504.583, 138279.851),
+            (46.0569, 14.5058, 461760.739, 102018.972),
+        ];
+
+        for (lat, lon, easting, northing) in cases {
+            let (x, y) = TransverseMercator::D96_TM.forward(lat, lon);
+            assert!((x - easting).abs() < 0.01, "{x} != {easting}");
+            assert!((y - northing).abs() < 0.01, "{y} != {northing}");
+
+            let (got_lat, got_lon) = TransverseMercator::D96_TM.inverse(x, y);
+            assert!((got_lat - lat).abs() < 1e-8);
+            assert!((got_lon - lon).abs() < 1e-8);
+
+            let point = Point::from_wgs84(lat, lon);
+            assert_eq!((point.x, point.y), (x, y));
+            assert_eq!(point.to_wgs84(), (got_lat, got_lon));
+        }
+    }
+
+    #[test]
+    fn d48_gk_d96_tm() {
+        // Reference values computed with the Helmert transformation and Krüger series.
+        let cases = [
+            ((462000.0, 101000.0), (461629.075, 101486.272)),
+            ((410000.0, 138000.0), (409629.382, 138487.931)),
+            ((600000.0, 160000.0), (599631.910, 160483.591)),
+        ];
+
+        for ((e48, n48), (e96, n96)) in cases {
+            let (x, y) = d48_gk_to_d96_tm(e48, n48);
+            assert!((x - e96).abs() < 0.01, "{x} != {e96}");
+            assert!((y - n96).abs() < 0.01, "{y} != {n96}");
+
+            let (x, y) = d96_tm_to_d48_gk(e96, n96);
+            assert!((x - e48).abs() < 0.01, "{x} != {e48}");
+            assert!((y - n48).abs() < 0.01, "{y} != {n48}");
+        }
+    }
+}
@@ -1,13 +1,55 @@
 //! Provides utilities for reading and writing points.
+
+pub mod export;
+
+mod codec;
+mod dedup;
+mod downsample;
+mod format;
+#[cfg(feature = "geotiff")]
+mod geotiff;
+#[cfg(feature = "gpkg")]
+mod gpkg;
+#[cfg(feature = "gpx")]
+mod gpx;
+#[cfg(feature = "las")]
+mod las;
+mod synthetic;
+mod tiled;
+mod xyz;
+
 use std::{
     fs,
     io::{self, BufRead},
-    path::Path,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
 };
 
 use thiserror::Error;
 
-use crate::Point;
+use crate::{Area, Point, grid::Quantization};
+
+use dedup::Deduplicator;
+use downsample::Downsampler;
+use format::{PointReader, PointWriter};
+use xyz::parse_xyz;
+
+pub use codec::{Codec, write_points_with};
+pub use downsample::{CellValue, DownsampleMode, downsample};
+pub use synthetic::synthetic_dem;
+pub use tiled::{TileStore, import_data_tiled};
+
+#[cfg(feature = "geotiff")]
+pub use geotiff::import_geotiff;
+#[cfg(feature = "gpkg")]
+pub use gpkg::{GpkgError, write_contours_gpkg};
+#[cfg(feature = "gpx")]
+pub use gpx::read_gpx;
+#[cfg(feature = "las")]
+pub use las::{CLASS_GROUND, import_las};
 
 /// The error type that can occur during data import.
 ///
@@ -18,76 +60,198 @@ pub enum ImportError {
     #[error("io error")]
     Io(#[from] io::Error),
 
-    #[error("invalid data (expected 3 components, found {0})")]
-    InvalidData(u8),
+    #[error(
+        "invalid data at {}:{line} (expected 3 components, found {found})",
+        path.display()
+    )]
+    InvalidData {
+        /// File that contains the invalid line.
+        path: PathBuf,
+        /// Line number, starting with 1.
+        line: u64,
+        /// Number of valid components on the line.
+        found: u8,
+    },
+
+    #[error("invalid ascii grid ({0})")]
+    InvalidGrid(&'static str),
+
+    #[error("invalid tile size {0} (expected a positive whole number of meters)")]
+    InvalidTileSize(f64),
+
+    #[cfg(feature = "geotiff")]
+    #[error("tiff error")]
+    Tiff(#[from] tiff::TiffError),
+
+    #[cfg(feature = "geotiff")]
+    #[error("invalid geotiff ({0})")]
+    InvalidGeoTiff(&'static str),
+
+    #[cfg(feature = "gpx")]
+    #[error("gpx error")]
+    Gpx(#[from] ::gpx::errors::GpxError),
+
+    #[cfg(feature = "las")]
+    #[error("las error")]
+    Las(#[from] ::las::Error),
+
+    #[cfg(feature = "zip")]
+    #[error("zip error")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("import was cancelled")]
+    Cancelled,
 }
 
 pub type ImportResult = Result<(), ImportError>;
 
-struct PointWriter<W: io::Write>(W);
+/// What to do when a malformed line is encountered during the import.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OnError {
+    /// Abort the import with the error.
+    #[default]
+    Fail,
+    /// Skip the line and report it in [`ImportReport::skipped`].
+    Skip,
+}
 
-impl<W: io::Write> PointWriter<W> {
-    fn write(&mut self, point: &Point<f32>) -> Result<(), io::Error> {
-        let buf = point.x.to_le_bytes();
-        self.0.write_all(&buf)?;
+/// Removal of duplicate points during the import.
+///
+/// Neighbouring data sets often overlap along their seams. The first occurrence
+/// of a point is kept and the later duplicates are counted in [`ImportReport::duplicates`].
+/// Already built trees can be deduplicated with [`DuplicatePolicy`](crate::qtree::DuplicatePolicy).
+///
+/// All kept coordinates are held in memory for the duration of the import.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Dedup {
+    /// Keep all points.
+    #[default]
+    Off,
+    /// Drop points with exactly the same coordinates as an already imported point.
+    Exact,
+    /// Drop points closer than the given distance to an already imported point.
+    Tolerance(f64),
+}
 
-        let buf = point.y.to_le_bytes();
-        self.0.write_all(&buf)?;
+/// Token for cancelling a running import, for example from another thread.
+///
+/// Clones share the same state, so the import can be cancelled through any of them.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
 
-        let buf = point.data.to_le_bytes();
-        self.0.write_all(&buf)?;
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        Ok(())
+    /// Requests cancellation of the imports using this token.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
     }
-}
 
-struct PointReader<R: io::Read>(R);
+    /// Returns weather the cancellation was requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
 
-impl<R: io::Read> PointReader<R> {
-    fn read(&mut self) -> Result<Vec<Point<f32>>, io::Error> {
-        let mut points = vec![];
+/// Options of [`import_data_with`].
+#[derive(Debug, Clone, Default)]
+pub struct ImportOptions {
+    pub on_error: OnError,
+    pub cancel: CancelToken,
+    pub dedup: Dedup,
+    /// If set, points outside of the area are discarded while parsing.
+    pub area: Option<Area>,
+    /// Downsampling of the points, applied after the duplicates are removed.
+    ///
+    /// With [`DownsampleMode::Grid`] the cells are written at the end of the import.
+    pub downsample: DownsampleMode,
+}
 
-        let mut comps = [0f32; 3];
-        let mut comp_idx = 0;
-        let mut buf = [0u8; 4];
-
-        loop {
-            match self.0.read_exact(&mut buf) {
-                Ok(_) => {
-                    let comp = f32::from_le_bytes(buf);
-                    comps[comp_idx] = comp;
-                    comp_idx += 1;
-
-                    if comp_idx == 3 {
-                        points.push(Point {
-                            x: comps[0],
-                            y: comps[1],
-                            data: comps[2],
-                        });
-                        comp_idx = 0;
-                    }
-                }
+impl ImportOptions {
+    fn is_included(&self, point: &Point<f32>) -> bool {
+        self.area
+            .as_ref()
+            .is_none_or(|area| area.is_point_inside(point))
+    }
 
-                // We reached EOF and can break
-                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
-                // Handle other errors
-                Err(err) => return Err(err),
-            }
+    fn check_cancelled(&self) -> ImportResult {
+        if self.cancel.is_cancelled() {
+            Err(ImportError::Cancelled)
+        } else {
+            Ok(())
         }
+    }
+}
+
+/// Summary of a finished import.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// Number of imported points.
+    pub points: u64,
+    /// Number of points dropped as duplicates.
+    pub duplicates: u64,
+    /// Errors of the lines that were skipped, in the order they were encountered.
+    pub skipped: Vec<ImportError>,
+}
 
-        Ok(points)
+/// Result of [`verify`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct VerifyReport {
+    /// Whether the chunks have checksums. Files written before checksums
+    /// were added are only checked for valid structure and values.
+    pub checksums: bool,
+    /// Number of checked chunks.
+    pub chunks: u64,
+    /// Number of points in the checked chunks.
+    pub points: u64,
+    /// Corrupted chunks, in the order of the file.
+    pub corrupt: Vec<CorruptChunk>,
+}
+
+impl VerifyReport {
+    /// Returns true if no corruption was found.
+    pub fn is_ok(&self) -> bool {
+        self.corrupt.is_empty()
     }
 }
 
+/// Chunk of a point file that failed the [`verify`] check.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorruptChunk {
+    /// Index of the chunk, starting with zero.
+    pub index: u64,
+    /// Byte offset of the chunk from the start of the file.
+    pub offset: u64,
+    pub corruption: Corruption,
+}
+
+/// Kind of corruption of a chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Corruption {
+    /// Checksum doesn't match the content of the chunk.
+    ChecksumMismatch,
+    /// Points can't be decoded, or have coordinates or heights that are not finite.
+    InvalidPoints,
+    /// Number of points or size of the chunk is invalid. Chunks after it can't be found.
+    InvalidChunk,
+    /// File ends inside of the chunk, or before all points of the header.
+    Truncated,
+}
+
 /// Read points from provided reader.
 ///
 /// Returned points contain height as data.
+/// Both the current format with a header and the legacy headerless
+/// stream of points are supported. Compressed files are decompressed
+/// automatically, if the feature of their [`Codec`] is enabled.
 ///
 /// If reading from file, you should wrap it into
 /// [BufReader](https://doc.rust-lang.org/std/io/struct.BufReader.html)
 /// to improve the performance.
 pub fn read_points(reader: impl io::Read) -> Result<Vec<Point<f32>>, io::Error> {
-    let mut reader = PointReader(reader);
+    let mut reader = PointReader(codec::decoder(reader)?);
     reader.read()
 }
 
@@ -99,76 +263,639 @@ pub fn read_points(reader: impl io::Read) -> Result<Vec<Point<f32>>, io::Error>
 /// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
 /// to improve the performance.
 pub fn write_points(writer: impl io::Write, points: &[Point<f32>]) -> Result<(), io::Error> {
-    let mut writer = PointWriter(writer);
+    let mut writer = PointWriter::with_count(writer, Some(points.len() as u64));
+    for p in points {
+        writer.write(p)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Write points to provided writer, with heights quantized to 16-bit integers.
+///
+/// Heights take half of the space, at the cost of their precision, which is the
+/// scale of the quantization. [`Quantization::DECIMETERS`] loses at most 5 cm,
+/// which is below the accuracy of the `DEM 0050` data. Heights are decoded to `f32`
+/// when read with [`read_points`] or [`read_points_in_area`].
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_points_quantized(
+    writer: impl io::Write,
+    points: &[Point<f32>],
+    quantization: Quantization,
+) -> Result<(), io::Error> {
+    let mut writer =
+        PointWriter::with_encoding(writer, Some(points.len() as u64), Some(quantization), false);
     for p in points {
         writer.write(p)?;
     }
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Write points to provided writer, delta encoded with heights quantized to 16-bit integers.
+///
+/// Every point is stored as the difference to the previous one, with coordinates rounded
+/// to centimeters, which takes only a few bytes for points on a grid. This is the smallest
+/// encoding, but chunks have to be decoded whole. Heights are quantized like with
+/// [`write_points_quantized`].
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_points_delta(
+    writer: impl io::Write,
+    points: &[Point<f32>],
+    quantization: Quantization,
+) -> Result<(), io::Error> {
+    let mut writer =
+        PointWriter::with_encoding(writer, Some(points.len() as u64), Some(quantization), true);
+    for p in points {
+        writer.write(p)?;
+    }
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Formatting options for [`write_xyz`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct XyzFormat {
+    /// Number of decimal places of written values.
+    pub precision: usize,
+    /// Separator between values on the same line.
+    pub separator: String,
+}
+
+impl Default for XyzFormat {
+    fn default() -> Self {
+        Self {
+            precision: 2,
+            separator: " ".to_string(),
+        }
+    }
+}
+
+/// Write points to provided writer in `.xyz` text format.
+///
+/// Each point is written in its own line as `<x> <y> <height>`,
+/// formatted according to the `format`.
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_xyz(
+    mut writer: impl io::Write,
+    points: &[Point<f32>],
+    format: &XyzFormat,
+) -> Result<(), io::Error> {
+    let XyzFormat {
+        precision,
+        separator,
+    } = format;
+
+    for p in points {
+        writeln!(
+            writer,
+            "{:.precision$}{separator}{:.precision$}{separator}{:.precision$}",
+            p.x, p.y, p.data
+        )?;
+    }
 
     Ok(())
 }
 
+/// Read points from Esri ASCII GRID (`.asc`) format.
+///
+/// Each cell is converted into a point in the center of the cell,
+/// with height as data. Cells with `NODATA_value` are skipped.
+pub fn read_asc(reader: impl BufRead) -> Result<Vec<Point<f32>>, ImportError> {
+    let mut points = vec![];
+    parse_asc(reader, &mut |p| {
+        points.push(p.clone());
+        Ok(())
+    })?;
+
+    Ok(points)
+}
+
+/// Number of points between the progress reports and cancellation checks
+/// while importing a single file.
+const PROGRESS_INTERVAL: u64 = 1 << 16;
+
+/// Progress of the data import.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImportProgress {
+    /// Number of files that have been imported.
+    pub files_done: usize,
+    /// Total number of files to import.
+    pub files_total: usize,
+    /// Size of the files that have been imported, in bytes.
+    pub bytes_read: u64,
+    /// Total size of the files to import, in bytes.
+    pub bytes_total: u64,
+    /// Number of points written so far.
+    pub points_written: u64,
+}
+
+impl ImportProgress {
+    fn new(files: &[(PathBuf, u64)]) -> Self {
+        Self {
+            files_done: 0,
+            files_total: files.len(),
+            bytes_read: 0,
+            bytes_total: files.iter().map(|(_, size)| size).sum(),
+            points_written: 0,
+        }
+    }
+}
+
+/// Write points to provided writer in an indexed format.
+///
+/// Points are grouped into square tiles of `tile_size` meters, and the file
+/// ends with an index of bounding boxes of the groups. This allows
+/// [`read_points_in_area`] to read only the parts of the file it needs.
+/// Indexed files can still be read with [`read_points`].
+pub fn write_points_indexed(
+    writer: impl io::Write,
+    points: &[Point<f32>],
+    tile_size: f64,
+) -> Result<(), io::Error> {
+    format::write_indexed(writer, points, tile_size)
+}
+
+/// Read points inside the area from provided reader.
+///
+/// For files written by [`write_points_indexed`] only the relevant parts of the
+/// file are read. Other files are scanned, skipping the parts outside of the area
+/// when possible. Compressed files are not supported, since they can't be sought into.
+///
+/// If reading from file, you should wrap it into
+/// [BufReader](https://doc.rust-lang.org/std/io/struct.BufReader.html)
+/// to improve the performance.
+pub fn read_points_in_area(
+    reader: impl io::Read + io::Seek,
+    area: &Area,
+) -> Result<Vec<Point<f32>>, io::Error> {
+    format::read_in_area(reader, area)
+}
+
+/// Checks the integrity of the point file at provided path.
+///
+/// Every chunk is checked against its checksum, and its points are decoded and
+/// checked for values that are not finite. Corrupted chunks are reported with their
+/// positions, instead of failing on the first one like [`read_points`] does.
+/// Offsets of compressed files are positions in the decompressed data.
+///
+/// Error is returned if the file can't be read or it has no valid header.
+pub fn verify(path: impl AsRef<Path>) -> Result<VerifyReport, io::Error> {
+    let file = fs::File::open(path)?;
+    format::verify(codec::decoder(io::BufReader::new(file))?)
+}
+
 /// Imports raw data from provided path.
 ///
+/// Path is either a directory, which is searched recursively, or a single file.
 /// Parsed points are written to provided writer.
 /// If writing to file, you should wrap it into
 /// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
 /// to improve the performance.
 /// See [`crate`] for more info on data format.
+/// Besides `.xyz` files, Esri ASCII GRID `.asc` files are imported as well.
+///
+/// With the `geotiff` feature enabled, `.tif` rasters are imported too.
+/// With the `las` feature enabled, all points of `.las` point clouds are imported too.
+/// With the `zip` feature enabled, `.xyz` files inside of `.zip` archives
+/// are imported too, without extracting the archives.
 pub fn import_data(input_path: impl AsRef<Path>, writer: impl io::Write) -> ImportResult {
-    let mut writer = PointWriter(writer);
-    import_recursive(&input_path, &mut writer)?;
-
+    import_data_with(input_path, writer, &ImportOptions::default(), |_| ())?;
     Ok(())
 }
 
-fn import_recursive<W: io::Write>(
-    input: impl AsRef<Path>,
-    writer: &mut PointWriter<W>,
+/// Imports raw data from provided path, keeping only the points inside the area.
+///
+/// Points are filtered while parsing, so the points outside
+/// of the area are never written. Otherwise it behaves the same as [`import_data`].
+pub fn import_data_in_area(
+    input_path: impl AsRef<Path>,
+    writer: impl io::Write,
+    area: &Area,
 ) -> ImportResult {
-    let entries = fs::read_dir(input)?;
-    for entry in entries {
-        let entry = entry?;
-        let path = entry.path();
+    let options = ImportOptions {
+        area: Some(area.clone()),
+        ..Default::default()
+    };
+    import_data_with(input_path, writer, &options, |_| ())?;
+    Ok(())
+}
 
-        if path.is_dir() {
-            import_recursive(&path, writer)?;
-        } else {
-            import_file(&path, writer)?;
+/// Imports raw data from provided path with the given options.
+///
+/// Files are imported in the order of their paths. `on_progress` is called
+/// after each file and periodically while importing large files.
+/// If the import is cancelled through [`ImportOptions::cancel`],
+/// [`ImportError::Cancelled`] is returned and the output is left incomplete.
+/// Otherwise it behaves the same as [`import_data`].
+pub fn import_data_with(
+    input_path: impl AsRef<Path>,
+    writer: impl io::Write,
+    options: &ImportOptions,
+    on_progress: impl FnMut(ImportProgress),
+) -> Result<ImportReport, ImportError> {
+    let mut writer = PointWriter::new(writer);
+    let report = import_into(input_path.as_ref(), options, on_progress, |p| {
+        writer.write(p)
+    })?;
+    writer.finish()?;
+
+    Ok(report)
+}
+
+/// Imports the files sequentially and passes the points to `write`.
+fn import_into(
+    input_path: &Path,
+    options: &ImportOptions,
+    mut on_progress: impl FnMut(ImportProgress),
+    mut write: impl FnMut(&Point<f32>) -> io::Result<()>,
+) -> Result<ImportReport, ImportError> {
+    let files = collect_files(input_path)?;
+    let mut progress = ImportProgress::new(&files);
+
+    let mut dedup = Deduplicator::new(options.dedup);
+    let mut downsampler = Downsampler::new(options.downsample);
+    let mut report = ImportReport::default();
+    for (path, size) in &files {
+        options.check_cancelled()?;
+        import_file(path, options, &mut report.skipped, &mut |p| {
+            if !options.is_included(p) {
+                return Ok(());
+            }
+            if !dedup.is_new(p) {
+                report.duplicates += 1;
+                return Ok(());
+            }
+            if !downsampler.push(p) {
+                return Ok(());
+            }
+
+            write(p)?;
+            progress.points_written += 1;
+            if progress.points_written.is_multiple_of(PROGRESS_INTERVAL) {
+                options.check_cancelled()?;
+                on_progress(progress);
+            }
+            Ok(())
+        })?;
+
+        progress.files_done += 1;
+        progress.bytes_read += size;
+        on_progress(progress);
+    }
+
+    for p in downsampler.finish() {
+        write(&p)?;
+        progress.points_written += 1;
+    }
+
+    report.points = progress.points_written;
+    Ok(report)
+}
+
+/// Imports raw data from provided path, parsing multiple files in parallel.
+///
+/// Files are imported in the order of their paths, which makes the output
+/// deterministic. After each file is written, `on_progress` is called.
+/// Otherwise it behaves the same as [`import_data_with`].
+#[cfg(feature = "rayon")]
+pub fn import_data_parallel(
+    input_path: impl AsRef<Path>,
+    writer: impl io::Write,
+    options: &ImportOptions,
+    mut on_progress: impl FnMut(ImportProgress),
+) -> Result<ImportReport, ImportError> {
+    use rayon::prelude::*;
+
+    let files = collect_files(input_path.as_ref())?;
+    let mut progress = ImportProgress::new(&files);
+
+    let mut writer = PointWriter::new(writer);
+    let mut dedup = Deduplicator::new(options.dedup);
+    let mut downsampler = Downsampler::new(options.downsample);
+    let mut report = ImportReport::default();
+
+    // Files are parsed in batches, so that only a limited number
+    // of parsed files is held in memory at once.
+    let batch_size = rayon::current_num_threads() * 4;
+    for batch in files.chunks(batch_size) {
+        options.check_cancelled()?;
+        let parsed = batch
+            .par_iter()
+            .map(|(path, _)| {
+                let mut points = vec![];
+                let mut skipped = vec![];
+                import_file(path, options, &mut skipped, &mut |p| {
+                    if !options.is_included(p) {
+                        return Ok(());
+                    }
+
+                    points.push(p.clone());
+                    if (points.len() as u64).is_multiple_of(PROGRESS_INTERVAL) {
+                        options.check_cancelled()?;
+                    }
+                    Ok(())
+                })?;
+                Ok((points, skipped))
+            })
+            .collect::<Result<Vec<_>, ImportError>>()?;
+
+        for ((points, skipped), (_, size)) in parsed.into_iter().zip(batch) {
+            // Duplicates are removed while writing, so that the
+            // first occurrence is kept as in the sequential import.
+            for p in &points {
+                if !dedup.is_new(p) {
+                    report.duplicates += 1;
+                } else if downsampler.push(p) {
+                    writer.write(p)?;
+                    progress.points_written += 1;
+                }
+            }
+            report.skipped.extend(skipped);
+
+            progress.files_done += 1;
+            progress.bytes_read += size;
+            on_progress(progress);
         }
     }
 
-    Ok(())
+    for p in downsampler.finish() {
+        writer.write(&p)?;
+        progress.points_written += 1;
+    }
+    writer.finish()?;
+
+    report.points = progress.points_written;
+    Ok(report)
 }
 
-fn import_file<W: io::Write>(input: impl AsRef<Path>, writer: &mut PointWriter<W>) -> ImportResult {
-    // Ignore non .xyz files
-    let Some(ext) = input.as_ref().extension() else {
-        return Ok(());
-    };
+/// Recursively collects all the files that can be imported, together with their sizes.
+///
+/// Files are sorted by their paths. If the input is a file, it is the only one
+/// and it must be in a supported format.
+fn collect_files(input: &Path) -> io::Result<Vec<(PathBuf, u64)>> {
+    let metadata = fs::metadata(input)?;
+    if metadata.is_file() {
+        if !is_supported(input) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported format of the input file",
+            ));
+        }
+        return Ok(vec![(input.to_path_buf(), metadata.len())]);
+    }
+
+    fn collect(input: &Path, files: &mut Vec<(PathBuf, u64)>) -> io::Result<()> {
+        for entry in fs::read_dir(input)? {
+            let entry = entry?;
+            let path = entry.path();
 
-    if ext.to_str() != Some("xyz") {
-        return Ok(());
+            if path.is_dir() {
+                collect(&path, files)?;
+            } else if is_supported(&path) {
+                files.push((path, entry.metadata()?.len()));
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = vec![];
+    collect(input, &mut files)?;
+    files.sort();
+
+    Ok(files)
+}
+
+/// Returns weather the file at the path is in a supported format.
+fn is_supported(path: &Path) -> bool {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("xyz" | "asc") => true,
+        #[cfg(feature = "geotiff")]
+        Some("tif" | "tiff") => true,
+        #[cfg(feature = "las")]
+        Some("las") => true,
+        #[cfg(feature = "laz")]
+        Some("laz") => true,
+        #[cfg(feature = "zip")]
+        Some("zip") => true,
+        _ => false,
     }
+}
 
-    let file = fs::File::open(input)?;
-    let mut reader = io::BufReader::new(file);
+/// Parses the file and calls `emit` for each parsed point.
+///
+/// Malformed lines that are skipped according to the options are pushed into `skipped`.
+fn import_file<F>(
+    input: impl AsRef<Path>,
+    options: &ImportOptions,
+    skipped: &mut Vec<ImportError>,
+    emit: &mut F,
+) -> ImportResult
+where
+    F: FnMut(&Point<f32>) -> ImportResult,
+{
+    let input = input.as_ref();
+    match input.extension().and_then(|ext| ext.to_str()) {
+        Some("xyz") => {
+            let file = fs::File::open(input)?;
+            parse_xyz(io::BufReader::new(file), input, options, skipped, emit)
+        }
+        Some("asc") => {
+            let file = fs::File::open(input)?;
+            parse_asc(io::BufReader::new(file), emit)
+        }
+        #[cfg(feature = "geotiff")]
+        Some("tif" | "tiff") => {
+            let file = fs::File::open(input)?;
+            geotiff::parse_geotiff(io::BufReader::new(file), emit)
+        }
+        #[cfg(feature = "las")]
+        Some("las") => {
+            let file = fs::File::open(input)?;
+            las::parse_las(io::BufReader::new(file), None, emit)
+        }
+        // Compressed point clouds can only be decompressed with the `laz` feature.
+        #[cfg(feature = "laz")]
+        Some("laz") => {
+            let file = fs::File::open(input)?;
+            las::parse_las(io::BufReader::new(file), None, emit)
+        }
+        #[cfg(feature = "zip")]
+        Some("zip") => {
+            let file = fs::File::open(input)?;
+            import_zip(io::BufReader::new(file), input, options, skipped, emit)
+        }
+        // Ignore unsupported files
+        _ => Ok(()),
+    }
+}
 
+/// Parses all `.xyz` and `.asc` entries of the zip archive.
+///
+/// Entries are read straight from the archive, without extracting them to disk.
+/// Errors of the entries are reported with paths inside of the archive at `path`.
+#[cfg(feature = "zip")]
+fn import_zip<R, F>(
+    reader: R,
+    path: &Path,
+    options: &ImportOptions,
+    skipped: &mut Vec<ImportError>,
+    emit: &mut F,
+) -> ImportResult
+where
+    R: io::Read + io::Seek,
+    F: FnMut(&Point<f32>) -> ImportResult,
+{
+    let mut archive = zip::ZipArchive::new(reader)?;
+    for i in 0..archive.len() {
+        let entry = archive.by_index(i)?;
+        if !entry.is_file() {
+            continue;
+        }
+
+        let name = entry.name()?;
+        if name.ends_with(".xyz") {
+            let path = path.join(&*name);
+            parse_xyz(io::BufReader::new(entry), &path, options, skipped, emit)?;
+        } else if name.ends_with(".asc") {
+            parse_asc(io::BufReader::new(entry), emit)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Header of the Esri ASCII GRID format.
+struct AscHeader {
+    ncols: usize,
+    nrows: usize,
+    /// X coordinate of the center of the lower left cell.
+    x: f64,
+    /// Y coordinate of the center of the lower left cell.
+    y: f64,
+    cellsize: f64,
+    nodata: Option<f32>,
+}
+
+/// Parses points in Esri ASCII GRID format and calls `emit` for each parsed point.
+fn parse_asc<F>(mut reader: impl BufRead, emit: &mut F) -> ImportResult
+where
+    F: FnMut(&Point<f32>) -> ImportResult,
+{
+    let mut ncols = None;
+    let mut nrows = None;
+    let mut x = None;
+    let mut y = None;
+    let mut cellsize = None;
+    let mut nodata = None;
+
+    // Header consists of key value lines. The first line that doesn't
+    // start with a key is already a part of the data.
     let mut buf = String::new();
     loop {
         buf.clear();
-        let bytes = reader.read_line(&mut buf)?;
-        if bytes == 0 {
+        if reader.read_line(&mut buf)? == 0 {
             break;
         }
 
-        let mut iter = buf.split_whitespace().filter_map(|s| s.parse::<f32>().ok());
-        let arr: [_; 3] = std::array::from_fn(|_| iter.next());
+        let mut parts = buf.split_whitespace();
+        let Some(key) = parts.next() else {
+            continue;
+        };
+        if key.parse::<f64>().is_ok() {
+            break;
+        }
 
-        writer.write(&Point {
-            x: arr[0].ok_or(ImportError::InvalidData(0))?,
-            y: arr[1].ok_or(ImportError::InvalidData(1))?,
-            data: arr[2].ok_or(ImportError::InvalidData(2))?,
-        })?;
+        let value = parts
+            .next()
+            .and_then(|v| v.parse::<f64>().ok())
+            .ok_or(ImportError::InvalidGrid("invalid header value"))?;
+        match key.to_ascii_lowercase().as_str() {
+            "ncols" => ncols = Some(value as usize),
+            "nrows" => nrows = Some(value as usize),
+            "xllcorner" => x = Some((value, true)),
+            "xllcenter" => x = Some((value, false)),
+            "yllcorner" => y = Some((value, true)),
+            "yllcenter" => y = Some((value, false)),
+            "cellsize" => cellsize = Some(value),
+            "nodata_value" => nodata = Some(value as f32),
+            _ => return Err(ImportError::InvalidGrid("unknown header key")),
+        }
+    }
+
+    let cellsize = cellsize.ok_or(ImportError::InvalidGrid("missing cellsize"))?;
+    if !(cellsize > 0.0 && cellsize.is_finite()) {
+        return Err(ImportError::InvalidGrid("invalid cellsize"));
+    }
+    let to_center = |(value, is_corner): (f64, bool)| {
+        if is_corner {
+            value + cellsize / 2.0
+        } else {
+            value
+        }
+    };
+    let header = AscHeader {
+        ncols: ncols.ok_or(ImportError::InvalidGrid("missing ncols"))?,
+        nrows: nrows.ok_or(ImportError::InvalidGrid("missing nrows"))?,
+        x: x.map(to_center)
+            .ok_or(ImportError::InvalidGrid("missing xllcorner"))?,
+        y: y.map(to_center)
+            .ok_or(ImportError::InvalidGrid("missing yllcorner"))?,
+        cellsize,
+        nodata,
+    };
+
+    // Rows are ordered from north to south and can be wrapped over multiple lines.
+    // The buffer already contains the first line of data.
+    let mut idx = 0;
+    let total = header
+        .ncols
+        .checked_mul(header.nrows)
+        .ok_or(ImportError::InvalidGrid("grid too large"))?;
+    loop {
+        for value in buf.split_whitespace() {
+            if idx == total {
+                return Err(ImportError::InvalidGrid("too many values"));
+            }
+
+            let value = value
+                .parse::<f32>()
+                .map_err(|_| ImportError::InvalidGrid("invalid value"))?;
+            let (row, col) = (idx / header.ncols, idx % header.ncols);
+            idx += 1;
+
+            if header.nodata == Some(value) {
+                continue;
+            }
+
+            emit(&Point {
+                x: header.x + col as f64 * header.cellsize,
+                y: header.y + (header.nrows - 1 - row) as f64 * header.cellsize,
+                data: value,
+            })?;
+        }
+
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+    }
+
+    if idx != total {
+        return Err(ImportError::InvalidGrid("too few values"));
     }
 
     Ok(())
@@ -176,14 +903,18 @@ fn import_file<W: io::Write>(input: impl AsRef<Path>, writer: &mut PointWriter<W
 
 #[cfg(test)]
 mod tests {
-    use crate::Point;
+    use std::io::Cursor;
+
+    use crate::{Area, Point, grid::Quantization};
 
-    use super::{PointReader, PointWriter};
+    use super::{
+        ImportError, ImportOptions, OnError, PointReader, PointWriter,
+        format::{CHUNK_SIZE, FLAG_BOUNDS, Layout, MAGIC},
+    };
 
     #[test]
     fn point_read_write() {
-        let mut buf = [0u8; 4 * 3 * 2]; // 4 bytes per f32 * 3 f32 per point * 2 points
-        let mut writer = PointWriter(&mut buf[..]);
+        let mut writer = PointWriter::new(vec![]);
 
         // Write points
         let points = vec![
@@ -201,6 +932,7 @@ mod tests {
         for p in &points {
             writer.write(p).unwrap();
         }
+        let buf = writer.finish().unwrap();
 
         // Read points
         let mut reader = PointReader(&buf[..]);
@@ -208,4 +940,467 @@ mod tests {
 
         assert_eq!(points, got_points);
     }
+
+    #[test]
+    fn point_read_legacy_and_chunks() {
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point {
+                x: i as f64,
+                y: -i as f64,
+                data: 0.5,
+            })
+            .collect();
+
+        // Multiple chunks with known count.
+        let mut buf = vec![];
+        super::write_points(&mut buf, &points).unwrap();
+        assert_eq!(super::read_points(&buf[..]).unwrap(), points);
+
+        // Last chunk is missing, so count doesn't match the header.
+        let layout = Layout::CURRENT;
+        let last_chunk =
+            4 + layout.bounds_size() + (points.len() % CHUNK_SIZE) * layout.point_size();
+        buf.truncate(buf.len() - last_chunk);
+        assert!(super::read_points(&buf[..]).is_err());
+
+        // Version 1 files are raw stream of f32 points.
+        let f32_points: Vec<u8> = points[..3]
+            .iter()
+            .flat_map(|p| [p.x as f32, p.y as f32, p.data])
+            .flat_map(f32::to_le_bytes)
+            .collect();
+        assert_eq!(super::read_points(&f32_points[..]).unwrap(), points[..3]);
+
+        // Version 2 files have a header and chunks with f32 coordinates.
+        let mut v2 = MAGIC.to_vec();
+        v2.extend(2u16.to_le_bytes());
+        v2.extend(0xFEFFu16.to_le_bytes());
+        v2.extend(FLAG_BOUNDS.to_le_bytes());
+        v2.extend(3u64.to_le_bytes());
+        v2.extend((CHUNK_SIZE as u32).to_le_bytes());
+        v2.extend(0u32.to_le_bytes());
+        v2.extend(3u32.to_le_bytes());
+        v2.extend(
+            [0.0f32, -2.0, 2.0, 0.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes()),
+        );
+        v2.extend(&f32_points);
+        assert_eq!(super::read_points(&v2[..]).unwrap(), points[..3]);
+    }
+
+    #[test]
+    fn point_read_quantized() {
+        let points: Vec<_> = (0..5000)
+            .map(|i| Point {
+                x: 462000.0 + (i % 100) as f64,
+                y: 101000.0 + (i / 100) as f64,
+                data: 295.0 + i as f32 * 0.01,
+            })
+            .collect();
+
+        let mut full = vec![];
+        super::write_points(&mut full, &points).unwrap();
+        let mut quantized = vec![];
+        super::write_points_quantized(&mut quantized, &points, Quantization::DECIMETERS).unwrap();
+        assert_eq!(full.len() - quantized.len(), points.len() * 2 - 8);
+
+        let read = super::read_points(&quantized[..]).unwrap();
+        assert_eq!(read.len(), points.len());
+        for (got, want) in read.iter().zip(&points) {
+            assert_eq!((got.x, got.y), (want.x, want.y));
+            assert!((got.data - want.data).abs() <= 0.05 + 1e-4);
+        }
+
+        let area = Area::from_bounds(462000.0, 101000.0, 462009.0, 101000.0);
+        let read = super::read_points_in_area(Cursor::new(&quantized), &area).unwrap();
+        assert_eq!(read.len(), 10);
+    }
+
+    #[test]
+    fn point_read_delta() {
+        let points: Vec<_> = (0..5000)
+            .map(|i| Point {
+                x: 462000.5 + (i % 100) as f64,
+                y: 101000.0 - (i / 100) as f64 * 0.25,
+                data: 295.0 + (i % 100) as f32 * 0.1,
+            })
+            .collect();
+
+        let mut quantized = vec![];
+        super::write_points_quantized(&mut quantized, &points, Quantization::DECIMETERS).unwrap();
+        let mut delta = vec![];
+        super::write_points_delta(&mut delta, &points, Quantization::DECIMETERS).unwrap();
+        assert!(delta.len() * 4 < quantized.len());
+
+        let read = super::read_points(&delta[..]).unwrap();
+        assert_eq!(read.len(), points.len());
+        for (got, want) in read.iter().zip(&points) {
+            assert_eq!((got.x, got.y), (want.x, want.y));
+            assert!((got.data - want.data).abs() <= 0.05 + 1e-4);
+        }
+
+        let area = Area::from_bounds(462000.0, 101000.0, 462010.0, 101000.0);
+        let read = super::read_points_in_area(Cursor::new(&delta), &area).unwrap();
+        assert_eq!(read.len(), 10);
+
+        // Truncated points are invalid.
+        delta.truncate(delta.len() - 1);
+        assert!(super::read_points(&delta[..]).is_err());
+    }
+
+    #[test]
+    fn verify_reports_corrupt_chunks() {
+        let points: Vec<_> = (0..10000)
+            .map(|i| Point {
+                x: 462000.0 + (i % 100) as f64,
+                y: 101000.0 + (i / 100) as f64,
+                data: 295.0,
+            })
+            .collect();
+        let mut data = vec![];
+        super::write_points(&mut data, &points).unwrap();
+
+        let path = std::env::temp_dir().join(format!("hribovje-verify-{}", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let report = super::verify(&path).unwrap();
+        assert!(report.is_ok());
+        assert!(report.checksums);
+        assert_eq!((report.chunks, report.points), (3, 10000));
+
+        // Flip a bit in a height of the second chunk.
+        let chunk_size = 4 + 32 + 4096 * 20 + 4;
+        let second = 32 + chunk_size;
+        data[second + 36 + 16] ^= 1;
+        assert!(super::read_points(&data[..]).is_err());
+
+        // Corrupted count of the first chunk, which mustn't be trusted for allocation.
+        let mut huge_count = data.clone();
+        huge_count[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+        let err = super::read_points(&huge_count[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        std::fs::write(&path, &huge_count).unwrap();
+        let report = super::verify(&path).unwrap();
+        assert_eq!(
+            report.corrupt,
+            [super::CorruptChunk {
+                index: 0,
+                offset: 32,
+                corruption: super::Corruption::InvalidChunk,
+            }]
+        );
+
+        // Smaller count is a valid size, but the checksum doesn't match.
+        let mut small_count = data.clone();
+        small_count[32..36].copy_from_slice(&4095u32.to_le_bytes());
+        let err = super::read_points(&small_count[..]).unwrap_err();
+        assert_eq!(err.to_string(), "chunk checksum mismatch");
+
+        data.truncate(data.len() - 10);
+        std::fs::write(&path, &data).unwrap();
+        let report = super::verify(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            report.corrupt,
+            [
+                super::CorruptChunk {
+                    index: 1,
+                    offset: second as u64,
+                    corruption: super::Corruption::ChecksumMismatch,
+                },
+                super::CorruptChunk {
+                    index: 2,
+                    offset: (second + chunk_size) as u64,
+                    corruption: super::Corruption::Truncated,
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn import_parallel_is_deterministic() {
+        let dir = std::env::temp_dir().join(format!("hribovje-import-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("nested")).unwrap();
+        std::fs::write(dir.join("b.xyz"), "3 4 5\n").unwrap();
+        std::fs::write(dir.join("a.xyz"), "0 1 2\n1 2 3\n").unwrap();
+        std::fs::write(dir.join("nested/c.xyz"), "6 7 8\n").unwrap();
+        std::fs::write(dir.join("ignored.txt"), "not points\n").unwrap();
+
+        let mut buf = vec![];
+        let mut progress = vec![];
+        super::import_data_parallel(&dir, &mut buf, &Default::default(), |p| {
+            progress.push(p.files_done)
+        })
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let heights: Vec<_> = super::read_points(&buf[..])
+            .unwrap()
+            .iter()
+            .map(|p| p.data)
+            .collect();
+        assert_eq!(heights, [2.0, 3.0, 5.0, 8.0]);
+        assert_eq!(progress, [1, 2, 3]);
+    }
+
+    #[test]
+    fn import_skips_malformed_lines() {
+        let dir = std::env::temp_dir().join(format!("hribovje-skip-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.xyz"), "0 1 2\n3 4\n5 6 7\nbad\n").unwrap();
+
+        let err = super::import_data(&dir, vec![]).unwrap_err();
+        assert!(
+            matches!(
+                &err,
+                ImportError::InvalidData {
+                    line: 2,
+                    found: 2,
+                    ..
+                }
+            ),
+            "{err}"
+        );
+        assert!(err.to_string().contains("a.xyz:2"));
+
+        let options = ImportOptions {
+            on_error: OnError::Skip,
+            ..Default::default()
+        };
+        let mut buf = vec![];
+        let report = super::import_data_with(&dir, &mut buf, &options, |_| ()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.points, 2);
+        assert_eq!(super::read_points(&buf[..]).unwrap().len(), 2);
+        let lines: Vec<_> = report
+            .skipped
+            .iter()
+            .map(|e| match e {
+                ImportError::InvalidData { line, .. } => *line,
+                _ => 0,
+            })
+            .collect();
+        assert_eq!(lines, [2, 4]);
+    }
+
+    #[test]
+    fn import_progress_and_cancel() {
+        let dir = std::env::temp_dir().join(format!("hribovje-cancel-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.xyz"), "0 1 2\n1 2 3\n").unwrap();
+        std::fs::write(dir.join("b.xyz"), "3 4 5\n").unwrap();
+
+        let mut progress = vec![];
+        let report =
+            super::import_data_with(&dir, vec![], &Default::default(), |p| progress.push(p))
+                .unwrap();
+        assert_eq!(report.points, 3);
+        let last = progress.last().unwrap();
+        assert_eq!((last.files_done, last.files_total), (2, 2));
+        assert_eq!((last.bytes_read, last.bytes_total), (18, 18));
+        assert_eq!(last.points_written, 3);
+
+        // Cancel after the first file.
+        let options = ImportOptions::default();
+        let err = super::import_data_with(&dir, vec![], &options, |_| options.cancel.cancel())
+            .unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(err, ImportError::Cancelled));
+    }
+
+    #[test]
+    fn import_only_points_in_area() {
+        let dir = std::env::temp_dir().join(format!("hribovje-area-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.xyz"), "0 0 1\n5 5 2\n20 5 3\n").unwrap();
+
+        let mut out = vec![];
+        let area = crate::Area::from_bounds(-1.0, -1.0, 10.0, 10.0);
+        super::import_data_in_area(&dir, &mut out, &area).unwrap();
+
+        // Single file is imported the same as the directory.
+        let mut single = vec![];
+        super::import_data_in_area(dir.join("a.xyz"), &mut single, &area).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(single, out);
+
+        let points = super::read_points(out.as_slice()).unwrap();
+        let heights: Vec<_> = points.iter().map(|p| p.data).collect();
+        assert_eq!(heights, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn import_downsampled_grid() {
+        let dir = std::env::temp_dir().join(format!("hribovje-downsample-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.xyz"), "1 1 1\n2 2 3\n15 5 4\n").unwrap();
+
+        let options = ImportOptions {
+            downsample: super::DownsampleMode::Grid {
+                cell_size: 10.0,
+                value: super::CellValue::Mean,
+            },
+            ..Default::default()
+        };
+        let mut out = vec![];
+        let report = super::import_data_with(&dir, &mut out, &options, |_| ()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(report.points, 2);
+        let points = super::read_points(out.as_slice()).unwrap();
+        assert_eq!((points[0].x, points[0].y, points[0].data), (5.0, 5.0, 2.0));
+        assert_eq!((points[1].x, points[1].y, points[1].data), (15.0, 5.0, 4.0));
+    }
+
+    #[test]
+    fn import_drops_seam_duplicates() {
+        let dir = std::env::temp_dir().join(format!("hribovje-dedup-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.xyz"), "0 0 1\n5 0 2\n").unwrap();
+        std::fs::write(dir.join("b.xyz"), "5 0 3\n10 0 4\n").unwrap();
+
+        let options = ImportOptions {
+            dedup: super::Dedup::Exact,
+            ..Default::default()
+        };
+        let mut out = vec![];
+        let report = super::import_data_with(&dir, &mut out, &options, |_| ()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!((report.points, report.duplicates), (3, 1));
+        let points = super::read_points(out.as_slice()).unwrap();
+        // First occurrence is kept.
+        assert_eq!(points[1].data, 2.0);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn import_zip_entries() {
+        use std::io::Write;
+
+        let mut archive = zip::ZipWriter::new(std::io::Cursor::new(vec![]));
+        let options = zip::write::SimpleFileOptions::default();
+        archive.start_file("tile/a.xyz", options).unwrap();
+        archive.write_all(b"0 1 2\n3 4 5\n").unwrap();
+        archive.start_file("readme.txt", options).unwrap();
+        archive.write_all(b"not points\n").unwrap();
+        let archive = archive.finish().unwrap();
+
+        let mut points = vec![];
+        super::import_zip(
+            archive,
+            std::path::Path::new("tiles.zip"),
+            &Default::default(),
+            &mut vec![],
+            &mut |p| {
+                points.push(p.clone());
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[1].data, 5.0);
+    }
+
+    #[test]
+    fn read_asc_grid() {
+        let data = "ncols 3\nNROWS 2\nxllcorner 100\nyllcorner 200\ncellsize 10\nNODATA_value -9999\n\
+            1 2 3\n4 -9999\n6\n";
+        let points = super::read_asc(data.as_bytes()).unwrap();
+
+        let got: Vec<_> = points.iter().map(|p| (p.x, p.y, p.data)).collect();
+        assert_eq!(
+            got,
+            [
+                (105.0, 215.0, 1.0),
+                (115.0, 215.0, 2.0),
+                (125.0, 215.0, 3.0),
+                (105.0, 205.0, 4.0),
+                (125.0, 205.0, 6.0),
+            ]
+        );
+
+        let truncated = "ncols 3\nnrows 2\nxllcenter 0\nyllcenter 0\ncellsize 1\n1 2 3\n";
+        assert!(super::read_asc(truncated.as_bytes()).is_err());
+
+        let huge = "ncols 1e10\nnrows 1e10\nxllcenter 0\nyllcenter 0\ncellsize 1\n1\n";
+        let err = super::read_asc(huge.as_bytes()).unwrap_err();
+        assert!(matches!(err, ImportError::InvalidGrid("grid too large")));
+
+        let flat = "ncols 1\nnrows 1\nxllcenter 0\nyllcenter 0\ncellsize 0\n1\n";
+        let err = super::read_asc(flat.as_bytes()).unwrap_err();
+        assert!(matches!(err, ImportError::InvalidGrid("invalid cellsize")));
+    }
+
+    #[test]
+    fn write_xyz_format() {
+        let points = [
+            Point {
+                x: 1.0,
+                y: 2.5,
+                data: 300.125,
+            },
+            Point {
+                x: -4.0,
+                y: 5.0,
+                data: 6.0,
+            },
+        ];
+
+        let mut buf = vec![];
+        super::write_xyz(&mut buf, &points, &super::XyzFormat::default()).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "1.00 2.50 300.12\n-4.00 5.00 6.00\n"
+        );
+
+        let format = super::XyzFormat {
+            precision: 0,
+            separator: ";".to_string(),
+        };
+        let mut buf = vec![];
+        super::write_xyz(&mut buf, &points, &format).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "1;2;300\n-4;5;6\n");
+    }
+
+    #[test]
+    fn read_indexed_area() {
+        let points: Vec<_> = (0..100_000)
+            .map(|i| Point {
+                x: (i % 400) as f64,
+                y: (i / 400) as f64,
+                data: i as f32,
+            })
+            .collect();
+        let area = Area::square(
+            Point {
+                x: 123.0,
+                y: 45.0,
+                data: (),
+            },
+            10.0,
+        );
+        let mut expected: Vec<_> = points
+            .iter()
+            .filter(|p| area.is_point_inside(p))
+            .cloned()
+            .collect();
+        expected.sort_by(|a, b| a.data.total_cmp(&b.data));
+
+        let mut indexed = vec![];
+        super::write_points_indexed(&mut indexed, &points, 50.0).unwrap();
+        let mut chunked = vec![];
+        super::write_points(&mut chunked, &points).unwrap();
+
+        for buf in [indexed, chunked] {
+            let mut got = super::read_points_in_area(Cursor::new(&buf), &area).unwrap();
+            got.sort_by(|a, b| a.data.total_cmp(&b.data));
+            assert_eq!(got, expected);
+
+            assert_eq!(super::read_points(&buf[..]).unwrap().len(), points.len());
+        }
+    }
 }
@@ -0,0 +1,161 @@
+//! Compression of point files.
+//!
+//! Compressed files are the whole point file passed through the compressor.
+//! The compression is detected by the magic bytes of the compressed stream.
+//!
+//! Files with a header start with their own magic bytes, so they are never mistaken
+//! for compressed ones. Version 1 files start with a raw `f32` easting instead. Gzip
+//! is detected by its method and flags as well, which rules out eastings inside
+//! of Slovenia, and zstd magic decodes to a negative easting. Version 1 files
+//! with coordinates far outside of the country can still be mistaken for compressed.
+
+use std::io::{self, Read};
+
+use crate::Point;
+
+use super::format::{MAGIC, read_full};
+
+/// Gzip magic bytes, followed by the deflate compression method.
+const GZIP_MAGIC: [u8; 3] = [0x1f, 0x8b, 0x08];
+/// Reserved bits of the gzip flags, which must be zero.
+const GZIP_RESERVED_FLAGS: u8 = 0xe0;
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression codec of the point file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    /// Points are written as they are.
+    #[default]
+    Uncompressed,
+    /// Gzip compression, requires the `gzip` feature.
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard compression, requires the `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+/// Returns the codec, detected by the magic bytes at the start of the file.
+pub(super) fn detect(prefix: &[u8]) -> io::Result<Codec> {
+    if prefix.starts_with(&MAGIC) {
+        return Ok(Codec::Uncompressed);
+    }
+
+    if prefix.starts_with(&GZIP_MAGIC)
+        && prefix.get(3).is_some_and(|f| f & GZIP_RESERVED_FLAGS == 0)
+    {
+        #[cfg(feature = "gzip")]
+        return Ok(Codec::Gzip);
+        #[cfg(not(feature = "gzip"))]
+        return Err(super::format::invalid_data(
+            "point file is compressed with gzip, enable `gzip` feature",
+        ));
+    }
+
+    if prefix.starts_with(&ZSTD_MAGIC) {
+        #[cfg(feature = "zstd")]
+        return Ok(Codec::Zstd);
+        #[cfg(not(feature = "zstd"))]
+        return Err(super::format::invalid_data(
+            "point file is compressed with zstd, enable `zstd` feature",
+        ));
+    }
+
+    Ok(Codec::Uncompressed)
+}
+
+/// Wraps the reader into a decompressor, if the data is compressed.
+pub(super) fn decoder<'a>(mut reader: impl io::Read + 'a) -> io::Result<Box<dyn io::Read + 'a>> {
+    let mut prefix = [0u8; MAGIC.len()];
+    let n = read_full(&mut reader, &mut prefix)?;
+    let codec = detect(&prefix[..n])?;
+
+    // Bytes read while detecting the codec are a part of the data.
+    let reader = io::Cursor::new(prefix).take(n as u64).chain(reader);
+    let reader: Box<dyn io::Read> = match codec {
+        Codec::Uncompressed => Box::new(reader),
+        #[cfg(feature = "gzip")]
+        Codec::Gzip => Box::new(flate2::read::GzDecoder::new(reader)),
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => Box::new(zstd::Decoder::new(reader)?),
+    };
+
+    Ok(reader)
+}
+
+/// Write points to provided writer, compressed with the codec.
+///
+/// Files written with any codec can be read with [`read_points`](super::read_points).
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_points_with(
+    writer: impl io::Write,
+    points: &[Point<f32>],
+    codec: Codec,
+) -> Result<(), io::Error> {
+    match codec {
+        Codec::Uncompressed => super::write_points(writer, points),
+        #[cfg(feature = "gzip")]
+        Codec::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+            super::write_points(&mut encoder, points)?;
+            encoder.finish()?;
+            Ok(())
+        }
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => {
+            let mut encoder = zstd::Encoder::new(writer, 0)?;
+            super::write_points(&mut encoder, points)?;
+            encoder.finish()?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, data::read_points};
+
+    use super::{Codec, write_points_with};
+
+    #[test]
+    fn codecs_roundtrip() {
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point {
+                x: (i % 100) as f64,
+                y: (i / 100) as f64,
+                data: 300.0,
+            })
+            .collect();
+
+        let codecs = [
+            Codec::Uncompressed,
+            #[cfg(feature = "gzip")]
+            Codec::Gzip,
+            #[cfg(feature = "zstd")]
+            Codec::Zstd,
+        ];
+        for codec in codecs {
+            let mut buf = vec![];
+            write_points_with(&mut buf, &points, codec).unwrap();
+            if codec != Codec::Uncompressed {
+                assert!(buf.len() < points.len() * 12 / 2);
+            }
+
+            assert_eq!(read_points(&buf[..]).unwrap(), points);
+        }
+
+        // Version 1 file, whose easting starts with the gzip magic bytes.
+        let x = f32::from_le_bytes([0x1f, 0x8b, 0x08, 0x49]);
+        assert!((370_000.0..630_000.0).contains(&x));
+        let v1: Vec<u8> = [x, 100_000.0, 300.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let read = read_points(&v1[..]).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].x, x as f64);
+    }
+}
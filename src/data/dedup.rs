@@ -0,0 +1,101 @@
+//! Removal of duplicate points during the import.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::Point;
+
+use super::Dedup;
+
+/// Remembers the imported points and recognizes the duplicates.
+///
+/// The first occurrence of a point is kept.
+pub(super) enum Deduplicator {
+    Off,
+    Exact(HashSet<(u64, u64)>),
+    Tolerance {
+        tolerance: f64,
+        cell_size: f64,
+        /// Kept points in the cells of a grid. Cells are small enough,
+        /// that each of them contains at most one kept point.
+        cells: HashMap<(i64, i64), (f64, f64)>,
+    },
+}
+
+impl Deduplicator {
+    pub fn new(dedup: Dedup) -> Self {
+        match dedup {
+            Dedup::Off => Self::Off,
+            Dedup::Exact => Self::Exact(HashSet::new()),
+            Dedup::Tolerance(tolerance) if tolerance > 0.0 => Self::Tolerance {
+                tolerance,
+                cell_size: tolerance / std::f64::consts::SQRT_2,
+                cells: HashMap::new(),
+            },
+            Dedup::Tolerance(_) => Self::Exact(HashSet::new()),
+        }
+    }
+
+    /// Returns `false` if the point is a duplicate of an already seen point.
+    pub fn is_new(&mut self, point: &Point<f32>) -> bool {
+        match self {
+            Self::Off => true,
+            // Adding zero turns -0.0 into 0.0, so that both have the same bits.
+            Self::Exact(seen) => {
+                seen.insert(((point.x + 0.0).to_bits(), (point.y + 0.0).to_bits()))
+            }
+            Self::Tolerance {
+                tolerance,
+                cell_size,
+                cells,
+            } => {
+                let cell = (
+                    (point.x / *cell_size).floor() as i64,
+                    (point.y / *cell_size).floor() as i64,
+                );
+
+                // Points within tolerance are at most two cells away.
+                for dx in -2..=2 {
+                    for dy in -2..=2 {
+                        let Some(&(x, y)) = cells.get(&(cell.0 + dx, cell.1 + dy)) else {
+                            continue;
+                        };
+                        let dist_sq = (x - point.x).powi(2) + (y - point.y).powi(2);
+                        if dist_sq <= *tolerance * *tolerance {
+                            return false;
+                        }
+                    }
+                }
+
+                cells.insert(cell, (point.x, point.y));
+                true
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, data::Dedup};
+
+    use super::Deduplicator;
+
+    fn point(x: f64, y: f64) -> Point<f32> {
+        Point { x, y, data: 0.0 }
+    }
+
+    #[test]
+    fn exact_and_tolerance() {
+        let mut exact = Deduplicator::new(Dedup::Exact);
+        assert!(exact.is_new(&point(1.0, 0.0)));
+        assert!(!exact.is_new(&point(1.0, -0.0)));
+        assert!(exact.is_new(&point(1.0, 0.001)));
+
+        let mut tolerance = Deduplicator::new(Dedup::Tolerance(0.5));
+        assert!(tolerance.is_new(&point(0.0, 0.0)));
+        assert!(!tolerance.is_new(&point(0.3, -0.3)));
+        assert!(!tolerance.is_new(&point(-0.5, 0.0)));
+        assert!(tolerance.is_new(&point(0.4, 0.4)));
+        assert!(tolerance.is_new(&point(5.0, 5.0)));
+        assert!(!tolerance.is_new(&point(5.1, 5.0)));
+    }
+}
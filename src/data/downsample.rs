@@ -0,0 +1,166 @@
+//! Reduction of the number of points for coarser resolutions.
+
+use std::collections::BTreeMap;
+
+use crate::Point;
+
+/// How points are reduced by [`downsample`] or during the import.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DownsampleMode {
+    /// Keep all points.
+    #[default]
+    Off,
+    /// Keep every n-th point, starting with the first one.
+    EveryNth(u64),
+    /// Snap points to the centers of grid cells with the given size in meters.
+    ///
+    /// Each cell with at least one point produces a single point,
+    /// with the height computed as specified by `value`.
+    Grid { cell_size: f64, value: CellValue },
+}
+
+/// Height of a grid cell when downsampling with [`DownsampleMode::Grid`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CellValue {
+    /// Height of the first point in the cell.
+    #[default]
+    First,
+    Min,
+    Max,
+    Mean,
+}
+
+/// Downsamples the points to coarser resolution.
+///
+/// Grid cells are returned ordered by rows from south to north
+/// and by columns from west to east.
+pub fn downsample(points: &[Point<f32>], mode: DownsampleMode) -> Vec<Point<f32>> {
+    let mut downsampler = Downsampler::new(mode);
+    let mut res: Vec<_> = points
+        .iter()
+        .filter(|p| downsampler.push(p))
+        .cloned()
+        .collect();
+    res.extend(downsampler.finish());
+    res
+}
+
+/// Streaming downsampler.
+///
+/// Points accepted by [`push`](Self::push) are kept as they are. Grid cells are
+/// accumulated instead and their points are returned by [`finish`](Self::finish).
+pub(super) struct Downsampler {
+    mode: DownsampleMode,
+    count: u64,
+    cells: BTreeMap<(i64, i64), Cell>,
+}
+
+struct Cell {
+    value: f64,
+    count: u32,
+}
+
+impl Downsampler {
+    pub fn new(mode: DownsampleMode) -> Self {
+        Self {
+            mode,
+            count: 0,
+            cells: BTreeMap::new(),
+        }
+    }
+
+    /// Returns `true` if the point should be kept as it is.
+    pub fn push(&mut self, point: &Point<f32>) -> bool {
+        match self.mode {
+            DownsampleMode::Off => true,
+            DownsampleMode::EveryNth(n) => {
+                let keep = self.count.is_multiple_of(n.max(1));
+                self.count += 1;
+                keep
+            }
+            DownsampleMode::Grid { cell_size, value } => {
+                let key = (
+                    (point.y / cell_size).floor() as i64,
+                    (point.x / cell_size).floor() as i64,
+                );
+                let height = point.data as f64;
+                self.cells
+                    .entry(key)
+                    .and_modify(|cell| {
+                        cell.count += 1;
+                        cell.value = match value {
+                            CellValue::First => cell.value,
+                            CellValue::Min => cell.value.min(height),
+                            CellValue::Max => cell.value.max(height),
+                            CellValue::Mean => cell.value + height,
+                        };
+                    })
+                    .or_insert(Cell {
+                        value: height,
+                        count: 1,
+                    });
+                false
+            }
+        }
+    }
+
+    /// Returns the points of the accumulated grid cells.
+    pub fn finish(self) -> impl Iterator<Item = Point<f32>> {
+        let (cell_size, mean) = match self.mode {
+            DownsampleMode::Grid { cell_size, value } => (cell_size, value == CellValue::Mean),
+            _ => (0.0, false),
+        };
+
+        self.cells.into_iter().map(move |((row, col), cell)| {
+            let height = if mean {
+                cell.value / cell.count as f64
+            } else {
+                cell.value
+            };
+            Point {
+                x: (col as f64 + 0.5) * cell_size,
+                y: (row as f64 + 0.5) * cell_size,
+                data: height as f32,
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::{CellValue, DownsampleMode, downsample};
+
+    #[test]
+    fn downsample_modes() {
+        let points: Vec<_> = (0..16)
+            .map(|i| Point {
+                x: (i % 4) as f64,
+                y: (i / 4) as f64,
+                data: i as f32,
+            })
+            .collect();
+
+        let every = downsample(&points, DownsampleMode::EveryNth(5));
+        let heights: Vec<_> = every.iter().map(|p| p.data).collect();
+        assert_eq!(heights, vec![0.0, 5.0, 10.0, 15.0]);
+
+        let grid = |value| {
+            downsample(
+                &points,
+                DownsampleMode::Grid {
+                    cell_size: 2.0,
+                    value,
+                },
+            )
+        };
+        let max = grid(CellValue::Max);
+        assert_eq!(max.len(), 4);
+        assert_eq!((max[0].x, max[0].y, max[0].data), (1.0, 1.0, 5.0));
+        assert_eq!((max[1].x, max[1].y, max[1].data), (3.0, 1.0, 7.0));
+        assert_eq!(grid(CellValue::Min)[3].data, 10.0);
+        assert_eq!(grid(CellValue::Mean)[0].data, 2.5);
+        assert_eq!(grid(CellValue::First)[2].data, 8.0);
+    }
+}
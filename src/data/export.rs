@@ -0,0 +1,195 @@
+//! Export of points into formats used by GIS tools.
+
+use std::io;
+
+use crate::{Point, grid::LineString};
+
+/// Coordinates used for the GeoJSON output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GeoJsonCoordinates {
+    /// Coordinates are written in D96/TM as they are.
+    ///
+    /// Since GeoJSON specification requires WGS84, the output contains
+    /// a legacy `crs` member, which is understood by QGIS and GDAL.
+    D96Tm,
+    /// Coordinates are reprojected to WGS84 longitude and latitude.
+    #[default]
+    Wgs84,
+}
+
+/// Write points to provided writer in CSV format.
+///
+/// Output contains a header line `x,y,height`, followed by a line per point.
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_csv(mut writer: impl io::Write, points: &[Point<f32>]) -> Result<(), io::Error> {
+    writeln!(writer, "x,y,height")?;
+    for p in points {
+        writeln!(writer, "{},{},{}", p.x, p.y, p.data)?;
+    }
+
+    Ok(())
+}
+
+/// Write points to provided writer as GeoJSON `FeatureCollection`.
+///
+/// Each point is written as a `Point` feature with height as the third
+/// coordinate and as `height` property.
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_geojson(
+    mut writer: impl io::Write,
+    points: &[Point<f32>],
+    coordinates: GeoJsonCoordinates,
+) -> Result<(), io::Error> {
+    write_header(&mut writer, coordinates)?;
+
+    for (i, p) in points.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+
+        write!(
+            writer,
+            r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":"#
+        )?;
+        match coordinates {
+            GeoJsonCoordinates::D96Tm => write!(writer, "[{},{},{}]", p.x, p.y, p.data)?,
+            GeoJsonCoordinates::Wgs84 => {
+                let (lat, lon) = p.to_wgs84();
+                // 7 decimal places is about 1 cm precision.
+                write!(writer, "[{lon:.7},{lat:.7},{}]", p.data)?;
+            }
+        }
+        write!(writer, r#"}},"properties":{{"height":{}}}}}"#, p.data)?;
+    }
+
+    writeln!(writer, "]}}")?;
+    Ok(())
+}
+
+/// Write contour lines to provided writer as GeoJSON `FeatureCollection`.
+///
+/// Each line is written as a `LineString` feature with `elevation` and `closed`
+/// properties, which can be used for labeling and styling in GIS tools.
+///
+/// If writing to file, you should wrap it into
+/// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+/// to improve the performance.
+pub fn write_contours_geojson(
+    mut writer: impl io::Write,
+    lines: &[LineString],
+    coordinates: GeoJsonCoordinates,
+) -> Result<(), io::Error> {
+    write_header(&mut writer, coordinates)?;
+
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+
+        write!(
+            writer,
+            r#"{{"type":"Feature","geometry":{{"type":"LineString","coordinates":["#
+        )?;
+        for (j, p) in line.points.iter().enumerate() {
+            if j > 0 {
+                write!(writer, ",")?;
+            }
+            match coordinates {
+                GeoJsonCoordinates::D96Tm => write!(writer, "[{},{}]", p.x, p.y)?,
+                GeoJsonCoordinates::Wgs84 => {
+                    let (lat, lon) = p.to_wgs84();
+                    write!(writer, "[{lon:.7},{lat:.7}]")?;
+                }
+            }
+        }
+        write!(
+            writer,
+            r#"]}},"properties":{{"elevation":{},"closed":{}}}}}"#,
+            line.elevation,
+            line.is_closed()
+        )?;
+    }
+
+    writeln!(writer, "]}}")?;
+    Ok(())
+}
+
+/// Writes the start of the `FeatureCollection`, up to the features.
+fn write_header(
+    writer: &mut impl io::Write,
+    coordinates: GeoJsonCoordinates,
+) -> Result<(), io::Error> {
+    write!(writer, r#"{{"type":"FeatureCollection","#)?;
+    if coordinates == GeoJsonCoordinates::D96Tm {
+        write!(
+            writer,
+            r#""crs":{{"type":"name","properties":{{"name":"urn:ogc:def:crs:EPSG::3794"}}}},"#
+        )?;
+    }
+    write!(writer, r#""features":["#)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::LineString};
+
+    use super::{GeoJsonCoordinates, write_contours_geojson, write_csv, write_geojson};
+
+    #[test]
+    fn export_csv_and_geojson() {
+        let points = [Point {
+            x: 500000.0,
+            y: 95576.32,
+            data: 312.5,
+        }];
+
+        let mut buf = vec![];
+        write_csv(&mut buf, &points).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "x,y,height\n500000,95576.32,312.5\n"
+        );
+
+        let mut buf = vec![];
+        write_geojson(&mut buf, &points, GeoJsonCoordinates::Wgs84).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            r#"{"type":"FeatureCollection","features":[{"type":"Feature","geometry":{"type":"Point","coordinates":[15.0000000,46.0000000,312.5]},"properties":{"height":312.5}}]}"#
+                .to_string()
+                + "\n"
+        );
+
+        let mut buf = vec![];
+        write_geojson(&mut buf, &points, GeoJsonCoordinates::D96Tm).unwrap();
+        let geojson = String::from_utf8(buf).unwrap();
+        assert!(geojson.contains("EPSG::3794"));
+        assert!(geojson.contains("[500000,95576.32,312.5]"));
+    }
+
+    #[test]
+    fn export_contours_geojson() {
+        let line = LineString {
+            elevation: 500.0,
+            points: [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 0.0)]
+                .map(|(x, y)| Point { x, y, data: () })
+                .to_vec(),
+        };
+
+        let mut buf = vec![];
+        write_contours_geojson(&mut buf, &[line], GeoJsonCoordinates::D96Tm).unwrap();
+        let geojson = String::from_utf8(buf).unwrap();
+        assert!(geojson.contains(
+            r#""geometry":{"type":"LineString","coordinates":[[0,0],[10,0],[10,10],[0,0]]}"#
+        ));
+        assert!(geojson.contains(r#""properties":{"elevation":500,"closed":true}"#));
+
+        let parsed: serde_json::Value = serde_json::from_str(&geojson).unwrap();
+        assert_eq!(parsed["features"].as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,934 @@
+//! Binary format of point files.
+//!
+//! Version 3 files start with a header, which is followed by chunks of points.
+//! All values are little endian.
+//!
+//! ```text
+//! header (32 bytes):
+//!     magic        [u8; 8] = "HRIBPNTS"
+//!     version      u16     = 3
+//!     byte_order   u16     = 0xFEFF
+//!     flags        u32     bit 0: chunks contain bounding boxes
+//!                          bit 1: file ends with an index of chunks
+//!                          bit 2: heights are quantized
+//!                          bit 3: points are delta encoded, requires quantized heights
+//!                          bit 4: chunks end with a checksum
+//!     point_count  u64     u64::MAX if unknown at the time of writing
+//!     chunk_size   u32     maximum number of points in a chunk
+//!     reserved     u32
+//! quantization (8 bytes), only with quantized flag:
+//!     scale        f32
+//!     offset       f32
+//! chunk:
+//!     count        u32
+//!     min_x        f64     only with bounding boxes flag
+//!     min_y        f64     only with bounding boxes flag
+//!     max_x        f64     only with bounding boxes flag
+//!     max_y        f64     only with bounding boxes flag
+//!     length       u32     only with delta encoded flag, size of the points in bytes
+//!     points       count * (x: f64, y: f64, height: f32)
+//!     checksum     u32     only with checksum flag, CRC-32 of the chunk before it
+//! ```
+//!
+//! Quantized heights are stored as `u16` instead of `f32`, see [`Quantization`].
+//!
+//! Delta encoded points store the differences to the previous point in the chunk,
+//! with the first point relative to zero. Coordinates are rounded to centimeters
+//! and the differences are stored as zigzag encoded LEB128 varints, in order `x, y, height`.
+//! Grid ordered points have small and repeating differences, which take a byte or two.
+//!
+//! Checksums are the IEEE CRC-32, the same as in zip and png. They are checked
+//! whenever a chunk is read, so that corrupted files fail instead of returning wrong heights.
+//!
+//! If the file is indexed, chunks are followed by the index and the footer.
+//! Point count is always known for indexed files, so that the index is
+//! not mistaken for a chunk.
+//!
+//! ```text
+//! index entry (44 bytes), one per chunk:
+//!     offset       u64     offset of the chunk from the start of the file
+//!     count        u32
+//!     min_x        f64
+//!     min_y        f64
+//!     max_x        f64
+//!     max_y        f64
+//! footer (24 bytes):
+//!     index_offset u64
+//!     chunk_count  u64
+//!     magic        [u8; 8] = "HRIBINDX"
+//! ```
+//!
+//! Older versions are still supported for reading:
+//! - Version 2 files have the same structure, but all coordinates are stored as `f32`.
+//! - Version 1 files are a raw stream of `f32` `(x, y, height)` triples without a header.
+
+use std::io::{self, Read, SeekFrom};
+
+use super::{CorruptChunk, Corruption, VerifyReport};
+use crate::{Area, Point, grid::Quantization};
+
+pub(super) const MAGIC: [u8; 8] = *b"HRIBPNTS";
+pub(super) const VERSION: u16 = 3;
+const BYTE_ORDER: u16 = 0xFEFF;
+
+pub(super) const FLAG_BOUNDS: u32 = 1;
+pub(super) const FLAG_INDEX: u32 = 2;
+pub(super) const FLAG_QUANTIZED: u32 = 4;
+pub(super) const FLAG_DELTA: u32 = 8;
+pub(super) const FLAG_CHECKSUM: u32 = 16;
+
+/// Steps of delta encoded coordinates per meter.
+const DELTA_RESOLUTION: f64 = 100.0;
+
+/// Maximum size of a varint of a 64-bit value.
+const MAX_VARINT_SIZE: usize = 10;
+
+const CHECKSUM_SIZE: usize = 4;
+
+/// Lookup table of the reversed IEEE CRC-32 polynomial.
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+const INDEX_MAGIC: [u8; 8] = *b"HRIBINDX";
+const FOOTER_SIZE: usize = 24;
+
+pub(super) const HEADER_SIZE: usize = 32;
+const QUANTIZATION_SIZE: usize = 8;
+
+/// Default number of points in a chunk.
+pub(super) const CHUNK_SIZE: usize = 4096;
+
+/// Largest number of points in a chunk accepted when reading.
+const MAX_CHUNK_SIZE: u32 = 1 << 20;
+
+const UNKNOWN_COUNT: u64 = u64::MAX;
+
+/// Bounding box as `(min_x, min_y, max_x, max_y)`.
+type Bounds = (f64, f64, f64, f64);
+
+/// Sizes of the structures, which depend on the version of the format.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Layout {
+    /// Size of a single coordinate, 4 bytes for `f32` and 8 bytes for `f64`.
+    coord_size: usize,
+    /// Quantization of the heights, which are `f32` without it.
+    quantization: Option<Quantization>,
+    /// Whether the points are delta encoded, which requires quantization.
+    delta: bool,
+}
+
+impl Layout {
+    pub const CURRENT: Self = Self {
+        coord_size: 8,
+        quantization: None,
+        delta: false,
+    };
+
+    fn for_version(version: u16) -> io::Result<Self> {
+        match version {
+            // Version 1 has no header, but it uses the same point layout as version 2.
+            1 | 2 => Ok(Self {
+                coord_size: 4,
+                quantization: None,
+                delta: false,
+            }),
+            VERSION => Ok(Self::CURRENT),
+            _ => Err(invalid_data("unsupported point file version")),
+        }
+    }
+
+    pub fn point_size(&self) -> usize {
+        let height_size = if self.quantization.is_some() { 2 } else { 4 };
+        2 * self.coord_size + height_size
+    }
+
+    pub fn bounds_size(&self) -> usize {
+        4 * self.coord_size
+    }
+
+    fn index_entry_size(&self) -> usize {
+        8 + 4 + self.bounds_size()
+    }
+
+    fn coord_at(&self, buf: &[u8], offset: usize) -> f64 {
+        if self.coord_size == 4 {
+            f32_at(buf, offset) as f64
+        } else {
+            f64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+        }
+    }
+
+    fn decode_point(&self, buf: &[u8]) -> Point<f32> {
+        let offset = 2 * self.coord_size;
+        let data = match self.quantization {
+            Some(quantization) => {
+                quantization.decode(u16::from_le_bytes([buf[offset], buf[offset + 1]]))
+            }
+            None => f32_at(buf, offset),
+        };
+
+        Point {
+            x: self.coord_at(buf, 0),
+            y: self.coord_at(buf, self.coord_size),
+            data,
+        }
+    }
+
+    /// Encodes points of a chunk. Only the current version can be written.
+    fn encode_points(&self, points: &[Point<f32>]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(points.len() * self.point_size());
+        if let (true, Some(quantization)) = (self.delta, self.quantization) {
+            let mut previous = (0, 0, 0);
+            for point in points {
+                let current = (
+                    (point.x * DELTA_RESOLUTION).round() as i64,
+                    (point.y * DELTA_RESOLUTION).round() as i64,
+                    quantization.encode(point.data) as i64,
+                );
+                write_varint(&mut buf, current.0 - previous.0);
+                write_varint(&mut buf, current.1 - previous.1);
+                write_varint(&mut buf, current.2 - previous.2);
+                previous = current;
+            }
+            return buf;
+        }
+
+        for point in points {
+            buf.extend(point.x.to_le_bytes());
+            buf.extend(point.y.to_le_bytes());
+            match self.quantization {
+                Some(quantization) => buf.extend(quantization.encode(point.data).to_le_bytes()),
+                None => buf.extend(point.data.to_le_bytes()),
+            }
+        }
+        buf
+    }
+
+    /// Decodes `count` points of a chunk, which must take the whole buffer.
+    fn decode_points(&self, buf: &[u8], count: u32) -> io::Result<Vec<Point<f32>>> {
+        let invalid = || invalid_data("invalid points in a chunk");
+
+        let (true, Some(quantization)) = (self.delta, self.quantization) else {
+            if buf.len() != count as usize * self.point_size() {
+                return Err(invalid());
+            }
+            return Ok(buf
+                .chunks_exact(self.point_size())
+                .map(|p| self.decode_point(p))
+                .collect());
+        };
+
+        let mut points = Vec::with_capacity(count as usize);
+        let mut position = 0;
+        let (mut x, mut y, mut height) = (0i64, 0i64, 0i64);
+        let mut next = |value: i64| {
+            read_varint(buf, &mut position)
+                .and_then(|delta| value.checked_add(delta))
+                .ok_or_else(invalid)
+        };
+        for _ in 0..count {
+            x = next(x)?;
+            y = next(y)?;
+            height = next(height)?;
+
+            let height = u16::try_from(height).map_err(|_| invalid())?;
+            points.push(Point {
+                x: x as f64 / DELTA_RESOLUTION,
+                y: y as f64 / DELTA_RESOLUTION,
+                data: quantization.decode(height),
+            });
+        }
+        if position != buf.len() {
+            return Err(invalid());
+        }
+
+        Ok(points)
+    }
+
+    fn decode_bounds(&self, buf: &[u8]) -> Bounds {
+        let c = self.coord_size;
+        (
+            self.coord_at(buf, 0),
+            self.coord_at(buf, c),
+            self.coord_at(buf, 2 * c),
+            self.coord_at(buf, 3 * c),
+        )
+    }
+}
+
+/// Header of a point file.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct Header {
+    pub version: u16,
+    pub flags: u32,
+    pub point_count: Option<u64>,
+    pub chunk_size: u32,
+    pub quantization: Option<Quantization>,
+}
+
+impl Header {
+    /// Creates the header. Quantization defaults to decimeters for delta encoded points.
+    fn new(
+        mut flags: u32,
+        point_count: Option<u64>,
+        mut quantization: Option<Quantization>,
+    ) -> Self {
+        if flags & FLAG_DELTA != 0 {
+            quantization.get_or_insert(Quantization::DECIMETERS);
+        }
+        if quantization.is_some() {
+            flags |= FLAG_QUANTIZED;
+        }
+
+        Self {
+            version: VERSION,
+            // New files always have checksums.
+            flags: flags | FLAG_CHECKSUM,
+            point_count,
+            chunk_size: CHUNK_SIZE as u32,
+            quantization,
+        }
+    }
+
+    /// Returns the size of the header, including the quantization.
+    fn size(&self) -> usize {
+        if self.quantization.is_some() {
+            HEADER_SIZE + QUANTIZATION_SIZE
+        } else {
+            HEADER_SIZE
+        }
+    }
+
+    fn write(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&BYTE_ORDER.to_le_bytes())?;
+        writer.write_all(&self.flags.to_le_bytes())?;
+        writer.write_all(&self.point_count.unwrap_or(UNKNOWN_COUNT).to_le_bytes())?;
+        writer.write_all(&self.chunk_size.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+
+        if let Some(quantization) = self.quantization {
+            writer.write_all(&quantization.scale.to_le_bytes())?;
+            writer.write_all(&quantization.offset.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Parses the header and reads the quantization following it, if the flag is set.
+    fn read(buf: &[u8; HEADER_SIZE], reader: &mut impl io::Read) -> io::Result<Self> {
+        let mut header = Self::from_bytes(buf)?;
+        if header.flags & FLAG_QUANTIZED != 0 {
+            let mut buf = [0u8; QUANTIZATION_SIZE];
+            reader.read_exact(&mut buf)?;
+            header.quantization = Some(Quantization {
+                scale: f32_at(&buf, 0),
+                offset: f32_at(&buf, 4),
+            });
+        } else if header.flags & FLAG_DELTA != 0 {
+            return Err(invalid_data("delta encoded points without quantization"));
+        }
+
+        Ok(header)
+    }
+
+    /// Parses the header. Magic bytes are expected to be already checked.
+    fn from_bytes(buf: &[u8; HEADER_SIZE]) -> io::Result<Self> {
+        let version = u16::from_le_bytes([buf[8], buf[9]]);
+        Layout::for_version(version)?;
+
+        let byte_order = u16::from_le_bytes([buf[10], buf[11]]);
+        if byte_order != BYTE_ORDER {
+            return Err(invalid_data("unsupported point file byte order"));
+        }
+
+        // Chunks are read whole, so their size must be reasonable.
+        let chunk_size = u32::from_le_bytes(buf[24..28].try_into().unwrap());
+        if chunk_size > MAX_CHUNK_SIZE {
+            return Err(invalid_data("invalid point file chunk size"));
+        }
+
+        let point_count = u64::from_le_bytes(buf[16..24].try_into().unwrap());
+        Ok(Self {
+            version,
+            flags: u32::from_le_bytes(buf[12..16].try_into().unwrap()),
+            point_count: (point_count != UNKNOWN_COUNT).then_some(point_count),
+            chunk_size,
+            quantization: None,
+        })
+    }
+
+    fn layout(&self) -> Layout {
+        Layout {
+            quantization: self.quantization,
+            delta: self.flags & FLAG_DELTA != 0,
+            ..Layout::for_version(self.version).expect("version is checked when parsing")
+        }
+    }
+
+    fn chunk_header_size(&self) -> usize {
+        let mut size = 4;
+        if self.flags & FLAG_BOUNDS != 0 {
+            size += self.layout().bounds_size();
+        }
+        if self.flags & FLAG_DELTA != 0 {
+            size += 4;
+        }
+        size
+    }
+
+    fn checksum_size(&self) -> usize {
+        if self.flags & FLAG_CHECKSUM != 0 {
+            CHECKSUM_SIZE
+        } else {
+            0
+        }
+    }
+
+    /// Returns the size of the points of the chunk with the header.
+    ///
+    /// None is returned if the count or the length of the chunk is invalid,
+    /// which is checked before anything is allocated for the chunk.
+    fn points_size(&self, chunk_header: &[u8]) -> Option<usize> {
+        let count = u32::from_le_bytes(chunk_header[..4].try_into().unwrap());
+        if count == 0 || count > self.chunk_size {
+            return None;
+        }
+
+        if self.flags & FLAG_DELTA != 0 {
+            let length = &chunk_header[chunk_header.len() - 4..];
+            let length = u32::from_le_bytes(length.try_into().unwrap()) as usize;
+            (length <= 3 * MAX_VARINT_SIZE * count as usize).then_some(length)
+        } else {
+            Some(count as usize * self.layout().point_size())
+        }
+    }
+
+    /// Reads the points of the chunk after its header, and checks the checksum.
+    fn read_points(
+        &self,
+        reader: &mut impl io::Read,
+        chunk_header: &[u8],
+    ) -> io::Result<Vec<Point<f32>>> {
+        let count = u32::from_le_bytes(chunk_header[..4].try_into().unwrap());
+
+        let points_size = self
+            .points_size(chunk_header)
+            .ok_or_else(|| invalid_data("invalid size of a chunk"))?;
+        let mut buf = vec![0u8; points_size + self.checksum_size()];
+        reader.read_exact(&mut buf)?;
+        if !self.checksum_matches(chunk_header, &buf) {
+            return Err(invalid_data("chunk checksum mismatch"));
+        }
+
+        self.layout().decode_points(&buf[..points_size], count)
+    }
+
+    /// Checks the checksum at the end of the chunk body, which follows the chunk header.
+    /// Chunks without checksums always match.
+    fn checksum_matches(&self, chunk_header: &[u8], body: &[u8]) -> bool {
+        if self.flags & FLAG_CHECKSUM == 0 {
+            return true;
+        }
+
+        let (points, checksum) = body.split_at(body.len() - CHECKSUM_SIZE);
+        crc32(&[chunk_header, points]) == u32::from_le_bytes(checksum.try_into().unwrap())
+    }
+}
+
+pub(super) fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Writes points in chunks.
+///
+/// Points are buffered until the chunk is full, which means that
+/// [`PointWriter::finish`] has to be called to write the last chunk.
+pub(super) struct PointWriter<W: io::Write> {
+    writer: W,
+    header: Option<Header>,
+    layout: Layout,
+    chunk: Vec<Point<f32>>,
+}
+
+impl<W: io::Write> PointWriter<W> {
+    /// Creates a writer for unknown number of points.
+    pub fn new(writer: W) -> Self {
+        Self::with_count(writer, None)
+    }
+
+    pub fn with_count(writer: W, point_count: Option<u64>) -> Self {
+        Self::with_encoding(writer, point_count, None, false)
+    }
+
+    /// Creates a writer, which quantizes the heights if the quantization is given,
+    /// and delta encodes the points if `delta` is set.
+    pub fn with_encoding(
+        writer: W,
+        point_count: Option<u64>,
+        quantization: Option<Quantization>,
+        delta: bool,
+    ) -> Self {
+        let flags = if delta {
+            FLAG_BOUNDS | FLAG_DELTA
+        } else {
+            FLAG_BOUNDS
+        };
+        let header = Header::new(flags, point_count, quantization);
+        Self {
+            writer,
+            layout: header.layout(),
+            header: Some(header),
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+        }
+    }
+
+    pub fn write(&mut self, point: &Point<f32>) -> Result<(), io::Error> {
+        self.chunk.push(point.clone());
+        if self.chunk.len() == CHUNK_SIZE {
+            self.flush_chunk()?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the remaining buffered points and returns the underlying writer.
+    pub fn finish(mut self) -> Result<W, io::Error> {
+        self.flush_chunk()?;
+        Ok(self.writer)
+    }
+
+    fn flush_chunk(&mut self) -> Result<(), io::Error> {
+        // Header is written lazily, so that creating the writer can't fail.
+        if let Some(header) = self.header.take() {
+            header.write(&mut self.writer)?;
+        }
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+
+        write_chunk(&mut self.writer, &self.chunk, &self.layout)?;
+        self.chunk.clear();
+
+        Ok(())
+    }
+}
+
+/// Writes a chunk with bounding box and returns the bounding box and the size of the chunk.
+fn write_chunk(
+    writer: &mut impl io::Write,
+    points: &[Point<f32>],
+    layout: &Layout,
+) -> io::Result<(Bounds, usize)> {
+    let bounds = bounds(points);
+    let (min_x, min_y, max_x, max_y) = bounds;
+
+    let encoded = layout.encode_points(points);
+    let mut chunk = Vec::with_capacity(8 + layout.bounds_size() + encoded.len() + CHECKSUM_SIZE);
+    chunk.extend((points.len() as u32).to_le_bytes());
+    for v in [min_x, min_y, max_x, max_y] {
+        chunk.extend(v.to_le_bytes());
+    }
+    if layout.delta {
+        chunk.extend((encoded.len() as u32).to_le_bytes());
+    }
+    chunk.extend(encoded);
+
+    // Files are written only with checksums.
+    let checksum = crc32(&[&chunk]);
+    chunk.extend(checksum.to_le_bytes());
+    writer.write_all(&chunk)?;
+
+    Ok((bounds, chunk.len()))
+}
+
+/// Writes an indexed file, with points grouped into square tiles.
+///
+/// Every chunk contains points of a single tile, so that the chunk bounding
+/// boxes are small and reading an area only needs to touch a few chunks.
+pub(super) fn write_indexed(
+    mut writer: impl io::Write,
+    points: &[Point<f32>],
+    tile_size: f64,
+) -> io::Result<()> {
+    let tile = |p: &Point<f32>| {
+        (
+            (p.y / tile_size).floor() as i64,
+            (p.x / tile_size).floor() as i64,
+        )
+    };
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(tile);
+
+    let header = Header::new(FLAG_BOUNDS | FLAG_INDEX, Some(points.len() as u64), None);
+    header.write(&mut writer)?;
+
+    let layout = header.layout();
+    let mut offset = header.size() as u64;
+    let mut index = vec![];
+    for tile_points in sorted.chunk_by(|a, b| tile(a) == tile(b)) {
+        for chunk in tile_points.chunks(CHUNK_SIZE) {
+            let (bounds, size) = write_chunk(&mut writer, chunk, &layout)?;
+            index.push((offset, chunk.len() as u32, bounds));
+            offset += size as u64;
+        }
+    }
+
+    for (chunk_offset, count, (min_x, min_y, max_x, max_y)) in &index {
+        writer.write_all(&chunk_offset.to_le_bytes())?;
+        writer.write_all(&count.to_le_bytes())?;
+        for v in [min_x, min_y, max_x, max_y] {
+            writer.write_all(&v.to_le_bytes())?;
+        }
+    }
+
+    writer.write_all(&offset.to_le_bytes())?;
+    writer.write_all(&(index.len() as u64).to_le_bytes())?;
+    writer.write_all(&INDEX_MAGIC)?;
+
+    Ok(())
+}
+
+/// Returns weather the bounding box intersects the area.
+fn bounds_intersect(bounds: Bounds, area: &Area) -> bool {
+    let (min_x, min_y, max_x, max_y) = bounds;
+    min_x <= area.max_x() && max_x >= area.min_x() && min_y <= area.max_y() && max_y >= area.min_y()
+}
+
+/// Returns the bounding box of the points.
+fn bounds(points: &[Point<f32>]) -> Bounds {
+    points.iter().fold(
+        (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+        |(min_x, min_y, max_x, max_y), p| {
+            (
+                min_x.min(p.x),
+                min_y.min(p.y),
+                max_x.max(p.x),
+                max_y.max(p.y),
+            )
+        },
+    )
+}
+
+pub(super) struct PointReader<R: io::Read>(pub R);
+
+impl<R: io::Read> PointReader<R> {
+    /// Reads all points, detecting the version of the format.
+    pub fn read(&mut self) -> Result<Vec<Point<f32>>, io::Error> {
+        let mut magic = [0u8; 8];
+        let n = read_full(&mut self.0, &mut magic)?;
+
+        if n == magic.len() && magic == MAGIC {
+            let mut buf = [0u8; HEADER_SIZE];
+            buf[..8].copy_from_slice(&magic);
+            self.0.read_exact(&mut buf[8..])?;
+            let header = Header::read(&buf, &mut self.0)?;
+
+            read_chunks(&mut self.0, &header)
+        } else {
+            // Bytes read while checking for the header are a part of the data.
+            read_raw(magic[..n].chain(&mut self.0))
+        }
+    }
+}
+
+/// Reads points inside the area.
+///
+/// Indexed files only read chunks that intersect the area. For other files
+/// with a header, chunks outside of the area are skipped. Version 1 files are read whole.
+pub(super) fn read_in_area<R: io::Read + io::Seek>(
+    mut reader: R,
+    area: &Area,
+) -> io::Result<Vec<Point<f32>>> {
+    let mut buf = [0u8; HEADER_SIZE];
+    let n = read_full(&mut reader, &mut buf)?;
+    if super::codec::detect(&buf[..n])? != super::codec::Codec::Uncompressed {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "compressed point files can't be read by area",
+        ));
+    }
+    if n < MAGIC.len() || buf[..8] != MAGIC {
+        reader.seek(SeekFrom::Start(0))?;
+        let mut points = read_raw(reader)?;
+        points.retain(|p| area.is_point_inside(p));
+        return Ok(points);
+    }
+    if n < HEADER_SIZE {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let header = Header::read(&buf, &mut reader)?;
+    let layout = header.layout();
+
+    let mut points = vec![];
+    let mut chunk_header = vec![0u8; header.chunk_header_size()];
+    let mut read_chunk_points = |reader: &mut R, chunk_header: &[u8]| -> io::Result<()> {
+        let chunk = header.read_points(reader, chunk_header)?;
+        points.extend(chunk.into_iter().filter(|p| area.is_point_inside(p)));
+        Ok(())
+    };
+
+    if header.flags & FLAG_INDEX != 0 {
+        reader.seek(SeekFrom::End(-(FOOTER_SIZE as i64)))?;
+        let mut footer = [0u8; FOOTER_SIZE];
+        reader.read_exact(&mut footer)?;
+        if footer[16..] != INDEX_MAGIC {
+            return Err(invalid_data("invalid point file index"));
+        }
+        let index_offset = u64::from_le_bytes(footer[..8].try_into().unwrap());
+        let chunk_count = u64::from_le_bytes(footer[8..16].try_into().unwrap());
+
+        // Index is read whole, before seeking to the chunks.
+        reader.seek(SeekFrom::Start(index_offset))?;
+        let mut chunks = vec![];
+        let mut entry = vec![0u8; layout.index_entry_size()];
+        for _ in 0..chunk_count {
+            reader.read_exact(&mut entry)?;
+            if bounds_intersect(layout.decode_bounds(&entry[12..]), area) {
+                chunks.push(u64::from_le_bytes(entry[..8].try_into().unwrap()));
+            }
+        }
+
+        for offset in chunks {
+            reader.seek(SeekFrom::Start(offset))?;
+            reader.read_exact(&mut chunk_header)?;
+            read_chunk_points(&mut reader, &chunk_header)?;
+        }
+
+        return Ok(points);
+    }
+
+    let has_bounds = header.flags & FLAG_BOUNDS != 0;
+    loop {
+        match read_full(&mut reader, &mut chunk_header[..4])? {
+            0 => break,
+            4 => (),
+            _ => return Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+
+        reader.read_exact(&mut chunk_header[4..])?;
+        if has_bounds && !bounds_intersect(layout.decode_bounds(&chunk_header[4..]), area) {
+            let skip = header
+                .points_size(&chunk_header)
+                .ok_or_else(|| invalid_data("invalid size of a chunk"))?
+                + header.checksum_size();
+            reader.seek(SeekFrom::Current(skip as i64))?;
+            continue;
+        }
+
+        read_chunk_points(&mut reader, &chunk_header)?;
+    }
+
+    Ok(points)
+}
+
+/// Computes the IEEE CRC-32 of the concatenated parts.
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = !0u32;
+    for part in parts {
+        for &byte in *part {
+            crc = CRC32_TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8);
+        }
+    }
+    !crc
+}
+
+/// Checks all chunks of a file with a header.
+///
+/// Chunks with valid structure, but wrong checksum or points, are reported and skipped.
+/// Chunks with invalid count or length end the check, since the start of the next
+/// chunk is unknown.
+pub(super) fn verify(mut reader: impl io::Read) -> io::Result<VerifyReport> {
+    let mut buf = [0u8; HEADER_SIZE];
+    if read_full(&mut reader, &mut buf)? < HEADER_SIZE || buf[..8] != MAGIC {
+        return Err(invalid_data(
+            "only point files with a header can be verified",
+        ));
+    }
+    let header = Header::read(&buf, &mut reader)?;
+    let layout = header.layout();
+
+    let mut report = VerifyReport {
+        checksums: header.flags & FLAG_CHECKSUM != 0,
+        ..Default::default()
+    };
+    let mut offset = header.size() as u64;
+    let mut chunk_header = vec![0u8; header.chunk_header_size()];
+    loop {
+        // Indexed files have the index after the last chunk.
+        if header.flags & FLAG_INDEX != 0 && header.point_count == Some(report.points) {
+            break;
+        }
+
+        let corrupt = |corruption| CorruptChunk {
+            index: report.chunks,
+            offset,
+            corruption,
+        };
+
+        let n = read_full(&mut reader, &mut chunk_header)?;
+        if n == 0 {
+            break;
+        } else if n < chunk_header.len() {
+            report.corrupt.push(corrupt(Corruption::Truncated));
+            return Ok(report);
+        }
+
+        let count = u32::from_le_bytes(chunk_header[..4].try_into().unwrap());
+        let Some(points_size) = header.points_size(&chunk_header) else {
+            report.corrupt.push(corrupt(Corruption::InvalidChunk));
+            return Ok(report);
+        };
+
+        let mut body = vec![0u8; points_size + header.checksum_size()];
+        if read_full(&mut reader, &mut body)? < body.len() {
+            report.corrupt.push(corrupt(Corruption::Truncated));
+            return Ok(report);
+        }
+
+        if !header.checksum_matches(&chunk_header, &body) {
+            report.corrupt.push(corrupt(Corruption::ChecksumMismatch));
+        } else {
+            let points = layout.decode_points(&body[..points_size], count);
+            let valid = points.is_ok_and(|points| {
+                points
+                    .iter()
+                    .all(|p| p.x.is_finite() && p.y.is_finite() && p.data.is_finite())
+            });
+            if !valid {
+                report.corrupt.push(corrupt(Corruption::InvalidPoints));
+            }
+        }
+
+        report.chunks += 1;
+        report.points += count as u64;
+        offset += (chunk_header.len() + body.len()) as u64;
+    }
+
+    if header
+        .point_count
+        .is_some_and(|count| count > report.points)
+    {
+        report.corrupt.push(CorruptChunk {
+            index: report.chunks,
+            offset,
+            corruption: Corruption::Truncated,
+        });
+    }
+
+    Ok(report)
+}
+
+/// Writes the value as a zigzag encoded LEB128 varint.
+fn write_varint(buf: &mut Vec<u8>, value: i64) {
+    let mut value = ((value << 1) ^ (value >> 63)) as u64;
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Reads a zigzag encoded LEB128 varint at the position, advancing it.
+/// None is returned if the varint is truncated or too long.
+fn read_varint(buf: &[u8], position: &mut usize) -> Option<i64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*position)?;
+        *position += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some((value >> 1) as i64 ^ -((value & 1) as i64));
+        }
+    }
+
+    None
+}
+
+fn f32_at(buf: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+/// Reads as many bytes as possible into the buffer, returning the number of bytes read.
+pub(super) fn read_full(reader: &mut impl io::Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(read) => n += read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(n)
+}
+
+/// Reads chunks of a file with a header.
+fn read_chunks(reader: &mut impl io::Read, header: &Header) -> io::Result<Vec<Point<f32>>> {
+    let mut points = vec![];
+
+    let mut buf = vec![0u8; header.chunk_header_size()];
+    loop {
+        // Indexed files have the index after the last chunk.
+        if header.flags & FLAG_INDEX != 0 && header.point_count == Some(points.len() as u64) {
+            break;
+        }
+
+        // EOF at the chunk boundary is the end of the file.
+        match read_full(reader, &mut buf[..4])? {
+            0 => break,
+            4 => (),
+            _ => return Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+        reader.read_exact(&mut buf[4..])?;
+        points.extend(header.read_points(reader, &buf)?);
+    }
+
+    if header
+        .point_count
+        .is_some_and(|count| count != points.len() as u64)
+    {
+        return Err(invalid_data("point count doesn't match the header"));
+    }
+
+    Ok(points)
+}
+
+/// Reads a version 1 file, which is a raw stream of points.
+fn read_raw(mut reader: impl io::Read) -> io::Result<Vec<Point<f32>>> {
+    let layout = Layout::for_version(1)?;
+    let mut points = vec![];
+    let mut buf = vec![0u8; layout.point_size()];
+
+    loop {
+        match reader.read_exact(&mut buf) {
+            Ok(_) => points.push(layout.decode_point(&buf)),
+
+            // We reached EOF and can break
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            // Handle other errors
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(points)
+}
@@ -0,0 +1,146 @@
+//! Import of raster DEMs in GeoTIFF format.
+
+use std::io;
+
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    tags::Tag,
+};
+
+use crate::Point;
+
+use super::{ImportError, ImportResult, PointWriter};
+
+/// Imports a single band GeoTIFF raster from provided reader.
+///
+/// Each pixel is converted into a point in the center of the pixel, with
+/// height as data. Pixels with GDAL `NODATA` value are skipped.
+/// Parsed points are written to provided writer.
+///
+/// Coordinates are taken from the raster georeferencing as they are, no
+/// reprojection is done. Make sure the raster is in D96/TM, if you want
+/// to mix it with `DEM 0050` data.
+pub fn import_geotiff(reader: impl io::Read + io::Seek, writer: impl io::Write) -> ImportResult {
+    let mut writer = PointWriter::new(writer);
+    parse_geotiff(reader, &mut |p| Ok(writer.write(p)?))?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Parses the GeoTIFF raster and calls `emit` for each parsed point.
+pub(super) fn parse_geotiff<R, F>(reader: R, emit: &mut F) -> ImportResult
+where
+    R: io::Read + io::Seek,
+    F: FnMut(&Point<f32>) -> ImportResult,
+{
+    let mut decoder = Decoder::new(reader)?;
+    let (width, height) = decoder.dimensions()?;
+
+    let scale = decoder
+        .find_tag(Tag::ModelPixelScaleTag)?
+        .ok_or(ImportError::InvalidGeoTiff("missing pixel scale"))?
+        .into_f64_vec()?;
+    let tiepoint = decoder
+        .find_tag(Tag::ModelTiepointTag)?
+        .ok_or(ImportError::InvalidGeoTiff("missing tiepoint"))?
+        .into_f64_vec()?;
+    if scale.len() < 2 || tiepoint.len() < 6 {
+        return Err(ImportError::InvalidGeoTiff("invalid georeferencing"));
+    }
+
+    let nodata = decoder
+        .find_tag(Tag::GdalNodata)?
+        .map(|v| v.into_string())
+        .transpose()?
+        .and_then(|v| {
+            v.trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                .parse()
+                .ok()
+        });
+
+    let values = to_f32(decoder.read_image()?);
+    if values.len() != width as usize * height as usize {
+        return Err(ImportError::InvalidGeoTiff(
+            "only single band rasters are supported",
+        ));
+    }
+
+    // Tiepoint maps raster position (i, j) to model position (x, y).
+    // Pixels are areas, so their centers are offset by half a pixel.
+    let (i, j, x, y) = (tiepoint[0], tiepoint[1], tiepoint[3], tiepoint[4]);
+    for (idx, value) in values.into_iter().enumerate() {
+        if nodata == Some(value) || value.is_nan() {
+            continue;
+        }
+
+        let col = (idx % width as usize) as f64;
+        let row = (idx / width as usize) as f64;
+        emit(&Point {
+            x: x + (col - i + 0.5) * scale[0],
+            y: y - (row - j + 0.5) * scale[1],
+            data: value,
+        })?;
+    }
+
+    Ok(())
+}
+
+fn to_f32(result: DecodingResult) -> Vec<f32> {
+    match result {
+        DecodingResult::U8(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::U32(v) => v.into_iter().map(|v| v as f32).collect(),
+        DecodingResult::U64(v) => v.into_iter().map(|v| v as f32).collect(),
+        DecodingResult::F16(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::F32(v) => v,
+        DecodingResult::F64(v) => v.into_iter().map(|v| v as f32).collect(),
+        DecodingResult::I8(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::I16(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::I32(v) => v.into_iter().map(|v| v as f32).collect(),
+        DecodingResult::I64(v) => v.into_iter().map(|v| v as f32).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use tiff::{encoder::TiffEncoder, encoder::colortype::Gray32Float, tags::Tag};
+
+    use crate::data::read_points;
+
+    #[test]
+    fn import_geotiff_pixels() {
+        let mut buf = Cursor::new(vec![]);
+        let mut encoder = TiffEncoder::new(&mut buf).unwrap();
+        let mut image = encoder.new_image::<Gray32Float>(2, 2).unwrap();
+        image
+            .encoder()
+            .write_tag(Tag::ModelPixelScaleTag, &[10.0f64, 10.0, 0.0][..])
+            .unwrap();
+        image
+            .encoder()
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0f64, 0.0, 0.0, 1000.0, 2000.0, 0.0][..],
+            )
+            .unwrap();
+        image.encoder().write_tag(Tag::GdalNodata, "-9999").unwrap();
+        image.write_data(&[1.0, 2.0, -9999.0, 4.0]).unwrap();
+
+        let mut out = vec![];
+        super::import_geotiff(Cursor::new(buf.into_inner()), &mut out).unwrap();
+        let points = read_points(&out[..]).unwrap();
+
+        let got: Vec<_> = points.iter().map(|p| (p.x, p.y, p.data)).collect();
+        assert_eq!(
+            got,
+            [
+                (1005.0, 1995.0, 1.0),
+                (1015.0, 1995.0, 2.0),
+                (1015.0, 1985.0, 4.0),
+            ]
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Export of contour lines into GeoPackage.
+//!
+//! [GeoPackage](https://www.geopackage.org/) is a SQLite database with
+//! vector layers, which is the default vector format of QGIS.
+
+use std::{io, path::Path};
+
+use rusqlite::{Connection, params};
+use thiserror::Error;
+
+use crate::grid::LineString;
+
+/// `GPKG` in ASCII, which identifies the SQLite file as GeoPackage.
+const APPLICATION_ID: i32 = 0x4750_4B47;
+
+/// GeoPackage version 1.3.0.
+const USER_VERSION: i32 = 10300;
+
+const SRS_ID: i32 = 3794;
+
+const D96_TM_WKT: &str = concat!(
+    r#"PROJCS["Slovenia 1996 / Slovene National Grid","#,
+    r#"GEOGCS["Slovenia 1996",DATUM["Slovenia_Geodetic_Datum_1996","#,
+    r#"SPHEROID["GRS 1980",6378137,298.257222101]],PRIMEM["Greenwich",0],"#,
+    r#"UNIT["degree",0.0174532925199433]],PROJECTION["Transverse_Mercator"],"#,
+    r#"PARAMETER["latitude_of_origin",0],PARAMETER["central_meridian",15],"#,
+    r#"PARAMETER["scale_factor",0.9999],PARAMETER["false_easting",500000],"#,
+    r#"PARAMETER["false_northing",-5000000],UNIT["metre",1],AUTHORITY["EPSG","3794"]]"#
+);
+
+const WGS84_WKT: &str = concat!(
+    r#"GEOGCS["WGS 84",DATUM["WGS_1984",SPHEROID["WGS 84",6378137,298.257223563]],"#,
+    r#"PRIMEM["Greenwich",0],UNIT["degree",0.0174532925199433],AUTHORITY["EPSG","4326"]]"#
+);
+
+/// The error type that can occur when writing GeoPackage.
+#[derive(Debug, Error)]
+pub enum GpkgError {
+    #[error("io error")]
+    Io(#[from] io::Error),
+
+    #[error("sqlite error")]
+    Sqlite(#[from] rusqlite::Error),
+}
+
+/// Writes contour lines into a new GeoPackage with a single `table` layer.
+///
+/// Lines are stored in D96/TM, with `elevation` and `closed` attributes.
+/// Existing file at the path is replaced.
+pub fn write_contours_gpkg(
+    path: impl AsRef<Path>,
+    table: &str,
+    lines: &[LineString],
+) -> Result<(), GpkgError> {
+    let path = path.as_ref();
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+
+    let mut conn = Connection::open(path)?;
+    conn.pragma_update(None, "application_id", APPLICATION_ID)?;
+    conn.pragma_update(None, "user_version", USER_VERSION)?;
+
+    let tx = conn.transaction()?;
+    create_metadata(&tx)?;
+
+    let table = table.replace('"', "\"\"");
+    tx.execute_batch(&format!(
+        r#"CREATE TABLE "{table}" (
+            fid INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+            geom LINESTRING,
+            elevation DOUBLE NOT NULL,
+            closed BOOLEAN NOT NULL
+        );"#
+    ))?;
+
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f64::MAX, f64::MAX, f64::MIN, f64::MIN);
+    {
+        let mut insert = tx.prepare(&format!(
+            r#"INSERT INTO "{table}" (geom, elevation, closed) VALUES (?1, ?2, ?3)"#
+        ))?;
+        for line in lines {
+            let (geometry, envelope) = encode_line(line);
+            min_x = min_x.min(envelope[0]);
+            max_x = max_x.max(envelope[1]);
+            min_y = min_y.min(envelope[2]);
+            max_y = max_y.max(envelope[3]);
+            insert.execute(params![geometry, line.elevation, line.is_closed()])?;
+        }
+    }
+
+    let bounds = (!lines.is_empty()).then_some((min_x, min_y, max_x, max_y));
+    tx.execute(
+        "INSERT INTO gpkg_contents (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id)
+         VALUES (?1, 'features', ?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            table,
+            bounds.map(|b| b.0),
+            bounds.map(|b| b.1),
+            bounds.map(|b| b.2),
+            bounds.map(|b| b.3),
+            SRS_ID
+        ],
+    )?;
+    tx.execute(
+        "INSERT INTO gpkg_geometry_columns VALUES (?1, 'geom', 'LINESTRING', ?2, 0, 0)",
+        params![table, SRS_ID],
+    )?;
+
+    tx.commit()?;
+    Ok(())
+}
+
+/// Creates the tables required by the specification.
+fn create_metadata(conn: &Connection) -> Result<(), rusqlite::Error> {
+    conn.execute_batch(
+        "CREATE TABLE gpkg_spatial_ref_sys (
+            srs_name TEXT NOT NULL,
+            srs_id INTEGER PRIMARY KEY,
+            organization TEXT NOT NULL,
+            organization_coordsys_id INTEGER NOT NULL,
+            definition TEXT NOT NULL,
+            description TEXT
+        );
+        CREATE TABLE gpkg_contents (
+            table_name TEXT NOT NULL PRIMARY KEY,
+            data_type TEXT NOT NULL,
+            identifier TEXT UNIQUE,
+            description TEXT DEFAULT '',
+            last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+            min_x DOUBLE,
+            min_y DOUBLE,
+            max_x DOUBLE,
+            max_y DOUBLE,
+            srs_id INTEGER,
+            CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+        );
+        CREATE TABLE gpkg_geometry_columns (
+            table_name TEXT NOT NULL,
+            column_name TEXT NOT NULL,
+            geometry_type_name TEXT NOT NULL,
+            srs_id INTEGER NOT NULL,
+            z TINYINT NOT NULL,
+            m TINYINT NOT NULL,
+            CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name),
+            CONSTRAINT fk_gc_tn FOREIGN KEY (table_name) REFERENCES gpkg_contents(table_name),
+            CONSTRAINT fk_gc_srs FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys (srs_id)
+        );",
+    )?;
+
+    let mut insert =
+        conn.prepare("INSERT INTO gpkg_spatial_ref_sys VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+    insert.execute(params![
+        "Undefined cartesian SRS",
+        -1,
+        "NONE",
+        -1,
+        "undefined",
+        None::<&str>
+    ])?;
+    insert.execute(params![
+        "Undefined geographic SRS",
+        0,
+        "NONE",
+        0,
+        "undefined",
+        None::<&str>
+    ])?;
+    insert.execute(params![
+        "WGS 84",
+        4326,
+        "EPSG",
+        4326,
+        WGS84_WKT,
+        None::<&str>
+    ])?;
+    insert.execute(params![
+        "Slovenia 1996 / Slovene National Grid",
+        SRS_ID,
+        "EPSG",
+        SRS_ID,
+        D96_TM_WKT,
+        None::<&str>
+    ])?;
+
+    Ok(())
+}
+
+/// Encodes the line as GeoPackage binary geometry and returns it
+/// together with its envelope `[min_x, max_x, min_y, max_y]`.
+fn encode_line(line: &LineString) -> (Vec<u8>, [f64; 4]) {
+    let mut envelope = [f64::MAX, f64::MIN, f64::MAX, f64::MIN];
+    for p in &line.points {
+        envelope = [
+            envelope[0].min(p.x),
+            envelope[1].max(p.x),
+            envelope[2].min(p.y),
+            envelope[3].max(p.y),
+        ];
+    }
+
+    let mut buf = Vec::with_capacity(8 + 32 + 9 + line.points.len() * 16);
+    // Magic, version 0, and flags for little endian with an XY envelope.
+    buf.extend([b'G', b'P', 0, 0b0000_0011]);
+    buf.extend(SRS_ID.to_le_bytes());
+    for v in envelope {
+        buf.extend(v.to_le_bytes());
+    }
+
+    // Well-known binary line string.
+    buf.push(1);
+    buf.extend(2u32.to_le_bytes());
+    buf.extend((line.points.len() as u32).to_le_bytes());
+    for p in &line.points {
+        buf.extend(p.x.to_le_bytes());
+        buf.extend(p.y.to_le_bytes());
+    }
+
+    (buf, envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use rusqlite::Connection;
+
+    use crate::{Point, grid::LineString};
+
+    use super::write_contours_gpkg;
+
+    #[test]
+    fn gpkg_contours() {
+        let path = std::env::temp_dir().join(format!("hribovje-{}.gpkg", std::process::id()));
+        let lines = [100.0, 150.0].map(|elevation| LineString {
+            elevation,
+            points: [(0.0, 0.0), (10.0, 5.0)]
+                .map(|(x, y)| Point { x, y, data: () })
+                .to_vec(),
+        });
+        write_contours_gpkg(&path, "contours", &lines).unwrap();
+
+        let conn = Connection::open(&path).unwrap();
+        let application_id: i32 = conn
+            .pragma_query_value(None, "application_id", |r| r.get(0))
+            .unwrap();
+        assert_eq!(application_id, 0x4750_4B47);
+
+        let (elevation, geom): (f64, Vec<u8>) = conn
+            .query_row(
+                "SELECT elevation, geom FROM contours ORDER BY fid DESC",
+                [],
+                |r| Ok((r.get(0)?, r.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(elevation, 150.0);
+        assert_eq!(&geom[..2], b"GP");
+        assert_eq!(geom.len(), 8 + 32 + 9 + 2 * 16);
+
+        let max_x: f64 = conn
+            .query_row("SELECT max_x FROM gpkg_contents", [], |r| r.get(0))
+            .unwrap();
+        drop(conn);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(max_x, 10.0);
+    }
+}
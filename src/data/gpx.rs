@@ -0,0 +1,93 @@
+//! Import of GPS tracks in GPX format.
+
+use std::io;
+
+use crate::Point;
+
+use super::ImportError;
+
+/// Reads track points from the GPX file.
+///
+/// Coordinates are reprojected from WGS84 to D96/TM. Every track segment is
+/// returned as a separate list of points, in the order they appear in the file.
+/// Points contain the elevation recorded by the GPS device, if any. Since those
+/// are usually noisy, they can be replaced with heights from the DEM
+/// by [`Terrain::replace_elevations`](crate::Terrain::replace_elevations).
+///
+/// Waypoints and routes are ignored.
+pub fn read_gpx(reader: impl io::Read) -> Result<Vec<Vec<Point<Option<f32>>>>, ImportError> {
+    let gpx = ::gpx::read(reader)?;
+
+    let segments = gpx
+        .tracks
+        .iter()
+        .flat_map(|track| &track.segments)
+        .map(|segment| {
+            segment
+                .points
+                .iter()
+                .map(|waypoint| {
+                    let position = waypoint.point();
+                    let point = Point::from_wgs84(position.y(), position.x());
+                    Point {
+                        x: point.x,
+                        y: point.y,
+                        data: waypoint.elevation.map(|e| e as f32),
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, Terrain, qtree::QuadTree};
+
+    use super::read_gpx;
+
+    #[test]
+    fn read_gpx_tracks() {
+        let gpx = r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1">
+  <trk>
+    <trkseg>
+      <trkpt lat="46.0" lon="15.0"><ele>300.5</ele></trkpt>
+      <trkpt lat="46.0001" lon="15.0"></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="46.3783" lon="13.8366"><ele>2864</ele></trkpt>
+    </trkseg>
+  </trk>
+</gpx>"#;
+
+        let mut segments = read_gpx(gpx.as_bytes()).unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].len(), 2);
+
+        let start = &segments[0][0];
+        assert!((start.x - 500000.0).abs() < 0.01);
+        assert!((start.y - 95576.318).abs() < 0.01);
+        assert_eq!(start.data, Some(300.5));
+        assert_eq!(segments[0][1].data, None);
+        assert_eq!(segments[1][0].data, Some(2864.0));
+
+        let terrain = Terrain::new(QuadTree::from_points(vec![
+            Point {
+                x: start.x,
+                y: start.y,
+                data: 310.0,
+            },
+            Point {
+                x: start.x + 1000.0,
+                y: start.y + 1000.0,
+                data: 320.0,
+            },
+        ]));
+        terrain.replace_elevations(&mut segments[0]);
+        assert_eq!(segments[0][0].data, Some(310.0));
+        assert_eq!(segments[0][1].data, None);
+    }
+}
@@ -0,0 +1,113 @@
+//! Import of LiDAR point clouds in LAS and LAZ format.
+
+use std::io;
+
+use crate::Point;
+
+use super::{ImportResult, PointWriter};
+
+/// Classification of ground points, as defined by the LAS specification.
+pub const CLASS_GROUND: u8 = 2;
+
+/// Number of points that are decoded at once.
+const BATCH_SIZE: u64 = 100_000;
+
+/// Imports LAS point cloud from provided reader.
+///
+/// Supports LAS versions 1.0 to 1.4. Compressed LAZ files are
+/// supported only when the `laz` feature is enabled.
+///
+/// If `classes` is provided, only points with one of the classifications
+/// are imported. For example, use `Some(&[CLASS_GROUND])` to import only ground points.
+/// Parsed points are written to provided writer, with height as data.
+///
+/// If reading from file, you should wrap it into
+/// [BufReader](https://doc.rust-lang.org/std/io/struct.BufReader.html)
+/// to improve the performance.
+pub fn import_las<R>(reader: R, writer: impl io::Write, classes: Option<&[u8]>) -> ImportResult
+where
+    R: io::Read + io::Seek + Send + Sync + 'static,
+{
+    let mut writer = PointWriter::new(writer);
+    parse_las(reader, classes, &mut |p| Ok(writer.write(p)?))?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Parses the LAS point cloud and calls `emit` for each point that passes the filter.
+pub(super) fn parse_las<R, F>(reader: R, classes: Option<&[u8]>, emit: &mut F) -> ImportResult
+where
+    R: io::Read + io::Seek + Send + Sync + 'static,
+    F: FnMut(&Point<f32>) -> ImportResult,
+{
+    let mut reader = las::Reader::new(reader)?;
+    let mut data = las::PointDataBuilder::new()
+        .for_header(reader.header())
+        .build();
+
+    while reader.fill_points(BATCH_SIZE, &mut data)? > 0 {
+        let points = data
+            .x()
+            .zip(data.y())
+            .zip(data.z())
+            .zip(data.classification());
+        for (((x, y), z), class) in points {
+            if classes.is_some_and(|classes| !classes.contains(&class)) {
+                continue;
+            }
+
+            emit(&Point {
+                x,
+                y,
+                data: z as f32,
+            })?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use las::{Builder, Writer, point::Classification};
+
+    use crate::data::read_points;
+
+    #[test]
+    fn import_las_ground_points() {
+        let mut builder = Builder::from((1, 4));
+        builder.transforms.x.scale = 0.01;
+        builder.transforms.y.scale = 0.01;
+        builder.transforms.z.scale = 0.01;
+        let mut writer = Writer::new(Cursor::new(vec![]), builder.into_header().unwrap()).unwrap();
+        for (i, class) in [
+            Classification::Ground,
+            Classification::HighVegetation,
+            Classification::Ground,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            writer
+                .write_point(las::Point {
+                    x: 500_000.0 + i as f64,
+                    y: 100_000.0,
+                    z: 300.5,
+                    classification: class,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let buf = writer.into_inner().unwrap();
+
+        let mut out = vec![];
+        super::import_las(buf, &mut out, Some(&[super::CLASS_GROUND])).unwrap();
+        let points = read_points(&out[..]).unwrap();
+
+        let got: Vec<_> = points.iter().map(|p| (p.x, p.data)).collect();
+        assert_eq!(got, [(500_000.0, 300.5), (500_002.0, 300.5)]);
+    }
+}
@@ -0,0 +1,72 @@
+//! Generator of synthetic terrain for tests and benchmarks.
+
+use crate::Point;
+
+/// Generates a digital elevation model on a regular grid.
+///
+/// Grid starts at `origin` and has `cols` columns and `rows` rows,
+/// which are `spacing` meters apart. Heights are a mix of smooth hills and
+/// small noise, between 0 and 2500 meters. Same `seed` always generates
+/// the same terrain, so the results are reproducible.
+pub fn synthetic_dem<T>(
+    origin: &Point<T>,
+    cols: usize,
+    rows: usize,
+    spacing: f64,
+    seed: u64,
+) -> Vec<Point<f32>> {
+    let phase = (seed % 1000) as f64;
+    let mut points = Vec::with_capacity(cols * rows);
+    for row in 0..rows {
+        for col in 0..cols {
+            let x = origin.x + col as f64 * spacing;
+            let y = origin.y + row as f64 * spacing;
+
+            let hills = (x / 1700.0 + phase).sin() * (y / 2300.0 - phase).cos()
+                + 0.5 * (x / 450.0 + y / 610.0 + phase).sin();
+            let noise = hash(seed, row as u64, col as u64) as f64 / u64::MAX as f64;
+            let height = 1200.0 + 700.0 * hills + 20.0 * noise;
+
+            points.push(Point {
+                x,
+                y,
+                data: height as f32,
+            });
+        }
+    }
+
+    points
+}
+
+/// Mixes the values into a pseudo random number.
+fn hash(seed: u64, row: u64, col: u64) -> u64 {
+    let mut h =
+        seed ^ row.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ col.wrapping_mul(0xC2B2_AE3D_27D4_EB4F);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xFF51_AFD7_ED55_8CCD);
+    h ^= h >> 33;
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::synthetic_dem;
+
+    #[test]
+    fn synthetic_dem_is_reproducible() {
+        let origin = Point {
+            x: 400_000.0,
+            y: 100_000.0,
+            data: (),
+        };
+        let dem = synthetic_dem(&origin, 30, 20, 5.0, 42);
+        assert_eq!(dem.len(), 600);
+        assert_eq!((dem[31].x, dem[31].y), (400_005.0, 100_005.0));
+        assert!(dem.iter().all(|p| (0.0..=2500.0).contains(&p.data)));
+
+        assert_eq!(dem, synthetic_dem(&origin, 30, 20, 5.0, 42));
+        assert_ne!(dem, synthetic_dem(&origin, 30, 20, 5.0, 7));
+    }
+}
@@ -0,0 +1,203 @@
+//! Point files split into a directory of square tiles.
+//!
+//! Each tile is stored in its own point file, named after the tile's extent as
+//! `<min_x>_<min_y>_<size>.bin` in whole meters, for example `370000_30000_10000.bin`.
+//! All tiles in a directory have the same size.
+
+use std::{
+    collections::{HashMap, hash_map::Entry},
+    fs,
+    io::{self, BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+use crate::{Area, Point};
+
+use super::{
+    ImportError, ImportOptions, ImportProgress, ImportReport,
+    format::{self, PointWriter},
+};
+
+const EXTENSION: &str = "bin";
+
+/// Imports raw data from provided path into a directory of tiles.
+///
+/// Points are grouped into square tiles of `tile_size` meters, which can be
+/// read with [`TileStore`]. Existing tiles in the directory are overwritten.
+/// A file is kept open for every tile while importing, so the tiles shouldn't
+/// be too small. Otherwise it behaves the same as [`import_data_with`](super::import_data_with).
+///
+/// Tiles of a different size, left from an earlier import, make the directory
+/// invalid for [`TileStore::open`], so a new directory should be used instead.
+/// Error is returned if `tile_size` is not a positive whole number of meters.
+pub fn import_data_tiled(
+    input_path: impl AsRef<Path>,
+    output_dir: impl AsRef<Path>,
+    tile_size: f64,
+    options: &ImportOptions,
+    on_progress: impl FnMut(ImportProgress),
+) -> Result<ImportReport, ImportError> {
+    if !(tile_size > 0.0 && tile_size.is_finite() && tile_size.fract() == 0.0) {
+        return Err(ImportError::InvalidTileSize(tile_size));
+    }
+
+    let output_dir = output_dir.as_ref();
+    fs::create_dir_all(output_dir)?;
+
+    let mut writers: HashMap<(i64, i64), PointWriter<BufWriter<fs::File>>> = HashMap::new();
+    let report = super::import_into(input_path.as_ref(), options, on_progress, |p| {
+        let key = (
+            (p.x / tile_size).floor() as i64,
+            (p.y / tile_size).floor() as i64,
+        );
+        let writer = match writers.entry(key) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let size = tile_size as i64;
+                let name = tile_name(key.0 * size, key.1 * size, size);
+                let file = fs::File::create(output_dir.join(name))?;
+                entry.insert(PointWriter::new(BufWriter::new(file)))
+            }
+        };
+        writer.write(p)
+    })?;
+
+    for writer in writers.into_values() {
+        writer
+            .finish()?
+            .into_inner()
+            .map_err(|err| err.into_error())?;
+    }
+
+    Ok(report)
+}
+
+fn tile_name(min_x: i64, min_y: i64, size: i64) -> String {
+    format!("{min_x}_{min_y}_{size}.{EXTENSION}")
+}
+
+/// Parses the extent of the tile from its file name.
+fn parse_tile_name(path: &Path) -> Option<Area> {
+    if path.extension()? != EXTENSION {
+        return None;
+    }
+
+    let mut parts = path.file_stem()?.to_str()?.split('_');
+    let min_x: f64 = parts.next()?.parse().ok()?;
+    let min_y: f64 = parts.next()?.parse().ok()?;
+    let size: f64 = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    Some(Area::from_bounds(min_x, min_y, min_x + size, min_y + size))
+}
+
+/// Directory of tiles written by [`import_data_tiled`].
+///
+/// Only the tiles that intersect the queried area are read.
+#[derive(Debug, Clone)]
+pub struct TileStore {
+    tiles: Vec<(Area, PathBuf)>,
+}
+
+impl TileStore {
+    /// Opens the directory of tiles. Files that are not tiles are ignored.
+    ///
+    /// Error is returned if the tiles are not all of the same size,
+    /// which happens when tiles of different imports are mixed.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let mut tiles = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if let Some(area) = parse_tile_name(&path) {
+                tiles.push((area, path));
+            }
+        }
+        tiles.sort_by(|a, b| a.1.cmp(&b.1));
+
+        if let Some((first, _)) = tiles.first()
+            && tiles
+                .iter()
+                .any(|(area, _)| area.half_width != first.half_width)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "tiles of different sizes in the directory",
+            ));
+        }
+
+        Ok(Self { tiles })
+    }
+
+    /// Returns the extents of all tiles.
+    pub fn tiles(&self) -> impl Iterator<Item = &Area> {
+        self.tiles.iter().map(|(area, _)| area)
+    }
+
+    /// Reads all points of the tile with the given index in [`TileStore::tiles`].
+    pub(crate) fn read_tile(&self, index: usize) -> Result<Vec<Point<f32>>, io::Error> {
+        let reader = BufReader::new(fs::File::open(&self.tiles[index].1)?);
+        super::read_points(reader)
+    }
+
+    /// Reads points inside the area.
+    pub fn read_in_area(&self, area: &Area) -> Result<Vec<Point<f32>>, io::Error> {
+        let mut points = vec![];
+        for (tile, path) in &self.tiles {
+            if !tile.intersects(area) {
+                continue;
+            }
+
+            let reader = BufReader::new(fs::File::open(path)?);
+            points.extend(format::read_in_area(reader, area)?);
+        }
+
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Area,
+        data::{ImportError, ImportOptions},
+    };
+
+    use super::{TileStore, import_data_tiled};
+
+    #[test]
+    fn import_and_read_tiles() {
+        let dir = std::env::temp_dir().join(format!("hribovje-tiled-{}", std::process::id()));
+        let input = dir.join("input");
+        let output = dir.join("tiles");
+        std::fs::create_dir_all(&input).unwrap();
+        std::fs::write(input.join("a.xyz"), "5 5 1\n15 5 2\n-5 25 3\n16 8 4\n").unwrap();
+
+        let report =
+            import_data_tiled(&input, &output, 10.0, &ImportOptions::default(), |_| ()).unwrap();
+        assert_eq!(report.points, 4);
+
+        let err = import_data_tiled(&input, &output, 2.5, &ImportOptions::default(), |_| ());
+        assert!(matches!(err, Err(ImportError::InvalidTileSize(2.5))));
+
+        let store = TileStore::open(&output).unwrap();
+        assert_eq!(store.tiles().count(), 3);
+        assert!(output.join("10_0_10.bin").exists());
+        assert!(output.join("-10_20_10.bin").exists());
+
+        let points = store
+            .read_in_area(&Area::from_bounds(12.0, 0.0, 30.0, 30.0))
+            .unwrap();
+
+        let heights: Vec<_> = points.iter().map(|p| p.data).collect();
+        assert_eq!(heights, vec![2.0, 4.0]);
+
+        // Tiles of an import with another size are mixed in.
+        import_data_tiled(&input, &output, 20.0, &ImportOptions::default(), |_| ()).unwrap();
+        assert!(output.join("0_0_20.bin").exists());
+        let err = TileStore::open(&output).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
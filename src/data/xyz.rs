@@ -0,0 +1,191 @@
+//! Parsing of points in `.xyz` text format.
+//!
+//! Lines are scanned as bytes, without UTF-8 validation, and numbers are parsed
+//! with a fast path for plain decimal numbers, which is what the DEM files contain.
+//! Everything else falls back to the standard library parser.
+
+use std::{io::BufRead, path::Path};
+
+use crate::Point;
+
+use super::{ImportError, ImportOptions, ImportResult, OnError};
+
+/// Exact powers of ten, which can be represented as `f64`.
+const POW10: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// Parses points in `.xyz` format and calls `emit` for each parsed point.
+///
+/// `path` is only used for reporting malformed lines.
+pub(super) fn parse_xyz<F>(
+    mut reader: impl BufRead,
+    path: &Path,
+    options: &ImportOptions,
+    skipped: &mut Vec<ImportError>,
+    emit: &mut F,
+) -> ImportResult
+where
+    F: FnMut(&Point<f32>) -> ImportResult,
+{
+    let mut buf = vec![];
+    let mut line = 0;
+    loop {
+        buf.clear();
+        let bytes = reader.read_until(b'\n', &mut buf)?;
+        if bytes == 0 {
+            break;
+        }
+        line += 1;
+
+        let mut iter = Numbers(&buf);
+        let arr: [_; 3] = std::array::from_fn(|_| iter.next());
+        let [Some(x), Some(y), Some(height)] = arr else {
+            let err = ImportError::InvalidData {
+                path: path.to_path_buf(),
+                line,
+                found: arr.iter().take_while(|v| v.is_some()).count() as u8,
+            };
+            match options.on_error {
+                OnError::Fail => return Err(err),
+                OnError::Skip => {
+                    skipped.push(err);
+                    continue;
+                }
+            }
+        };
+
+        emit(&Point {
+            x,
+            y,
+            data: height as f32,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Iterator over numbers of a line.
+///
+/// Tokens, which are not valid numbers, are skipped.
+struct Numbers<'a>(&'a [u8]);
+
+impl Iterator for Numbers<'_> {
+    type Item = f64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let start = self.0.iter().position(|b| !b.is_ascii_whitespace())?;
+            let (value, len) = parse_decimal(&self.0[start..]);
+            let (token, rest) = self.0[start..].split_at(len);
+            self.0 = rest;
+
+            let value = value.or_else(|| std::str::from_utf8(token).ok()?.parse().ok());
+            if value.is_some() {
+                return value;
+            }
+        }
+    }
+}
+
+/// Parses a plain decimal number like `-123.45` at the start of the input.
+///
+/// Returns the parsed value and the length of the token, which ends at whitespace
+/// or at the end of input. Value is `None` if the token has any other form, or if
+/// it can't be parsed exactly, in which case the caller should fall back to a full parser.
+fn parse_decimal(input: &[u8]) -> (Option<f64>, usize) {
+    let (negative, skip) = match input.first() {
+        Some(b'-') => (true, 1),
+        Some(b'+') => (false, 1),
+        _ => (false, 0),
+    };
+
+    let mut mantissa: u64 = 0;
+    let mut digit_count = 0;
+    let mut fraction_start = None;
+    let mut simple = true;
+    let mut len = skip;
+    for &b in &input[skip..] {
+        match b {
+            b'0'..=b'9' => {
+                mantissa = mantissa.wrapping_mul(10).wrapping_add((b - b'0') as u64);
+                digit_count += 1;
+            }
+            b'.' if fraction_start.is_none() => fraction_start = Some(len + 1),
+            b if b.is_ascii_whitespace() => break,
+            _ => simple = false,
+        }
+        len += 1;
+    }
+
+    // Mantissa must be exactly representable as f64.
+    if !simple || digit_count == 0 || digit_count > 15 {
+        return (None, len);
+    }
+
+    // Both the mantissa and the power of ten are exact,
+    // so the division is correctly rounded.
+    let fraction_digits = fraction_start.map_or(0, |start| len - start);
+    let value = mantissa as f64 / POW10[fraction_digits];
+    (Some(if negative { -value } else { value }), len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Numbers;
+
+    fn parse_f64(token: &[u8]) -> Option<f64> {
+        let mut numbers = Numbers(token);
+        numbers.next().filter(|_| numbers.next().is_none())
+    }
+
+    #[test]
+    fn parse_matches_std() {
+        let cases = [
+            "0",
+            "-0",
+            "1",
+            "462000.00",
+            "101000.25",
+            "-12.5",
+            "+3.75",
+            "0.1",
+            ".5",
+            "5.",
+            "295.123456789",
+            "1e3",
+            "-2.5E-3",
+            "12345678901234567890",
+            "0.30000000000000004",
+            "inf",
+            "NaN",
+            "1.2.3",
+            "-",
+            ".",
+            "",
+            "1a",
+        ];
+        for case in cases {
+            let expected = case.parse::<f64>().ok();
+            let got = parse_f64(case.as_bytes());
+            assert_eq!(
+                got.map(f64::to_bits),
+                expected.map(f64::to_bits),
+                "parsing {case:?}"
+            );
+        }
+
+        // Pseudo random decimals with two to six decimal places.
+        let mut state = 7u64;
+        for _ in 0..10_000 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let decimals = (state >> 60) as usize % 5 + 2;
+            let value = (state >> 20) as f64 / 1e3 - 1e6;
+            let case = format!("{value:.decimals$}");
+            assert_eq!(parse_f64(case.as_bytes()), case.parse().ok(), "{case}");
+        }
+    }
+}
@@ -0,0 +1,362 @@
+//! Downloading of the source data.
+//!
+//! Archives are described by a manifest, a text file with one archive per line:
+//!
+//! ```text
+//! # name  min_x   min_y   max_x   max_y   url                                   sha256
+//! nw      370000  100000  500000  200000  https://example.com/DEM_0050_NW.zip   4f3c...
+//! ```
+//!
+//! Extents are in D96/TM and the checksum is optional. Lines starting with `#` are comments.
+//! Archives are saved under the last segment of their url, which must be unique.
+//! Archives named after a [`Region`] can be downloaded by the region with [`download_region`].
+//!
+//! The crate doesn't include a list of the archives, so the manifest has to be written
+//! by hand. Links to the `DEM 0050` archives and LiDAR blocks can be copied from the
+//! [public geodetic data collection](https://ipi.eprostor.gov.si/jgp/data).
+//!
+//! Interrupted downloads are kept in `.part` files and resumed on the next run,
+//! if the server supports range requests.
+
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{Area, Region};
+
+/// The error type that can occur when downloading the data.
+#[derive(Debug, Error)]
+pub enum DownloadError {
+    #[error("io error")]
+    Io(#[from] io::Error),
+
+    #[error("http error")]
+    Http(#[from] Box<ureq::Error>),
+
+    #[error("invalid manifest at line {0}")]
+    InvalidManifest(usize),
+
+    /// Archive would be saved under the same file name as an earlier one.
+    #[error("duplicate file name {name} at line {line} of the manifest")]
+    DuplicateFileName { name: String, line: usize },
+
+    #[error("checksum of {} doesn't match (expected {expected}, found {found})", path.display())]
+    ChecksumMismatch {
+        path: PathBuf,
+        expected: String,
+        found: String,
+    },
+}
+
+/// Archive listed in the manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestEntry {
+    pub name: String,
+    /// Area covered by the archive.
+    pub area: Area,
+    pub url: String,
+    /// Expected SHA-256 checksum as a hex string.
+    pub sha256: Option<String>,
+}
+
+impl ManifestEntry {
+    /// Returns name of the downloaded file, which is the last segment of the url.
+    pub fn file_name(&self) -> &str {
+        let path = self.url.split(['?', '#']).next().unwrap_or_default();
+        match path.rsplit('/').next() {
+            Some(name) if !name.is_empty() => name,
+            _ => &self.name,
+        }
+    }
+
+    /// Returns the region, if the archive is named after one.
+    pub fn region(&self) -> Option<Region> {
+        Region::from_name(&self.name)
+    }
+}
+
+/// Parses the manifest, see [module documentation](self) for the format.
+///
+/// Entries with the same [`ManifestEntry::file_name`] are rejected,
+/// since they would overwrite each other.
+pub fn parse_manifest(reader: impl BufRead) -> Result<Vec<ManifestEntry>, DownloadError> {
+    let mut entries = vec![];
+    let mut file_names = HashSet::new();
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let invalid = || DownloadError::InvalidManifest(idx + 1);
+        let parts: Vec<_> = line.split_whitespace().collect();
+        let (name, bounds, url, sha256) = match parts[..] {
+            [name, a, b, c, d, url] => (name, [a, b, c, d], url, None),
+            [name, a, b, c, d, url, sha256] => (name, [a, b, c, d], url, Some(sha256)),
+            _ => return Err(invalid()),
+        };
+        let mut parsed = [0.0; 4];
+        for (value, s) in parsed.iter_mut().zip(bounds) {
+            *value = s.parse().map_err(|_| invalid())?;
+        }
+        let [min_x, min_y, max_x, max_y] = parsed;
+
+        let entry = ManifestEntry {
+            name: name.to_string(),
+            area: Area::from_bounds(min_x, min_y, max_x, max_y),
+            url: url.to_string(),
+            sha256: sha256.map(|s| s.to_ascii_lowercase()),
+        };
+        if !file_names.insert(entry.file_name().to_string()) {
+            return Err(DownloadError::DuplicateFileName {
+                name: entry.file_name().to_string(),
+                line: idx + 1,
+            });
+        }
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+/// Downloads the archive into the directory and returns path of the downloaded file.
+///
+/// Already downloaded files with a matching checksum are not downloaded again.
+/// `on_progress` is called with the number of downloaded bytes and
+/// the total size, if it is known.
+pub fn download(
+    entry: &ManifestEntry,
+    dir: impl AsRef<Path>,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<PathBuf, DownloadError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+    let path = dir.join(entry.file_name());
+    if path.exists() {
+        verify(entry, &path)?;
+        return Ok(path);
+    }
+
+    let part = dir.join(format!("{}.part", entry.file_name()));
+    let resume_from = fs::metadata(&part).map_or(0, |m| m.len());
+
+    let mut request = ureq::get(&entry.url);
+    if resume_from > 0 {
+        request = request.header("Range", format!("bytes={resume_from}-"));
+    }
+    let (mut file, mut downloaded, total, mut body) = match request.call() {
+        Ok(response) => {
+            let resumed = response.status().as_u16() == 206;
+            let length = response
+                .headers()
+                .get("content-length")
+                .and_then(|v| v.to_str().ok()?.parse::<u64>().ok());
+
+            // Server can ignore the range and send the whole file instead.
+            let (file, downloaded) = if resumed {
+                (OpenOptions::new().append(true).open(&part)?, resume_from)
+            } else {
+                (File::create(&part)?, 0)
+            };
+            let total = length.map(|l| l + downloaded);
+            let body: Box<dyn Read> = Box::new(response.into_body().into_reader());
+            (file, downloaded, total, body)
+        }
+        // Partial file is already complete.
+        Err(ureq::Error::StatusCode(416)) if resume_from > 0 => {
+            let file = OpenOptions::new().append(true).open(&part)?;
+            let body: Box<dyn Read> = Box::new(io::empty());
+            (file, resume_from, Some(resume_from), body)
+        }
+        Err(err) => return Err(Box::new(err).into()),
+    };
+
+    let mut buf = vec![0; 1 << 16];
+    loop {
+        let n = body.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&buf[..n])?;
+        downloaded += n as u64;
+        on_progress(downloaded, total);
+    }
+    file.sync_all()?;
+
+    if let Err(err) = verify(entry, &part) {
+        // Corrupted download can't be resumed, so the next run starts from scratch.
+        fs::remove_file(&part)?;
+        return Err(err);
+    }
+    fs::rename(&part, &path)?;
+
+    Ok(path)
+}
+
+/// Downloads all archives from the manifest, which intersect the area.
+///
+/// If the area is not given, all archives are downloaded.
+pub fn download_area(
+    manifest: &[ManifestEntry],
+    area: Option<&Area>,
+    dir: impl AsRef<Path>,
+    mut on_progress: impl FnMut(&ManifestEntry, u64, Option<u64>),
+) -> Result<Vec<PathBuf>, DownloadError> {
+    manifest
+        .iter()
+        .filter(|entry| area.is_none_or(|area| entry.area.intersects(area)))
+        .map(|entry| {
+            download(entry, dir.as_ref(), |done, total| {
+                on_progress(entry, done, total)
+            })
+        })
+        .collect()
+}
+
+/// Downloads all archives from the manifest, which are named after the region.
+pub fn download_region(
+    manifest: &[ManifestEntry],
+    region: Region,
+    dir: impl AsRef<Path>,
+    mut on_progress: impl FnMut(&ManifestEntry, u64, Option<u64>),
+) -> Result<Vec<PathBuf>, DownloadError> {
+    manifest
+        .iter()
+        .filter(|entry| entry.region() == Some(region))
+        .map(|entry| {
+            download(entry, dir.as_ref(), |done, total| {
+                on_progress(entry, done, total)
+            })
+        })
+        .collect()
+}
+
+/// Checks the checksum of the file, if the entry has one.
+fn verify(entry: &ManifestEntry, path: &Path) -> Result<(), DownloadError> {
+    let Some(expected) = &entry.sha256 else {
+        return Ok(());
+    };
+
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    let found: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    if found == *expected {
+        Ok(())
+    } else {
+        Err(DownloadError::ChecksumMismatch {
+            path: path.to_path_buf(),
+            expected: expected.clone(),
+            found,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+    };
+
+    use crate::Region;
+
+    use super::{DownloadError, ManifestEntry, download, parse_manifest};
+
+    #[test]
+    fn manifest_format() {
+        let manifest = "# regions\n\
+            nw 370000 100000 500000 200000 https://example.com/dem/NW.zip?x=1\n\n\
+            se 500000 30000 630000 100000 https://example.com/dem/SE.zip ABCD\n";
+        let entries = parse_manifest(manifest.as_bytes()).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].file_name(), "NW.zip");
+        assert_eq!(entries[0].area.min_y(), 100000.0);
+        assert_eq!(entries[1].sha256.as_deref(), Some("abcd"));
+        assert_eq!(entries[1].region(), Some(Region::SE));
+
+        let err = parse_manifest("nw 1 2 x 4 url\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, DownloadError::InvalidManifest(1)));
+
+        let manifest = "nw 0 0 1 1 https://example.com/nw/dem.zip\n\
+            ne 1 0 2 1 https://example.com/ne/dem.zip\n";
+        let err = parse_manifest(manifest.as_bytes()).unwrap_err();
+        assert!(
+            matches!(err, DownloadError::DuplicateFileName { name, line: 2 } if name == "dem.zip")
+        );
+    }
+
+    /// Serves the content, honouring range requests.
+    fn serve(content: &'static [u8], requests: usize) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut start = 0;
+                for line in BufReader::new(&stream).lines() {
+                    let line = line.unwrap();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(range) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                        start = range.trim_end_matches('-').parse().unwrap();
+                    }
+                }
+
+                let status = if start > 0 {
+                    "206 Partial Content"
+                } else {
+                    "200 OK"
+                };
+                let body = &content[start..];
+                write!(
+                    stream,
+                    "HTTP/1.1 {status}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    body.len()
+                )
+                .unwrap();
+                stream.write_all(body).unwrap();
+            }
+        });
+
+        format!("http://{addr}/data/archive.zip")
+    }
+
+    #[test]
+    fn resume_and_verify() {
+        let dir = std::env::temp_dir().join(format!("hribovje-download-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("archive.zip.part"), b"hello ").unwrap();
+
+        let mut entry = ManifestEntry {
+            name: "test".to_string(),
+            area: crate::Area::from_bounds(0.0, 0.0, 1.0, 1.0),
+            url: serve(b"hello world", 2),
+            // SHA-256 of "hello world".
+            sha256: Some(
+                "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9".to_string(),
+            ),
+        };
+        let path = download(&entry, &dir, |_, _| ()).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"hello world");
+        assert!(!dir.join("archive.zip.part").exists());
+
+        // Existing file is verified instead of downloaded again.
+        entry.sha256 = Some("00".to_string());
+        let err = download(&entry, &dir, |_, _| ()).unwrap_err();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(err, DownloadError::ChecksumMismatch { .. }));
+    }
+}
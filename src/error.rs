@@ -0,0 +1,102 @@
+use thiserror::Error;
+
+use crate::{
+    data::ImportError,
+    grid::{AlignmentError, MosaicError},
+    qtree::{InsertError, QueryError, ReadError},
+};
+
+/// Error of any fallible operation in this crate.
+///
+/// Errors of the individual modules convert into it,
+/// so that `?` can be used when combining them.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("import error")]
+    Import(#[from] ImportError),
+
+    #[error("insert error")]
+    Insert(#[from] InsertError),
+
+    #[error("query error")]
+    Query(#[from] QueryError),
+
+    #[error("read error")]
+    Read(#[from] ReadError),
+
+    #[error("mosaic error")]
+    Mosaic(#[from] MosaicError),
+
+    #[error("alignment error")]
+    Alignment(#[from] AlignmentError),
+
+    #[cfg(feature = "download")]
+    #[error("download error")]
+    Download(#[from] crate::download::DownloadError),
+
+    #[cfg(feature = "png")]
+    #[error("png error")]
+    Png(#[from] png::EncodingError),
+
+    #[cfg(feature = "png")]
+    #[error("tile error")]
+    Tile(#[from] crate::tiles::TileError),
+
+    #[cfg(feature = "gpkg")]
+    #[error("geopackage error")]
+    Gpkg(#[from] crate::data::GpkgError),
+
+    #[cfg(feature = "geotiff")]
+    #[error("tiff error")]
+    Tiff(#[from] tiff::TiffError),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use crate::{
+        Area, Point,
+        qtree::{InsertError, QuadTree},
+    };
+
+    use super::Error;
+
+    fn insert_outside() -> Result<(), Error> {
+        let mut tree = QuadTree::new(Area::square(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+        ));
+        tree.insert(Point {
+            x: 5.0,
+            y: 0.0,
+            data: (),
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn module_errors_convert() {
+        let err = insert_outside().unwrap_err();
+        assert!(matches!(err, Error::Insert(InsertError::OutsideArea)));
+        assert!(err.source().is_some());
+
+        let points = vec![
+            Point {
+                x: f64::NAN,
+                y: 0.0,
+                data: (),
+            },
+            Point {
+                x: 1.0,
+                y: 1.0,
+                data: (),
+            },
+        ];
+        assert_eq!(QuadTree::from_points(points).size(), 1);
+    }
+}
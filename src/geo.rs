@@ -0,0 +1,150 @@
+//! Conversions between the types of this crate and [`geo_types`].
+
+use geo_types::{Coord, Polygon, Rect};
+
+use crate::{
+    Area, Point,
+    qtree::{self, QuadTree, QueryError, QueryOutcome},
+};
+
+impl<T> From<&Point<T>> for Coord<f64> {
+    fn from(point: &Point<T>) -> Self {
+        Coord {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+impl<T> From<&Point<T>> for geo_types::Point<f64> {
+    fn from(point: &Point<T>) -> Self {
+        geo_types::Point::new(point.x, point.y)
+    }
+}
+
+impl From<Coord<f64>> for Point {
+    fn from(coord: Coord<f64>) -> Self {
+        Point {
+            x: coord.x,
+            y: coord.y,
+            data: (),
+        }
+    }
+}
+
+impl From<geo_types::Point<f64>> for Point {
+    fn from(point: geo_types::Point<f64>) -> Self {
+        point.0.into()
+    }
+}
+
+impl From<&Area> for Rect<f64> {
+    fn from(area: &Area) -> Self {
+        Rect::new(
+            Coord {
+                x: area.min_x(),
+                y: area.min_y(),
+            },
+            Coord {
+                x: area.max_x(),
+                y: area.max_y(),
+            },
+        )
+    }
+}
+
+impl From<&Area> for Polygon<f64> {
+    fn from(area: &Area) -> Self {
+        Rect::from(area).to_polygon()
+    }
+}
+
+impl From<Rect<f64>> for Area {
+    fn from(rect: Rect<f64>) -> Self {
+        Area::from_bounds(rect.min().x, rect.min().y, rect.max().x, rect.max().y)
+    }
+}
+
+impl<T: Clone> QuadTree<T> {
+    /// Queries points inside the [`geo_types::Polygon`], respecting its holes.
+    ///
+    /// Only nodes intersecting the bounding box of the polygon are visited.
+    /// Points are cloned from the tree and put into `results`.
+    /// If there are more points in the polygon than length of the results,
+    /// the query stops once results are full and the outcome is marked as truncated.
+    pub fn query_geo_polygon(
+        &self,
+        polygon: &Polygon<f64>,
+        results: &mut [Point<T>],
+    ) -> Result<QueryOutcome, QueryError> {
+        if polygon.exterior().0.len() < 3 {
+            return Ok(QueryOutcome {
+                written: 0,
+                truncated: false,
+            });
+        }
+
+        let (min_x, min_y, max_x, max_y) = polygon.exterior().coords().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), c| {
+                (
+                    min_x.min(c.x),
+                    min_y.min(c.y),
+                    max_x.max(c.x),
+                    max_y.max(c.y),
+                )
+            },
+        );
+        let bounds = Area::from_bounds(min_x, min_y, max_x, max_y);
+
+        let ring = |ring: &geo_types::LineString<f64>, p: &Point<T>| {
+            qtree::is_inside_ring(p, ring.0.iter().map(|c| (c.x, c.y)))
+        };
+        self.query_filtered(
+            &bounds,
+            |p| ring(polygon.exterior(), p) && !polygon.interiors().iter().any(|i| ring(i, p)),
+            results,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{Polygon, Rect, coord};
+
+    use crate::{Area, Point, qtree::QuadTree};
+
+    #[test]
+    fn query_polygon_with_hole() {
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point {
+                x: (i % 100) as f64 + 0.5,
+                y: (i / 100) as f64 + 0.5,
+                data: i as f32,
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+
+        let outer = Rect::new(coord! { x: 10.0, y: 10.0 }, coord! { x: 30.0, y: 30.0 });
+        let hole = Rect::new(coord! { x: 15.0, y: 15.0 }, coord! { x: 20.0, y: 20.0 });
+        let polygon = Polygon::new(
+            outer.to_polygon().exterior().clone(),
+            vec![hole.to_polygon().exterior().clone()],
+        );
+
+        let mut results = vec![
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: 0.0
+            };
+            1000
+        ];
+        let outcome = tree.query_geo_polygon(&polygon, &mut results).unwrap();
+        assert_eq!(outcome.written, 20 * 20 - 5 * 5);
+
+        let area = Area::from(outer);
+        assert_eq!(Rect::from(&area), outer);
+        assert_eq!(tree.count(&area), 20 * 20);
+    }
+}
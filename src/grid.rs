@@ -0,0 +1,259 @@
+//! Regular grids of values, which are a better fit for raster analysis than the quad tree.
+
+pub mod hydrology;
+pub mod render;
+
+mod algebra;
+mod contour;
+mod convert;
+mod curvature;
+mod detrend;
+mod fill;
+mod focal;
+#[cfg(feature = "geotiff")]
+mod geotiff;
+mod gradient;
+mod hillshade;
+mod mosaic;
+mod pyramid;
+mod quantize;
+mod resample;
+mod slope;
+mod smooth;
+mod volume;
+
+use crate::Point;
+
+pub use algebra::AlignmentError;
+pub(crate) use contour::contours_at;
+pub use contour::{LineString, contours};
+pub use convert::Aggregation;
+pub use curvature::Curvature;
+pub use fill::FillMethod;
+pub use focal::Window;
+#[cfg(feature = "geotiff")]
+pub use geotiff::write_geotiff;
+pub use gradient::GradientMethod;
+pub use mosaic::{MosaicError, Overlap};
+pub use pyramid::RasterPyramid;
+pub use quantize::{Quantization, QuantizedRaster};
+pub use resample::ResampleMethod;
+pub use smooth::SmoothMethod;
+pub use volume::CutFill;
+
+/// Value of cells without data in rasters constructed from points.
+pub const NODATA: f32 = -9999.0;
+
+/// Regular grid of square cells.
+///
+/// Rows are ordered from north to south and columns from west to east, like in
+/// images and the Esri ASCII GRID format. Values are stored in row-major order.
+/// Cells with value equal to `nodata` are considered empty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster<T = f32> {
+    /// Center of the north-west cell.
+    origin: Point,
+    cell_size: f64,
+    ncols: usize,
+    nrows: usize,
+    nodata: Option<T>,
+    data: Vec<T>,
+}
+
+impl<T: Copy + PartialEq> Raster<T> {
+    /// Creates a raster with all cells set to `value`.
+    ///
+    /// `origin` is the center of the north-west cell.
+    pub fn new(origin: Point, cell_size: f64, ncols: usize, nrows: usize, value: T) -> Self {
+        Self {
+            origin,
+            cell_size,
+            ncols,
+            nrows,
+            nodata: None,
+            data: vec![value; ncols * nrows],
+        }
+    }
+
+    /// Sets the value of cells without data.
+    pub fn with_nodata(mut self, nodata: Option<T>) -> Self {
+        self.nodata = nodata;
+        self
+    }
+
+    /// Returns the center of the north-west cell.
+    pub fn origin(&self) -> &Point {
+        &self.origin
+    }
+
+    /// Returns the width and height of a cell in meters.
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    pub fn ncols(&self) -> usize {
+        self.ncols
+    }
+
+    pub fn nrows(&self) -> usize {
+        self.nrows
+    }
+
+    pub fn nodata(&self) -> Option<T> {
+        self.nodata
+    }
+
+    /// Returns all values in row-major order, including the ones without data.
+    pub fn data(&self) -> &[T] {
+        &self.data
+    }
+
+    /// Returns the value of the cell.
+    ///
+    /// None is returned if the cell is outside of the raster or has no data.
+    pub fn get(&self, row: usize, col: usize) -> Option<T> {
+        if row >= self.nrows || col >= self.ncols {
+            return None;
+        }
+
+        let value = self.data[row * self.ncols + col];
+        (self.nodata != Some(value)).then_some(value)
+    }
+
+    /// Sets the value of the cell.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the cell is outside of the raster.
+    pub fn set(&mut self, row: usize, col: usize, value: T) {
+        assert!(row < self.nrows && col < self.ncols, "cell out of bounds");
+        self.data[row * self.ncols + col] = value;
+    }
+
+    /// Returns `(row, col)` of the cell containing the location.
+    pub fn cell_at(&self, x: f64, y: f64) -> Option<(usize, usize)> {
+        let col = ((x - self.origin.x) / self.cell_size + 0.5).floor();
+        let row = ((self.origin.y - y) / self.cell_size + 0.5).floor();
+
+        let inside = col >= 0.0 && row >= 0.0;
+        let (row, col) = (row as usize, col as usize);
+        (inside && row < self.nrows && col < self.ncols).then_some((row, col))
+    }
+
+    /// Returns the value of the cell containing the location.
+    pub fn value_at(&self, x: f64, y: f64) -> Option<T> {
+        let (row, col) = self.cell_at(x, y)?;
+        self.get(row, col)
+    }
+
+    /// Creates a raster with the same extent, with all cells set to `value`.
+    pub fn same_extent<U: Copy + PartialEq>(&self, value: U) -> Raster<U> {
+        Raster::new(
+            self.origin.clone(),
+            self.cell_size,
+            self.ncols,
+            self.nrows,
+            value,
+        )
+    }
+
+    /// Returns the center of the cell.
+    pub fn cell_center(&self, row: usize, col: usize) -> Point {
+        Point {
+            x: self.origin.x + col as f64 * self.cell_size,
+            y: self.origin.y - row as f64 * self.cell_size,
+            data: (),
+        }
+    }
+}
+
+impl Raster<f32> {
+    /// Creates a raster from points, which contain height as data.
+    ///
+    /// The raster covers the bounding box of the points, with points at
+    /// the centers of the cells. Points are snapped to the nearest cell,
+    /// so they are expected to already lie on a grid with the given cell size,
+    /// as they do in the `DEM 0050` data. Cells without a point have [`NODATA`].
+    pub fn from_points(points: &[Point<f32>], cell_size: f64) -> Self {
+        if points.is_empty() {
+            return Self::new(
+                Point {
+                    x: 0.0,
+                    y: 0.0,
+                    data: (),
+                },
+                cell_size,
+                0,
+                0,
+                NODATA,
+            )
+            .with_nodata(Some(NODATA));
+        }
+
+        let (min_x, max_y, max_x, min_y) = points.iter().fold(
+            (f64::MAX, f64::MIN, f64::MIN, f64::MAX),
+            |(min_x, max_y, max_x, min_y), p| {
+                (
+                    min_x.min(p.x),
+                    max_y.max(p.y),
+                    max_x.max(p.x),
+                    min_y.min(p.y),
+                )
+            },
+        );
+
+        let ncols = ((max_x - min_x) / cell_size).round() as usize + 1;
+        let nrows = ((max_y - min_y) / cell_size).round() as usize + 1;
+        let origin = Point {
+            x: min_x,
+            y: max_y,
+            data: (),
+        };
+
+        let mut raster =
+            Self::new(origin, cell_size, ncols, nrows, NODATA).with_nodata(Some(NODATA));
+        for p in points {
+            let col = ((p.x - min_x) / cell_size).round() as usize;
+            let row = ((max_y - p.y) / cell_size).round() as usize;
+            raster.set(row, col, p.data);
+        }
+
+        raster
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::{NODATA, Raster};
+
+    #[test]
+    fn raster_from_points() {
+        let points: Vec<_> = (0..12)
+            .filter(|&i| i != 5)
+            .map(|i| Point {
+                x: 1000.0 + (i % 4) as f64 * 5.0,
+                y: 2000.0 + (i / 4) as f64 * 5.0,
+                data: i as f32,
+            })
+            .collect();
+        let raster = Raster::from_points(&points, 5.0);
+
+        assert_eq!((raster.ncols(), raster.nrows()), (4, 3));
+        assert_eq!(raster.origin().x, 1000.0);
+        assert_eq!(raster.origin().y, 2010.0);
+
+        // North row is first.
+        assert_eq!(raster.get(0, 0), Some(8.0));
+        assert_eq!(raster.get(2, 3), Some(3.0));
+        assert_eq!(raster.get(1, 1), None);
+        assert_eq!(raster.data()[5], NODATA);
+        assert_eq!(raster.get(3, 0), None);
+
+        assert_eq!(raster.cell_at(1002.4, 2007.6), Some((0, 0)));
+        assert_eq!(raster.value_at(1015.0, 2000.0), Some(3.0));
+        assert_eq!(raster.value_at(997.0, 2000.0), None);
+        assert_eq!(raster.cell_center(2, 3).x, 1015.0);
+    }
+}
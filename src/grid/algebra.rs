@@ -0,0 +1,160 @@
+//! Cell by cell operations between rasters.
+
+use std::ops::{Add, Sub};
+
+use thiserror::Error;
+
+use super::{NODATA, Raster};
+
+/// Relative tolerance of cell sizes and alignment.
+const TOLERANCE: f64 = 1e-6;
+
+/// The error type that can occur when combining rasters on different grids.
+#[derive(Debug, Error, PartialEq)]
+pub enum AlignmentError {
+    /// Rasters have different cell sizes and have to be resampled first.
+    #[error("rasters have different cell sizes")]
+    CellSize,
+
+    /// Cell centers of the rasters don't lie on the same grid.
+    #[error("rasters are not aligned to the same grid")]
+    Misaligned,
+}
+
+impl<T: Copy + PartialEq> Raster<T> {
+    /// Applies `f` to the value of every cell with data.
+    ///
+    /// Cells without data have `nodata` in the result.
+    pub fn map<U, F>(&self, nodata: U, f: F) -> Raster<U>
+    where
+        U: Copy + PartialEq,
+        F: Fn(T) -> U,
+    {
+        let mut result = self.same_extent(nodata).with_nodata(Some(nodata));
+        for (out, value) in result.data.iter_mut().zip(&self.data) {
+            if self.nodata != Some(*value) {
+                *out = f(*value);
+            }
+        }
+        result
+    }
+}
+
+impl Raster<f32> {
+    /// Combines values of the cells at the same location with `f`.
+    ///
+    /// Rasters have to have the same cell size and cells aligned to the same grid,
+    /// but they can cover different extents. The result covers only the overlap,
+    /// and cells without data in either raster have [`NODATA`].
+    pub fn zip_map<F>(&self, other: &Raster<f32>, f: F) -> Result<Raster<f32>, AlignmentError>
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        let cell_size = self.cell_size;
+        if (other.cell_size - cell_size).abs() > TOLERANCE * cell_size {
+            return Err(AlignmentError::CellSize);
+        }
+
+        // Position of the other raster in cells of this raster.
+        let dc = (other.origin.x - self.origin.x) / cell_size;
+        let dr = (self.origin.y - other.origin.y) / cell_size;
+        if (dc - dc.round()).abs() > TOLERANCE || (dr - dr.round()).abs() > TOLERANCE {
+            return Err(AlignmentError::Misaligned);
+        }
+        let (dr, dc) = (dr.round() as isize, dc.round() as isize);
+
+        let overlap = |offset: isize, len: usize, other_len: usize| {
+            let start = offset.max(0);
+            let end = (offset + other_len as isize).min(len as isize);
+            start as usize..end.max(start) as usize
+        };
+        let rows = overlap(dr, self.nrows, other.nrows);
+        let cols = overlap(dc, self.ncols, other.ncols);
+
+        let origin = self.cell_center(rows.start, cols.start);
+        let mut result = Raster::new(origin, cell_size, cols.len(), rows.len(), NODATA)
+            .with_nodata(Some(NODATA));
+        for (r, row) in rows.enumerate() {
+            for (c, col) in cols.clone().enumerate() {
+                let a = self.get(row, col);
+                let b = other.get((row as isize - dr) as usize, (col as isize - dc) as usize);
+                if let (Some(a), Some(b)) = (a, b) {
+                    result.set(r, c, f(a, b));
+                }
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Returns the smaller value of the cells at the same location.
+    ///
+    /// See [`Raster::zip_map`] for the alignment of the rasters.
+    pub fn min(&self, other: &Raster<f32>) -> Result<Raster<f32>, AlignmentError> {
+        self.zip_map(other, f32::min)
+    }
+
+    /// Returns the larger value of the cells at the same location.
+    ///
+    /// See [`Raster::zip_map`] for the alignment of the rasters.
+    pub fn max(&self, other: &Raster<f32>) -> Result<Raster<f32>, AlignmentError> {
+        self.zip_map(other, f32::max)
+    }
+}
+
+/// Sums the cells at the same location, see [`Raster::zip_map`].
+impl Add for &Raster<f32> {
+    type Output = Result<Raster<f32>, AlignmentError>;
+
+    fn add(self, other: Self) -> Self::Output {
+        self.zip_map(other, |a, b| a + b)
+    }
+}
+
+/// Subtracts the cells at the same location, see [`Raster::zip_map`].
+///
+/// For example, canopy height is the surface model minus the terrain model.
+impl Sub for &Raster<f32> {
+    type Output = Result<Raster<f32>, AlignmentError>;
+
+    fn sub(self, other: Self) -> Self::Output {
+        self.zip_map(other, |a, b| a - b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{AlignmentError, Raster},
+    };
+
+    fn raster(x: f64, y: f64, value: f32) -> Raster {
+        let origin = Point { x, y, data: () };
+        Raster::new(origin, 1.0, 4, 3, value).with_nodata(Some(-1.0))
+    }
+
+    #[test]
+    fn algebra_on_overlap() {
+        let mut dsm = raster(0.0, 10.0, 320.0);
+        dsm.set(0, 2, -1.0);
+        let dem = raster(1.0, 9.0, 300.0);
+
+        let canopy = (&dsm - &dem).unwrap();
+        assert_eq!((canopy.ncols(), canopy.nrows()), (3, 2));
+        assert_eq!(canopy.origin().x, 1.0);
+        assert_eq!(canopy.origin().y, 9.0);
+        assert_eq!(canopy.get(0, 0), Some(20.0));
+        assert_eq!(canopy.value_at(2.0, 10.0), None);
+
+        assert_eq!((&dsm + &dem).unwrap().get(1, 2), Some(620.0));
+        assert_eq!(dsm.max(&dem).unwrap().get(0, 0), Some(320.0));
+        assert_eq!(dsm.min(&dem).unwrap().get(0, 0), Some(300.0));
+        assert_eq!(dsm.map(0u8, |v| (v > 310.0) as u8).get(0, 2), None);
+
+        assert_eq!(
+            dsm.zip_map(&raster(0.5, 10.0, 0.0), |a, _| a),
+            Err(AlignmentError::Misaligned)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Contour lines extracted with marching squares.
+
+use std::collections::HashMap;
+
+use crate::Point;
+
+use super::Raster;
+
+/// Contour line, which connects locations with the same elevation.
+///
+/// Closed lines have the same first and last point.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineString {
+    /// Elevation of the line in meters.
+    pub elevation: f64,
+    pub points: Vec<Point>,
+}
+
+impl LineString {
+    /// Returns weather the line is closed.
+    pub fn is_closed(&self) -> bool {
+        self.points.len() > 2 && self.points.first() == self.points.last()
+    }
+
+    /// Smooths the line with Chaikin's corner cutting algorithm.
+    ///
+    /// Every iteration replaces each corner with two points at a quarter of the
+    /// adjacent segments. End points of open lines are kept in place.
+    pub fn smooth(&self, iterations: usize) -> Self {
+        let closed = self.is_closed();
+        let mut points = self.points.clone();
+        for _ in 0..iterations {
+            if points.len() < 3 {
+                break;
+            }
+
+            let mut smoothed = Vec::with_capacity(points.len() * 2);
+            if !closed {
+                smoothed.push(points[0].clone());
+            }
+            for segment in points.windows(2) {
+                let (a, b) = (&segment[0], &segment[1]);
+                smoothed.push(lerp(a, b, 0.25));
+                smoothed.push(lerp(a, b, 0.75));
+            }
+            if closed {
+                smoothed.push(smoothed[0].clone());
+            } else {
+                smoothed.push(points[points.len() - 1].clone());
+            }
+
+            points = smoothed;
+        }
+
+        Self {
+            elevation: self.elevation,
+            points,
+        }
+    }
+}
+
+/// Edge between two neighbouring cell centers, identified by the first cell
+/// and weather it goes to the east or to the south neighbour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Edge {
+    East(usize, usize),
+    South(usize, usize),
+}
+
+/// Extracts contour lines at multiples of `interval` meters.
+///
+/// Values of the raster are taken at the centers of the cells. Squares between
+/// the centers that touch a cell without data are skipped, which ends the lines
+/// at the gaps in the data. Lines are not oriented.
+///
+/// Every level scans the whole raster, so the number of levels between the
+/// lowest and the highest value is limited to the number of cells.
+///
+/// # Panics
+///
+/// Panics if `interval` is not positive and finite, or if it gives more levels than cells.
+pub fn contours(raster: &Raster<f32>, interval: f64) -> Vec<LineString> {
+    assert!(
+        interval > 0.0 && interval.is_finite(),
+        "contour interval must be positive and finite"
+    );
+
+    let (min, max) = raster
+        .data()
+        .iter()
+        .filter(|&&v| raster.nodata() != Some(v))
+        .fold((f64::MAX, f64::MIN), |(min, max), &v| {
+            (min.min(v as f64), max.max(v as f64))
+        });
+    if min > max {
+        return vec![];
+    }
+
+    // Levels are computed from their index, since adding up the interval
+    // accumulates rounding errors and stalls for intervals below the precision.
+    let first = (min / interval).ceil() as i64;
+    let last = (max / interval).floor() as i64;
+    let levels = last.saturating_sub(first).saturating_add(1);
+    assert!(
+        levels <= raster.data().len() as i64,
+        "contour interval {interval} gives more levels than cells"
+    );
+
+    let mut lines = vec![];
+    for i in first..=last {
+        lines.extend(contours_at(raster, i as f64 * interval));
+    }
+
+    lines
+}
+
+/// Extracts contour lines at the given level.
+pub(crate) fn contours_at(raster: &Raster<f32>, level: f64) -> Vec<LineString> {
+    let value = |row, col| raster.get(row, col).map(|v| v as f64);
+    let point_on = |edge: Edge| {
+        let (from, to, (v1, v2)) = match edge {
+            Edge::East(r, c) => (
+                raster.cell_center(r, c),
+                raster.cell_center(r, c + 1),
+                (value(r, c), value(r, c + 1)),
+            ),
+            Edge::South(r, c) => (
+                raster.cell_center(r, c),
+                raster.cell_center(r + 1, c),
+                (value(r, c), value(r + 1, c)),
+            ),
+        };
+        let (v1, v2) = (v1.unwrap(), v2.unwrap());
+        lerp(&from, &to, (level - v1) / (v2 - v1))
+    };
+
+    let mut segments = vec![];
+    for row in 0..raster.nrows().saturating_sub(1) {
+        for col in 0..raster.ncols().saturating_sub(1) {
+            let corners = [
+                value(row, col),
+                value(row, col + 1),
+                value(row + 1, col + 1),
+                value(row + 1, col),
+            ];
+            let [Some(tl), Some(tr), Some(br), Some(bl)] = corners else {
+                continue;
+            };
+
+            let top = Edge::East(row, col);
+            let bottom = Edge::East(row + 1, col);
+            let left = Edge::South(row, col);
+            let right = Edge::South(row, col + 1);
+
+            let case = [tl, tr, br, bl]
+                .iter()
+                .fold(0, |case, &v| (case << 1) | (v >= level) as u8);
+            let center_above = (tl + tr + br + bl) / 4.0 >= level;
+            match case {
+                0 | 15 => (),
+                1 | 14 => segments.push((left, bottom)),
+                2 | 13 => segments.push((bottom, right)),
+                3 | 12 => segments.push((left, right)),
+                4 | 11 => segments.push((top, right)),
+                6 | 9 => segments.push((top, bottom)),
+                7 | 8 => segments.push((left, top)),
+                // Saddles are resolved by the value in the center of the square.
+                5 if center_above => segments.extend([(left, top), (bottom, right)]),
+                5 => segments.extend([(top, right), (left, bottom)]),
+                10 if center_above => segments.extend([(top, right), (left, bottom)]),
+                10 => segments.extend([(left, top), (bottom, right)]),
+                _ => unreachable!(),
+            }
+        }
+    }
+
+    // Every edge is shared by at most two segments, which are joined into lines.
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (i, (a, b)) in segments.iter().enumerate() {
+        by_edge.entry(*a).or_default().push(i);
+        by_edge.entry(*b).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    let mut lines = vec![];
+    let trace = |start: Edge, used: &mut [bool]| {
+        let mut edges = vec![start];
+        let mut current = start;
+        while let Some(&i) = by_edge[&current].iter().find(|&&i| !used[i]) {
+            used[i] = true;
+            let (a, b) = segments[i];
+            current = if a == current { b } else { a };
+            edges.push(current);
+        }
+
+        LineString {
+            elevation: level,
+            points: edges.into_iter().map(point_on).collect(),
+        }
+    };
+
+    // Open lines start at edges with a single segment, remaining segments form closed lines.
+    let mut starts: Vec<_> = by_edge
+        .iter()
+        .filter(|(_, segments)| segments.len() == 1)
+        .map(|(edge, _)| *edge)
+        .collect();
+    // Hash map order is random, so the starts are sorted to keep the output deterministic.
+    starts.sort_by_key(|edge| match *edge {
+        Edge::East(r, c) => (r, c, 0),
+        Edge::South(r, c) => (r, c, 1),
+    });
+    for start in starts {
+        if by_edge[&start].iter().any(|&i| !used[i]) {
+            lines.push(trace(start, &mut used));
+        }
+    }
+    for i in 0..segments.len() {
+        if !used[i] {
+            lines.push(trace(segments[i].0, &mut used));
+        }
+    }
+
+    // Level equal to a peak produces lines that collapse into a single point.
+    lines.retain(|line| line.points.windows(2).any(|w| w[0] != w[1]));
+    lines
+}
+
+/// Linearly interpolates between two points.
+fn lerp(a: &Point, b: &Point, t: f64) -> Point {
+    Point {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        data: (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::contours;
+
+    #[test]
+    fn contours_of_hill() {
+        // Cone shaped hill with the top in the center.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 100.0,
+                data: (),
+            },
+            10.0,
+            11,
+            11,
+            0.0,
+        );
+        for row in 0..11 {
+            for col in 0..11 {
+                let p = raster.cell_center(row, col);
+                let distance = p.distance(&Point {
+                    x: 50.0,
+                    y: 50.0,
+                    data: (),
+                });
+                raster.set(row, col, (100.0 - distance) as f32);
+            }
+        }
+
+        let lines = contours(&raster, 25.0);
+        let elevations: Vec<_> = lines.iter().map(|l| l.elevation).collect();
+        assert_eq!(elevations, [50.0, 50.0, 50.0, 50.0, 75.0]);
+
+        // The lowest contour is cut by the edges of the raster.
+        assert!(lines[..4].iter().all(|l| !l.is_closed()));
+
+        // Contours around the hill are closed.
+        let line = &lines[4];
+        assert!(line.is_closed());
+        for p in &line.points {
+            let distance = p.distance(&Point {
+                x: 50.0,
+                y: 50.0,
+                data: (),
+            });
+            assert!((distance - 25.0).abs() < 1.5, "{distance}");
+        }
+
+        let smooth = line.smooth(2);
+        assert!(smooth.is_closed());
+        assert_eq!(smooth.points.len(), (line.points.len() - 1) * 4 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "more levels than cells")]
+    fn contours_with_tiny_interval() {
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 10.0,
+                data: (),
+            },
+            10.0,
+            2,
+            2,
+            0.0,
+        );
+        raster.set(1, 1, 3000.0);
+
+        contours(&raster, 1e-9);
+    }
+}
@@ -0,0 +1,145 @@
+//! Conversions between rasters and quad trees.
+
+use super::{NODATA, Raster};
+use crate::{Point, qtree::QuadTree};
+
+/// How points falling into the same cell are combined by [`Raster::from_qtree`],
+/// or cells of an overview by [`Raster::build_pyramid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aggregation {
+    #[default]
+    Mean,
+    Min,
+    Max,
+    /// Value of the point nearest to the center of the cell.
+    Nearest,
+}
+
+impl Raster<f32> {
+    /// Creates a raster from all points of the tree, which contains heights as data.
+    ///
+    /// Cells are aligned the same way as in [`Raster::from_points`], but any number
+    /// of points can fall into a cell and their heights are combined with
+    /// the aggregation. Cells without a point have [`NODATA`].
+    pub fn from_qtree(tree: &QuadTree<f32>, cell_size: f64, aggregation: Aggregation) -> Self {
+        let (min_x, max_y, max_x, min_y) = tree.iter().fold(
+            (f64::MAX, f64::MIN, f64::MIN, f64::MAX),
+            |(min_x, max_y, max_x, min_y), p| {
+                (
+                    min_x.min(p.x),
+                    max_y.max(p.y),
+                    max_x.max(p.x),
+                    min_y.min(p.y),
+                )
+            },
+        );
+        let origin = Point {
+            x: min_x,
+            y: max_y,
+            data: (),
+        };
+        if tree.size() == 0 {
+            return Self::new(origin, cell_size, 0, 0, NODATA).with_nodata(Some(NODATA));
+        }
+
+        let ncols = ((max_x - min_x) / cell_size).round() as usize + 1;
+        let nrows = ((max_y - min_y) / cell_size).round() as usize + 1;
+        let mut raster =
+            Self::new(origin, cell_size, ncols, nrows, NODATA).with_nodata(Some(NODATA));
+
+        // Accumulated value and count, or distance to the center for the nearest point.
+        let mut cells = vec![(0.0f64, 0.0f64); ncols * nrows];
+        for p in tree.iter() {
+            let col = (((p.x - min_x) / cell_size).round() as usize).min(ncols - 1);
+            let row = (((max_y - p.y) / cell_size).round() as usize).min(nrows - 1);
+            let idx = row * ncols + col;
+            let value = p.data as f64;
+
+            let (acc, count) = &mut cells[idx];
+            let first = *count == 0.0;
+            match aggregation {
+                Aggregation::Mean => *acc += value,
+                Aggregation::Min if first || value < *acc => *acc = value,
+                Aggregation::Max if first || value > *acc => *acc = value,
+                Aggregation::Nearest => {
+                    let distance = p.distance_sq(&raster.cell_center(row, col));
+                    if first || distance < *acc {
+                        *acc = distance;
+                        raster.data[idx] = p.data;
+                    }
+                }
+                _ => (),
+            }
+            *count += 1.0;
+        }
+
+        for (value, (acc, count)) in raster.data.iter_mut().zip(cells) {
+            if count == 0.0 {
+                continue;
+            }
+            match aggregation {
+                Aggregation::Mean => *value = (acc / count) as f32,
+                Aggregation::Min | Aggregation::Max => *value = acc as f32,
+                Aggregation::Nearest => (),
+            }
+        }
+
+        raster
+    }
+}
+
+impl QuadTree<f32> {
+    /// Creates a tree with a point at the center of every cell with data.
+    pub fn from_raster(raster: &Raster<f32>) -> Self {
+        let mut points = Vec::with_capacity(raster.data.len());
+        for row in 0..raster.nrows {
+            for col in 0..raster.ncols {
+                if let Some(value) = raster.get(row, col) {
+                    let center = raster.cell_center(row, col);
+                    points.push(Point {
+                        x: center.x,
+                        y: center.y,
+                        data: value,
+                    });
+                }
+            }
+        }
+
+        Self::from_points(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{Aggregation, Raster},
+        qtree::QuadTree,
+    };
+
+    #[test]
+    fn raster_qtree_roundtrip() {
+        let points: Vec<_> = (0..12)
+            .filter(|&i| i != 5)
+            .map(|i| Point {
+                x: 1000.0 + (i % 4) as f64 * 5.0,
+                y: 2000.0 + (i / 4) as f64 * 5.0,
+                data: i as f32,
+            })
+            .collect();
+        let raster = Raster::from_points(&points, 5.0);
+
+        let tree = QuadTree::from_raster(&raster);
+        assert_eq!(tree.size(), 11);
+        assert_eq!(Raster::from_qtree(&tree, 5.0, Aggregation::Nearest), raster);
+
+        // Cell in the second row and column holds points 1, 2 and 6.
+        let coarse = Raster::from_qtree(&tree, 10.0, Aggregation::Mean);
+        assert_eq!((coarse.ncols(), coarse.nrows()), (3, 2));
+        assert_eq!(coarse.get(1, 1), Some(3.0));
+        let max = Raster::from_qtree(&tree, 10.0, Aggregation::Max);
+        assert_eq!(max.get(1, 1), Some(6.0));
+        let min = Raster::from_qtree(&tree, 10.0, Aggregation::Min);
+        assert_eq!(min.get(1, 1), Some(1.0));
+    }
+}
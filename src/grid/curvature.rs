@@ -0,0 +1,107 @@
+//! Profile and plan curvature of the terrain.
+
+use super::{NODATA, Raster};
+
+/// Curvature of the terrain in `1/m`, returned by [`Raster::curvature`].
+///
+/// Positive values are convex and negative values are concave.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curvature {
+    /// Curvature in the direction of the steepest slope, which
+    /// controls acceleration and deceleration of the flow.
+    pub profile: Raster<f32>,
+    /// Curvature of the contour lines, which controls
+    /// divergence and convergence of the flow.
+    pub plan: Raster<f32>,
+}
+
+impl Raster<f32> {
+    /// Computes profile and plan curvature of every cell.
+    ///
+    /// Derivatives are estimated from the 3x3 window with the method of
+    /// Zevenbergen and Thorne. Curvature is zero on flat cells, which have
+    /// no slope direction, and cells without data have [`NODATA`].
+    pub fn curvature(&self) -> Curvature {
+        let empty = self.same_extent(NODATA).with_nodata(Some(NODATA));
+        let mut curvature = Curvature {
+            profile: empty.clone(),
+            plan: empty,
+        };
+
+        let l2 = self.cell_size * self.cell_size;
+        for row in 0..self.nrows {
+            for col in 0..self.ncols {
+                let Some([a, b, c, d, e, f, g, h, i]) = self.window(row, col) else {
+                    continue;
+                };
+
+                // Derivatives with x axis pointing east and y axis pointing north.
+                let p = (f - d) / (2.0 * self.cell_size);
+                let q = (b - h) / (2.0 * self.cell_size);
+                let r = (d - 2.0 * e + f) / l2;
+                let t = (b - 2.0 * e + h) / l2;
+                let s = (c + g - a - i) / (4.0 * l2);
+
+                let gradient_sq = p * p + q * q;
+                let (profile, plan) = if gradient_sq < 1e-12 {
+                    (0.0, 0.0)
+                } else {
+                    (
+                        -(p * p * r + 2.0 * p * q * s + q * q * t)
+                            / (gradient_sq * (1.0 + gradient_sq).powf(1.5)),
+                        -(q * q * r - 2.0 * p * q * s + p * p * t) / gradient_sq.powf(1.5),
+                    )
+                };
+                curvature.profile.set(row, col, profile as f32);
+                curvature.plan.set(row, col, plan as f32);
+            }
+        }
+
+        curvature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    #[test]
+    fn curvature_of_dome() {
+        // Dome z = -(x^2 + y^2) / 100 on a 1 m grid, centered at the origin.
+        let mut raster = Raster::new(
+            Point {
+                x: -20.0,
+                y: 20.0,
+                data: (),
+            },
+            1.0,
+            41,
+            41,
+            0.0,
+        );
+        for row in 0..41 {
+            for col in 0..41 {
+                let center = raster.cell_center(row, col);
+                let z = -(center.x * center.x + center.y * center.y) / 100.0;
+                raster.set(row, col, z as f32);
+            }
+        }
+
+        let curvature = raster.curvature();
+
+        // Contours are circles with radius of the distance from the top.
+        let plan = curvature.plan.value_at(10.0, 0.0).unwrap();
+        assert!((plan - 0.1).abs() < 1e-4, "{plan}");
+        let plan = curvature.plan.value_at(-5.0, 0.0).unwrap();
+        assert!((plan - 0.2).abs() < 1e-4, "{plan}");
+
+        let slope_sq = 0.2f32 * 0.2;
+        let profile = curvature.profile.value_at(10.0, 0.0).unwrap();
+        assert!(
+            (profile - 0.02 / (1.0 + slope_sq).powf(1.5)).abs() < 1e-4,
+            "{profile}"
+        );
+
+        assert_eq!(curvature.plan.value_at(0.0, 0.0), Some(0.0));
+    }
+}
@@ -0,0 +1,75 @@
+//! Removing the regional trend of the terrain.
+
+use crate::{Plane, PlaneSums};
+
+use super::Raster;
+
+impl Raster<f32> {
+    /// Fits a plane to the centers of the cells with data, with the least squares method.
+    ///
+    /// None is returned if there are less than three cells with data or if they all lie on a line.
+    pub fn fit_plane(&self) -> Option<Plane> {
+        let mut sums = PlaneSums::default();
+        for row in 0..self.nrows {
+            for col in 0..self.ncols {
+                if let Some(z) = self.get(row, col) {
+                    let center = self.cell_center(row, col);
+                    sums.add(center.x, center.y, z as f64);
+                }
+            }
+        }
+
+        sums.solve()
+    }
+
+    /// Subtracts the fitted plane from the terrain, leaving the local relief,
+    /// such as terraces and mounds, with the regional slope removed.
+    ///
+    /// Cells without data have [`NODATA`](super::NODATA). None is returned if
+    /// [`Raster::fit_plane`] can't fit the plane.
+    pub fn detrend(&self) -> Option<Raster<f32>> {
+        let plane = self.fit_plane()?;
+        Some(self.focal(0, |w| {
+            let (row, col) = w.position();
+            let center = self.cell_center(row, col);
+            Some((w.center() as f64 - plane.height_at(center.x, center.y)) as f32)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{NODATA, Raster},
+    };
+
+    #[test]
+    fn detrend_removes_slope() {
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 40.0,
+                data: (),
+            },
+            10.0,
+            5,
+            5,
+            0.0,
+        );
+        for row in 0..5 {
+            for col in 0..5 {
+                // Rises 1 m per 10 m towards north.
+                raster.set(row, col, 100.0 - row as f32);
+            }
+        }
+
+        let plane = raster.fit_plane().unwrap();
+        assert!(plane.a.abs() < 1e-9);
+        assert!((plane.b - 0.1).abs() < 1e-9);
+
+        let flat = raster.detrend().unwrap();
+        assert!(flat.data().iter().all(|z| z.abs() < 1e-4));
+        assert_eq!(flat.nodata(), Some(NODATA));
+    }
+}
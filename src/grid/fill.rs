@@ -0,0 +1,143 @@
+//! Interpolation of holes in rasters.
+
+use super::Raster;
+use crate::{Point, interpolate::IdwSum, qtree::QuadTree, tin::Tin};
+
+/// Number of border cells used by [`FillMethod::Idw`].
+const IDW_NEIGHBOURS: usize = 8;
+
+/// Interpolation used by [`Raster::fill_gaps`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillMethod {
+    /// Value of the nearest cell with data.
+    Nearest,
+    /// Inverse distance weighting of the nearest cells on the edge of the gap.
+    #[default]
+    Idw,
+    /// Linear interpolation over the triangulation of the cells on the edges of the gaps,
+    /// which gives flat surfaces, for example over lakes.
+    /// Cells outside of the triangulation are not filled.
+    Tin,
+}
+
+impl Raster<f32> {
+    /// Interpolates values of cells without data, which are at most
+    /// `max_distance` meters away from a cell with data.
+    ///
+    /// Only cells on the edges of the gaps are used for the interpolation.
+    /// The distance limit keeps large areas without data, such as the
+    /// surroundings of the country, empty, while small holes from water
+    /// bodies or LiDAR dropouts are filled. Rasters without a nodata value
+    /// have no gaps and are returned unchanged.
+    pub fn fill_gaps(&self, method: FillMethod, max_distance: f64) -> Raster<f32> {
+        let mut result = self.clone();
+        if self.nodata.is_none() {
+            return result;
+        }
+
+        let gaps: Vec<_> = (0..self.nrows)
+            .flat_map(|row| (0..self.ncols).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.get(row, col).is_none())
+            .collect();
+        let edges: Vec<_> = (0..self.nrows)
+            .flat_map(|row| (0..self.ncols).map(move |col| (row, col)))
+            .filter_map(|(row, col)| {
+                let value = self.get(row, col)?;
+                self.borders_gap(row, col).then(|| {
+                    let center = self.cell_center(row, col);
+                    Point {
+                        x: center.x,
+                        y: center.y,
+                        data: value,
+                    }
+                })
+            })
+            .collect();
+        if gaps.is_empty() || edges.is_empty() {
+            return result;
+        }
+
+        let tin = (method == FillMethod::Tin).then(|| Tin::new(edges.clone()));
+        let tree = QuadTree::from_points(edges);
+        let mut neighbours = Vec::with_capacity(IDW_NEIGHBOURS);
+        for (row, col) in gaps {
+            let center = self.cell_center(row, col);
+            let Some(nearest) = tree.nearest_within(&center, max_distance) else {
+                continue;
+            };
+
+            let value = match (&tin, method) {
+                (Some(tin), _) => tin.elevation_at(center.x, center.y),
+                (_, FillMethod::Idw) => {
+                    neighbours.clear();
+                    tree.nearest_k(&center, IDW_NEIGHBOURS, &mut neighbours);
+                    let mut sum = IdwSum::new(&center, 2.0, f64::INFINITY);
+                    sum.extend(neighbours.iter().copied());
+                    sum.value()
+                }
+                _ => Some(nearest.data),
+            };
+            if let Some(value) = value {
+                result.set(row, col, value);
+            }
+        }
+
+        result
+    }
+
+    /// Returns weather any of the eight neighbours of the cell has no data.
+    fn borders_gap(&self, row: usize, col: usize) -> bool {
+        (row.saturating_sub(1)..=(row + 1).min(self.nrows - 1)).any(|r| {
+            (col.saturating_sub(1)..=(col + 1).min(self.ncols - 1))
+                .any(|c| self.get(r, c).is_none())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{FillMethod, NODATA, Raster},
+    };
+
+    #[test]
+    fn fill_small_holes() {
+        // Plane rising towards east, with a lake in the middle
+        // and no data in the east.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 100.0,
+                data: (),
+            },
+            1.0,
+            30,
+            20,
+            NODATA,
+        )
+        .with_nodata(Some(NODATA));
+        for row in 0..20 {
+            for col in 0..20 {
+                let lake = (8..12).contains(&row) && (8..12).contains(&col);
+                if !lake {
+                    raster.set(row, col, col as f32);
+                }
+            }
+        }
+
+        for method in [FillMethod::Nearest, FillMethod::Idw, FillMethod::Tin] {
+            let filled = raster.fill_gaps(method, 3.0);
+            let value = filled.get(9, 10).unwrap();
+            assert!((value - 10.0).abs() <= 2.0, "{method:?} {value}");
+            assert!(filled.get(9, 9).is_some());
+
+            // Far away from the data stays empty. Triangulation doesn't extrapolate.
+            assert_eq!(filled.get(10, 21).is_some(), method != FillMethod::Tin);
+            assert_eq!(filled.get(10, 29), None);
+        }
+
+        let tin = raster.fill_gaps(FillMethod::Tin, 3.0).get(9, 10).unwrap();
+        assert!((tin - 10.0).abs() < 1e-4, "{tin}");
+    }
+}
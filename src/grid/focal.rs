@@ -0,0 +1,222 @@
+//! Moving window operations over rasters.
+
+use super::{NODATA, Raster};
+
+/// Square neighbourhood of a cell, with `2 * radius + 1` cells on each side,
+/// passed to [`Raster::focal`].
+pub struct Window<'a> {
+    raster: &'a Raster<f32>,
+    row: usize,
+    col: usize,
+    radius: usize,
+    center: f32,
+}
+
+impl Window<'_> {
+    /// Returns the value of the center cell.
+    pub fn center(&self) -> f32 {
+        self.center
+    }
+
+    /// Returns `(row, col)` of the center cell in the raster.
+    pub fn position(&self) -> (usize, usize) {
+        (self.row, self.col)
+    }
+
+    /// Returns the number of cells between the center and the edge of the window.
+    pub fn radius(&self) -> usize {
+        self.radius
+    }
+
+    /// Returns the width and height of a cell in meters.
+    pub fn cell_size(&self) -> f64 {
+        self.raster.cell_size
+    }
+
+    /// Returns the value at the offset from the center in cells, with rows
+    /// growing towards south. None is returned for cells without data,
+    /// outside of the raster or outside of the window.
+    pub fn get(&self, dr: isize, dc: isize) -> Option<f32> {
+        let radius = self.radius as isize;
+        if dr.abs() > radius || dc.abs() > radius {
+            return None;
+        }
+
+        let row = self.row.checked_add_signed(dr)?;
+        let col = self.col.checked_add_signed(dc)?;
+        self.raster.get(row, col)
+    }
+
+    /// Returns `(dr, dc, value)` of the neighbours with data, excluding the center.
+    pub fn neighbours(&self) -> impl Iterator<Item = (isize, isize, f32)> + '_ {
+        let radius = self.radius as isize;
+        (-radius..=radius)
+            .flat_map(move |dr| (-radius..=radius).map(move |dc| (dr, dc)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(|(dr, dc)| Some((dr, dc, self.get(dr, dc)?)))
+    }
+}
+
+impl Raster<f32> {
+    /// Computes a new raster by calling `f` on the window of `radius` cells
+    /// around every cell with data.
+    ///
+    /// Neighbours outside of the raster or without data are missing from the window,
+    /// so `f` decides how to handle the edges. Cells without data, and cells where
+    /// `f` returns None, have [`NODATA`]. With the `rayon` feature, rows are
+    /// processed on multiple threads.
+    pub fn focal<F>(&self, radius: usize, f: F) -> Raster<f32>
+    where
+        F: Fn(&Window) -> Option<f32> + Sync,
+    {
+        let mut result = self.same_extent(NODATA).with_nodata(Some(NODATA));
+        if self.ncols == 0 {
+            return result;
+        }
+
+        let fill_row = |row: usize, out: &mut [f32]| {
+            for (col, value) in out.iter_mut().enumerate() {
+                let Some(center) = self.get(row, col) else {
+                    continue;
+                };
+                let window = Window {
+                    raster: self,
+                    row,
+                    col,
+                    radius,
+                    center,
+                };
+                if let Some(v) = f(&window) {
+                    *value = v;
+                }
+            }
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            result
+                .data
+                .par_chunks_mut(self.ncols)
+                .enumerate()
+                .for_each(|(row, out)| fill_row(row, out));
+        }
+        #[cfg(not(feature = "rayon"))]
+        for (row, out) in result.data.chunks_mut(self.ncols).enumerate() {
+            fill_row(row, out);
+        }
+
+        result
+    }
+
+    /// Computes the terrain ruggedness index (TRI) of Riley et al. in meters.
+    ///
+    /// It is the square root of the sum of squared height differences between
+    /// the cell and its eight neighbours. Missing neighbours are skipped.
+    pub fn tri(&self) -> Raster<f32> {
+        self.focal(1, |w| {
+            let sum: f64 = w
+                .neighbours()
+                .map(|(_, _, v)| (v as f64 - w.center() as f64).powi(2))
+                .sum();
+            Some(sum.sqrt() as f32)
+        })
+    }
+
+    /// Computes the topographic position index (TPI) in meters.
+    ///
+    /// It is the difference between the height of the cell and the mean height
+    /// of the cells within `radius` meters, so ridges are positive and valleys
+    /// negative. Cells without any neighbour have [`NODATA`].
+    pub fn tpi(&self, radius: f64) -> Raster<f32> {
+        let cells = (radius / self.cell_size).floor().max(1.0) as usize;
+        let radius_sq = (radius / self.cell_size).powi(2).max(1.0);
+        self.focal(cells, |w| {
+            let (sum, count) = w
+                .neighbours()
+                .filter(|(dr, dc, _)| (dr * dr + dc * dc) as f64 <= radius_sq)
+                .fold((0.0, 0), |(sum, count), (_, _, v)| {
+                    (sum + v as f64, count + 1)
+                });
+            (count > 0).then(|| (w.center() as f64 - sum / count as f64) as f32)
+        })
+    }
+
+    /// Computes roughness, which is the difference between the highest
+    /// and the lowest cell in the 3x3 window, in meters.
+    pub fn roughness(&self) -> Raster<f32> {
+        self.focal(1, |w| {
+            let (min, max) = w
+                .neighbours()
+                .fold((w.center(), w.center()), |(min, max), (_, _, v)| {
+                    (min.min(v), max.max(v))
+                });
+            Some(max - min)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    #[test]
+    fn ruggedness_of_peak() {
+        // Flat terrain with a single 10 m peak in the middle.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            5.0,
+            7,
+            7,
+            100.0,
+        );
+        raster.set(3, 3, 110.0);
+
+        let tri = raster.tri();
+        assert_eq!(tri.get(3, 3), Some((8.0f32 * 100.0).sqrt()));
+        assert_eq!(tri.get(2, 2), Some(10.0));
+        assert_eq!(tri.get(0, 0), Some(0.0));
+
+        let roughness = raster.roughness();
+        assert_eq!(roughness.get(3, 3), Some(10.0));
+        assert_eq!(roughness.get(4, 2), Some(10.0));
+        assert_eq!(roughness.get(5, 5), Some(0.0));
+
+        // Within 5 m are only the four direct neighbours.
+        let tpi = raster.tpi(5.0);
+        assert_eq!(tpi.get(3, 3), Some(10.0));
+        assert_eq!(tpi.get(3, 4), Some(-2.5));
+        assert_eq!(tpi.get(2, 2), Some(0.0));
+    }
+
+    #[test]
+    fn custom_focal_operation() {
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            5,
+            4,
+            1.0,
+        )
+        .with_nodata(Some(-1.0));
+        raster.set(0, 0, -1.0);
+
+        // Number of neighbours with data within two cells.
+        let count = raster.focal(2, |w| Some(w.neighbours().count() as f32));
+        assert_eq!(count.get(2, 2), Some(18.0));
+        assert_eq!(count.get(1, 1), Some(14.0));
+        assert_eq!(count.get(0, 0), None);
+
+        let edges = raster.focal(1, |w| w.get(-1, 0).map(|_| w.center()));
+        assert_eq!(edges.get(0, 3), None);
+        assert_eq!(edges.get(1, 3), Some(1.0));
+    }
+}
@@ -0,0 +1,96 @@
+//! Export of rasters in GeoTIFF format.
+
+use std::io;
+
+use tiff::{
+    TiffResult,
+    encoder::{TiffEncoder, colortype::Gray32Float},
+    tags::Tag,
+};
+
+use super::Raster;
+
+/// EPSG code of D96/TM.
+const EPSG_D96_TM: u16 = 3794;
+
+/// GeoTIFF keys as `(id, value)`, stored directly in the key directory.
+const GEO_KEYS: [(u16, u16); 4] = [
+    // GTModelTypeGeoKey: projected.
+    (1024, 1),
+    // GTRasterTypeGeoKey: pixels are areas.
+    (1025, 1),
+    // ProjectedCSTypeGeoKey.
+    (3072, EPSG_D96_TM),
+    // ProjLinearUnitsGeoKey: meters.
+    (3076, 9001),
+];
+
+/// Writes the raster as a single band, 32-bit float GeoTIFF in D96/TM (EPSG:3794).
+///
+/// Nodata value of the raster is written in the GDAL nodata tag,
+/// which is understood by QGIS and GDAL, and by [`import_geotiff`](crate::data::import_geotiff).
+pub fn write_geotiff(writer: impl io::Write + io::Seek, raster: &Raster<f32>) -> TiffResult<()> {
+    let mut encoder = TiffEncoder::new(writer)?;
+    let mut image = encoder.new_image::<Gray32Float>(raster.ncols as u32, raster.nrows as u32)?;
+
+    let half = raster.cell_size / 2.0;
+    let (west, north) = (raster.origin.x - half, raster.origin.y + half);
+    let directory = image.encoder();
+    directory.write_tag(
+        Tag::ModelPixelScaleTag,
+        &[raster.cell_size, raster.cell_size, 0.0][..],
+    )?;
+    directory.write_tag(
+        Tag::ModelTiepointTag,
+        &[0.0, 0.0, 0.0, west, north, 0.0][..],
+    )?;
+
+    let mut keys = vec![1, 1, 0, GEO_KEYS.len() as u16];
+    for (id, value) in GEO_KEYS {
+        keys.extend([id, 0, 1, value]);
+    }
+    directory.write_tag(Tag::GeoKeyDirectoryTag, &keys[..])?;
+
+    if let Some(nodata) = raster.nodata {
+        directory.write_tag(Tag::GdalNodata, nodata.to_string().as_str())?;
+    }
+
+    image.write_data(&raster.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::{
+        Point,
+        data::{import_geotiff, read_points},
+        grid::{NODATA, Raster},
+    };
+
+    use super::write_geotiff;
+
+    #[test]
+    fn geotiff_roundtrip() {
+        let points: Vec<_> = (0..12)
+            .filter(|&i| i != 5)
+            .map(|i| Point {
+                x: 460_000.0 + (i % 4) as f64 * 5.0,
+                y: 100_000.0 + (i / 4) as f64 * 5.0,
+                data: i as f32 + 0.25,
+            })
+            .collect();
+        let raster = Raster::from_points(&points, 5.0);
+        assert_eq!(raster.nodata(), Some(NODATA));
+
+        let mut tiff = Cursor::new(vec![]);
+        write_geotiff(&mut tiff, &raster).unwrap();
+
+        tiff.set_position(0);
+        let mut out = vec![];
+        import_geotiff(tiff, &mut out).unwrap();
+        let mut imported = read_points(out.as_slice()).unwrap();
+        imported.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x)));
+        assert_eq!(imported, points);
+    }
+}
@@ -0,0 +1,62 @@
+//! Derivatives of the surface, which are shared by the terrain analyses.
+
+use super::Raster;
+
+/// Method used to estimate the gradient of the surface from the neighbouring cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GradientMethod {
+    /// Horn's method, which uses all eight neighbours.
+    /// It is more robust to noise and suited for rough terrain.
+    #[default]
+    Horn,
+    /// Zevenbergen and Thorne's method, which uses only the four direct neighbours.
+    /// It is better suited for smooth terrain.
+    ZevenbergenThorne,
+}
+
+impl Raster<f32> {
+    /// Returns the 3x3 window around the cell in row-major order.
+    ///
+    /// Missing neighbours, either outside of the raster or without data, are
+    /// replaced with the value of the center, so that edges can be analyzed.
+    /// None is returned if the center has no data.
+    pub(super) fn window(&self, row: usize, col: usize) -> Option<[f64; 9]> {
+        let center = self.get(row, col)? as f64;
+
+        let mut window = [center; 9];
+        for (i, value) in window.iter_mut().enumerate() {
+            let (r, c) = ((row + i / 3).checked_sub(1), (col + i % 3).checked_sub(1));
+            if let (Some(r), Some(c)) = (r, c)
+                && let Some(v) = self.get(r, c)
+            {
+                *value = v as f64;
+            }
+        }
+
+        Some(window)
+    }
+
+    /// Returns the gradient `(dz/dx, dz/dy)` of the cell.
+    ///
+    /// X axis points east and y axis points south, following the order of rows.
+    pub(super) fn gradient(
+        &self,
+        row: usize,
+        col: usize,
+        method: GradientMethod,
+    ) -> Option<(f64, f64)> {
+        let [a, b, c, d, _, f, g, h, i] = self.window(row, col)?;
+
+        let gradient = match method {
+            GradientMethod::Horn => (
+                ((c + 2.0 * f + i) - (a + 2.0 * d + g)) / (8.0 * self.cell_size),
+                ((g + 2.0 * h + i) - (a + 2.0 * b + c)) / (8.0 * self.cell_size),
+            ),
+            GradientMethod::ZevenbergenThorne => (
+                (f - d) / (2.0 * self.cell_size),
+                (h - b) / (2.0 * self.cell_size),
+            ),
+        };
+        Some(gradient)
+    }
+}
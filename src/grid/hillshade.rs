@@ -0,0 +1,83 @@
+//! Shaded relief.
+
+use super::{GradientMethod, Raster};
+
+impl Raster<f32> {
+    /// Computes the shaded relief of the terrain, lit from the given direction.
+    ///
+    /// `azimuth` is the direction of the light in degrees clockwise from north,
+    /// and `altitude` its angle above the horizon in degrees. The usual values
+    /// are `315` and `45`. Slopes are computed with Horn's method.
+    ///
+    /// Illumination is scaled to values from 1 to 255, while 0 is used for cells without data.
+    pub fn hillshade(&self, azimuth: f64, altitude: f64) -> Raster<u8> {
+        let zenith = (90.0 - altitude).to_radians();
+        // Azimuth in mathematical angle, counter clockwise from east.
+        let azimuth = (450.0 - azimuth).to_radians();
+
+        let mut shade = Raster::new(
+            self.origin.clone(),
+            self.cell_size,
+            self.ncols,
+            self.nrows,
+            0,
+        )
+        .with_nodata(Some(0));
+        for row in 0..self.nrows {
+            for col in 0..self.ncols {
+                let Some((dx, dy)) = self.gradient(row, col, GradientMethod::Horn) else {
+                    continue;
+                };
+
+                let slope = dx.hypot(dy).atan();
+                let aspect = dy.atan2(-dx);
+                let value = zenith.cos() * slope.cos()
+                    + zenith.sin() * slope.sin() * (azimuth - aspect).cos();
+                shade.set(row, col, (255.0 * value).round().clamp(1.0, 255.0) as u8);
+            }
+        }
+
+        shade
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    #[test]
+    fn hillshade_lights_facing_slopes() {
+        // Terrain rises towards east.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            5,
+            5,
+            0.0,
+        );
+        for row in 0..5 {
+            for col in 0..5 {
+                raster.set(row, col, col as f32);
+            }
+        }
+
+        let flat = Raster::new(raster.origin().clone(), 1.0, 3, 3, 100.0);
+        assert_eq!(flat.hillshade(315.0, 45.0).get(1, 1), Some(180));
+
+        // Slope faces west, so it is lit from the west and in shadow from the east.
+        let west = raster.hillshade(270.0, 45.0).get(2, 2).unwrap();
+        let east = raster.hillshade(90.0, 45.0).get(2, 2).unwrap();
+        assert_eq!(west, 255);
+        assert_eq!(east, 1);
+
+        let mut holes = raster.with_nodata(Some(-1.0));
+        holes.set(0, 0, -1.0);
+        let shade = holes.hillshade(315.0, 45.0);
+        assert_eq!(shade.get(0, 0), None);
+        assert!(shade.get(0, 1).is_some());
+    }
+}
@@ -0,0 +1,370 @@
+//! Flow routing over the terrain with the D8 method.
+//!
+//! Flow directions are encoded as in Esri tools, with a power of two for every neighbour:
+//!
+//! ```text
+//! 32  64  128
+//! 16   x    1
+//!  8   4    2
+//! ```
+//!
+//! Cells without a lower neighbour, such as pits and flats, have direction 0.
+//! Sinks in the terrain interrupt the flow, so they should be filled first with [`Raster::fill_sinks`].
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use super::Raster;
+
+/// Flow direction of cells without data.
+pub const NO_DIRECTION: u8 = 255;
+
+/// Row and column offsets of the neighbours with their direction codes.
+const NEIGHBOURS: [(isize, isize, u8); 8] = [
+    (0, 1, 1),
+    (1, 1, 2),
+    (1, 0, 4),
+    (1, -1, 8),
+    (0, -1, 16),
+    (-1, -1, 32),
+    (-1, 0, 64),
+    (-1, 1, 128),
+];
+
+impl Raster<f32> {
+    /// Fills sinks of the terrain with the priority-flood algorithm.
+    ///
+    /// Every cell in the returned raster has a path of strictly decreasing heights
+    /// to the edge of the raster or to a cell without data. Filled areas are raised
+    /// by the smallest representable increments, so that they are not completely
+    /// flat and the flow can be routed over them.
+    pub fn fill_sinks(&self) -> Raster<f32> {
+        let mut filled = self.clone();
+        let mut closed = vec![false; self.nrows * self.ncols];
+        let mut queue = BinaryHeap::new();
+
+        // The flood starts at the cells, where the water can leave the raster.
+        for row in 0..self.nrows {
+            for col in 0..self.ncols {
+                let Some(z) = self.get(row, col) else {
+                    continue;
+                };
+                let is_outlet = NEIGHBOURS.iter().any(|&(dr, dc, _)| {
+                    offset(self, row, col, dr, dc).is_none_or(|(r, c)| self.get(r, c).is_none())
+                });
+                if is_outlet {
+                    closed[row * self.ncols + col] = true;
+                    queue.push(FloodCell { z, row, col });
+                }
+            }
+        }
+
+        while let Some(FloodCell { z, row, col }) = queue.pop() {
+            for (dr, dc, _) in NEIGHBOURS {
+                let Some((r, c)) = offset(self, row, col, dr, dc) else {
+                    continue;
+                };
+                let Some(neighbour) = filled.get(r, c) else {
+                    continue;
+                };
+                if closed[r * self.ncols + c] {
+                    continue;
+                }
+
+                closed[r * self.ncols + c] = true;
+                let neighbour = neighbour.max(z.next_up());
+                filled.set(r, c, neighbour);
+                queue.push(FloodCell {
+                    z: neighbour,
+                    row: r,
+                    col: c,
+                });
+            }
+        }
+
+        filled
+    }
+}
+
+/// Cell in the priority queue of the flood, ordered so that the lowest cell is popped first.
+struct FloodCell {
+    z: f32,
+    row: usize,
+    col: usize,
+}
+
+impl PartialEq for FloodCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FloodCell {}
+
+impl PartialOrd for FloodCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FloodCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.z.total_cmp(&self.z)
+    }
+}
+
+/// Computes the direction of the steepest descent for every cell.
+///
+/// Cells without data have [`NO_DIRECTION`].
+pub fn flow_direction(dem: &Raster<f32>) -> Raster<u8> {
+    let mut directions = dem
+        .same_extent(NO_DIRECTION)
+        .with_nodata(Some(NO_DIRECTION));
+    for row in 0..dem.nrows() {
+        for col in 0..dem.ncols() {
+            let Some(z) = dem.get(row, col) else {
+                continue;
+            };
+
+            let mut best = (0.0, 0);
+            for (dr, dc, code) in NEIGHBOURS {
+                let Some(neighbour) =
+                    offset(dem, row, col, dr, dc).and_then(|(r, c)| dem.get(r, c))
+                else {
+                    continue;
+                };
+
+                let distance = if dr != 0 && dc != 0 {
+                    std::f64::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                let drop = (z - neighbour) as f64 / distance;
+                if drop > best.0 {
+                    best = (drop, code);
+                }
+            }
+            directions.set(row, col, best.1);
+        }
+    }
+
+    directions
+}
+
+/// Counts the cells that flow into every cell, not including the cell itself.
+///
+/// Multiply the count increased by one with the area of a cell to get the catchment area.
+/// Cells without data have no value.
+pub fn flow_accumulation(directions: &Raster<u8>) -> Raster<u32> {
+    let mut accumulation = directions.same_extent(0).with_nodata(Some(u32::MAX));
+
+    // Cells are processed in topological order, starting with the ones without inflow.
+    let mut inflow = vec![0u8; directions.nrows() * directions.ncols()];
+    for row in 0..directions.nrows() {
+        for col in 0..directions.ncols() {
+            match downstream(directions, row, col) {
+                Some((r, c)) => inflow[r * directions.ncols() + c] += 1,
+                None if directions.get(row, col).is_none() => {
+                    accumulation.set(row, col, u32::MAX);
+                }
+                None => (),
+            }
+        }
+    }
+
+    let mut queue: VecDeque<_> = (0..directions.nrows())
+        .flat_map(|row| (0..directions.ncols()).map(move |col| (row, col)))
+        .filter(|&(row, col)| {
+            directions.get(row, col).is_some() && inflow[row * directions.ncols() + col] == 0
+        })
+        .collect();
+    while let Some((row, col)) = queue.pop_front() {
+        let Some((r, c)) = downstream(directions, row, col) else {
+            continue;
+        };
+
+        let count = accumulation.get(row, col).unwrap() + 1;
+        accumulation.set(r, c, accumulation.get(r, c).unwrap() + count);
+
+        let idx = r * directions.ncols() + c;
+        inflow[idx] -= 1;
+        if inflow[idx] == 0 {
+            queue.push_back((r, c));
+        }
+    }
+
+    accumulation
+}
+
+/// Delineates the watershed of the outlet cell.
+///
+/// Returns a mask, where the cells that drain through the outlet, including the
+/// outlet itself, are `true`.
+pub fn watershed(directions: &Raster<u8>, row: usize, col: usize) -> Raster<bool> {
+    let mut mask = directions.same_extent(false);
+    if directions.get(row, col).is_none() {
+        return mask;
+    }
+
+    mask.set(row, col, true);
+    let mut queue = VecDeque::from([(row, col)]);
+    while let Some((row, col)) = queue.pop_front() {
+        for (dr, dc, _) in NEIGHBOURS {
+            let Some((r, c)) = offset(directions, row, col, dr, dc) else {
+                continue;
+            };
+            if mask.get(r, c) == Some(false) && downstream(directions, r, c) == Some((row, col)) {
+                mask.set(r, c, true);
+                queue.push_back((r, c));
+            }
+        }
+    }
+
+    mask
+}
+
+/// Returns the cell into which the cell flows.
+fn downstream(directions: &Raster<u8>, row: usize, col: usize) -> Option<(usize, usize)> {
+    let direction = directions.get(row, col)?;
+    let (dr, dc, _) = NEIGHBOURS.iter().find(|(_, _, code)| *code == direction)?;
+    let (r, c) = offset(directions, row, col, *dr, *dc)?;
+
+    directions.get(r, c).is_some().then_some((r, c))
+}
+
+/// Returns the neighbouring cell, if it is inside the raster.
+fn offset<T>(
+    raster: &Raster<T>,
+    row: usize,
+    col: usize,
+    dr: isize,
+    dc: isize,
+) -> Option<(usize, usize)>
+where
+    T: Copy + PartialEq,
+{
+    let r = row.checked_add_signed(dr)?;
+    let c = col.checked_add_signed(dc)?;
+
+    (r < raster.nrows() && c < raster.ncols()).then_some((r, c))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{NODATA, Raster},
+    };
+
+    use super::{NO_DIRECTION, flow_accumulation, flow_direction, watershed};
+
+    #[test]
+    fn fill_sinks_drains_pits() {
+        // Bowl with a pit in the middle and a lower rim on the east.
+        let mut dem = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            5,
+            5,
+            10.0,
+        );
+        for row in 1..4 {
+            for col in 1..4 {
+                dem.set(row, col, 5.0);
+            }
+        }
+        dem.set(2, 2, 1.0);
+        dem.set(2, 4, 8.0);
+
+        let filled = dem.fill_sinks();
+        // Edges are unchanged, the pit is raised close to the level of the outlet.
+        assert_eq!(filled.get(0, 0), Some(10.0));
+        assert_eq!(filled.get(2, 4), Some(8.0));
+        let pit = filled.get(2, 2).unwrap();
+        assert!(pit > 8.0 && pit < 8.001, "{pit}");
+
+        // Every cell drains through the lowest point of the rim.
+        let accumulation = flow_accumulation(&flow_direction(&filled));
+        assert_eq!(accumulation.get(2, 4), Some(24));
+    }
+
+    #[test]
+    fn flow_in_valley() {
+        // V shaped valley along the middle column, sloping towards south.
+        let mut dem = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            5,
+            4,
+            0.0,
+        );
+        for row in 0..4 {
+            for col in 0..5 {
+                let height = (col as f32 - 2.0).abs() * 10.0 + (3 - row) as f32;
+                dem.set(row, col, height);
+            }
+        }
+
+        let directions = flow_direction(&dem);
+        // Slopes flow into the valley, valley flows south, outlet has no lower neighbour.
+        assert_eq!(directions.get(0, 0), Some(1));
+        assert_eq!(directions.get(1, 4), Some(16));
+        assert_eq!(directions.get(1, 2), Some(4));
+        assert_eq!(directions.get(3, 2), Some(0));
+
+        let accumulation = flow_accumulation(&directions);
+        assert_eq!(accumulation.get(3, 2), Some(19));
+        assert_eq!(accumulation.get(0, 0), Some(0));
+
+        let mask = watershed(&directions, 3, 2);
+        assert!(mask.data().iter().all(|&inside| inside));
+        let mask = watershed(&directions, 1, 2);
+        assert_eq!(mask.data().iter().filter(|&&inside| inside).count(), 10);
+    }
+
+    #[test]
+    fn cells_without_data() {
+        // Slope towards east with a gap in the middle.
+        let mut dem = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            3,
+            3,
+            0.0,
+        )
+        .with_nodata(Some(NODATA));
+        for row in 0..3 {
+            for col in 0..3 {
+                dem.set(row, col, (3 - col) as f32);
+            }
+        }
+        dem.set(1, 1, NODATA);
+
+        let directions = flow_direction(&dem);
+        assert_eq!(directions.get(1, 1), None);
+        assert_eq!(directions.data()[4], NO_DIRECTION);
+        assert_eq!(directions.get(1, 0), Some(2));
+
+        let accumulation = flow_accumulation(&directions);
+        assert_eq!(accumulation.get(1, 1), None);
+        assert_eq!(accumulation.get(1, 2), Some(0));
+        assert_eq!(accumulation.get(2, 2), Some(3));
+
+        let mask = watershed(&directions, 1, 1);
+        assert!(mask.data().iter().all(|&inside| !inside));
+    }
+}
@@ -0,0 +1,213 @@
+//! Cropping and merging of rasters.
+
+use thiserror::Error;
+
+use super::{NODATA, Raster};
+use crate::{Area, Point};
+
+/// Relative tolerance of cell sizes and alignment of merged rasters.
+const TOLERANCE: f64 = 1e-6;
+
+/// The error type that can occur when merging rasters.
+#[derive(Debug, Error, PartialEq)]
+pub enum MosaicError {
+    #[error("no rasters to merge")]
+    Empty,
+
+    /// Rasters have different cell sizes and have to be resampled first.
+    #[error("rasters have different cell sizes")]
+    CellSize,
+
+    /// Cell centers of the rasters don't lie on the same grid.
+    #[error("rasters are not aligned to the same grid")]
+    Misaligned,
+}
+
+/// How values of overlapping cells are combined by [`Raster::mosaic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Overlap {
+    /// Value of the raster, which comes first in the slice.
+    #[default]
+    First,
+    /// Mean of the values of all rasters.
+    Mean,
+}
+
+impl<T: Copy + PartialEq> Raster<T> {
+    /// Returns the part of the raster with cell centers inside the area.
+    ///
+    /// If there are no such cells, the raster is empty.
+    pub fn crop(&self, area: &Area) -> Raster<T> {
+        let to_col = |x: f64| (x - self.origin.x) / self.cell_size;
+        let to_row = |y: f64| (self.origin.y - y) / self.cell_size;
+
+        let clamp = |v: f64, n: usize| v.max(0.0).min(n as f64) as usize;
+        let first_col = clamp(to_col(area.min_x()).ceil(), self.ncols);
+        let last_col = clamp(to_col(area.max_x()).floor() + 1.0, self.ncols);
+        let first_row = clamp(to_row(area.max_y()).ceil(), self.nrows);
+        let last_row = clamp(to_row(area.min_y()).floor() + 1.0, self.nrows);
+
+        let ncols = last_col.saturating_sub(first_col);
+        let nrows = last_row.saturating_sub(first_row);
+        let mut data = Vec::with_capacity(ncols * nrows);
+        for row in first_row..first_row + nrows {
+            let start = row * self.ncols + first_col;
+            data.extend_from_slice(&self.data[start..start + ncols]);
+        }
+
+        Raster {
+            origin: self.cell_center(first_row, first_col),
+            cell_size: self.cell_size,
+            ncols,
+            nrows,
+            nodata: self.nodata,
+            data,
+        }
+    }
+}
+
+impl Raster<f32> {
+    /// Merges rasters into a single raster, which covers all of them.
+    ///
+    /// Rasters have to have the same cell size and cells aligned to the same grid,
+    /// as the regional rasters of the `DEM 0050` data do. Cells without data are ignored,
+    /// so gaps in one raster are filled from the others and there are no seams
+    /// between them. Cells not covered by any raster have [`NODATA`].
+    pub fn mosaic(rasters: &[Raster<f32>], overlap: Overlap) -> Result<Raster<f32>, MosaicError> {
+        let first = rasters.first().ok_or(MosaicError::Empty)?;
+        let cell_size = first.cell_size;
+
+        let mut west = f64::MAX;
+        let mut north = f64::MIN;
+        for raster in rasters {
+            if (raster.cell_size - cell_size).abs() > TOLERANCE * cell_size {
+                return Err(MosaicError::CellSize);
+            }
+            for offset in [
+                raster.origin.x - first.origin.x,
+                raster.origin.y - first.origin.y,
+            ] {
+                let cells = offset / cell_size;
+                if (cells - cells.round()).abs() > TOLERANCE {
+                    return Err(MosaicError::Misaligned);
+                }
+            }
+            west = west.min(raster.origin.x);
+            north = north.max(raster.origin.y);
+        }
+
+        // Offsets of the rasters in cells of the result.
+        let offsets: Vec<_> = rasters
+            .iter()
+            .map(|r| {
+                let col = ((r.origin.x - west) / cell_size).round() as usize;
+                let row = ((north - r.origin.y) / cell_size).round() as usize;
+                (row, col)
+            })
+            .collect();
+        let ncols = rasters
+            .iter()
+            .zip(&offsets)
+            .map(|(r, (_, c))| c + r.ncols)
+            .max();
+        let nrows = rasters
+            .iter()
+            .zip(&offsets)
+            .map(|(r, (r0, _))| r0 + r.nrows)
+            .max();
+        let (ncols, nrows) = (ncols.unwrap_or(0), nrows.unwrap_or(0));
+
+        let origin = Point {
+            x: west,
+            y: north,
+            data: (),
+        };
+        let mut result =
+            Raster::new(origin, cell_size, ncols, nrows, NODATA).with_nodata(Some(NODATA));
+        let mut sums = vec![(0.0, 0u32); ncols * nrows];
+        for (raster, (row0, col0)) in rasters.iter().zip(offsets) {
+            for row in 0..raster.nrows {
+                for col in 0..raster.ncols {
+                    let Some(value) = raster.get(row, col) else {
+                        continue;
+                    };
+                    let (row, col) = (row0 + row, col0 + col);
+                    match overlap {
+                        Overlap::First => {
+                            if result.get(row, col).is_none() {
+                                result.set(row, col, value);
+                            }
+                        }
+                        Overlap::Mean => {
+                            let (sum, count) = &mut sums[row * ncols + col];
+                            *sum += value as f64;
+                            *count += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        if overlap == Overlap::Mean {
+            for (value, (sum, count)) in result.data.iter_mut().zip(sums) {
+                if count > 0 {
+                    *value = (sum / count as f64) as f32;
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Area, Point,
+        grid::{MosaicError, Overlap, Raster},
+    };
+
+    fn raster(x: f64, y: f64, ncols: usize, nrows: usize, value: f32) -> Raster {
+        let origin = Point { x, y, data: () };
+        Raster::new(origin, 5.0, ncols, nrows, value).with_nodata(Some(-1.0))
+    }
+
+    #[test]
+    fn crop_and_mosaic() {
+        let west = raster(0.0, 100.0, 4, 4, 10.0);
+        let mut east = raster(10.0, 95.0, 4, 4, 20.0);
+        east.set(0, 0, -1.0);
+
+        let merged = Raster::mosaic(&[west.clone(), east.clone()], Overlap::First).unwrap();
+        assert_eq!((merged.ncols(), merged.nrows()), (6, 5));
+        assert_eq!(merged.value_at(0.0, 100.0), Some(10.0));
+        // Overlap is taken from the first raster, gap in the east from the west.
+        assert_eq!(merged.value_at(15.0, 90.0), Some(10.0));
+        assert_eq!(merged.value_at(10.0, 95.0), Some(10.0));
+        assert_eq!(merged.value_at(25.0, 80.0), Some(20.0));
+        assert_eq!(merged.value_at(0.0, 80.0), None);
+
+        let mean = Raster::mosaic(&[west.clone(), east], Overlap::Mean).unwrap();
+        assert_eq!(mean.value_at(15.0, 90.0), Some(15.0));
+        assert_eq!(mean.value_at(10.0, 95.0), Some(10.0));
+
+        let shifted = raster(2.0, 100.0, 2, 2, 0.0);
+        assert_eq!(
+            Raster::mosaic(&[west.clone(), shifted], Overlap::First),
+            Err(MosaicError::Misaligned)
+        );
+        assert_eq!(Raster::mosaic(&[], Overlap::First), Err(MosaicError::Empty));
+
+        let cropped = merged.crop(&Area::from_bounds(4.0, 86.0, 16.0, 96.0));
+        assert_eq!((cropped.ncols(), cropped.nrows()), (3, 2));
+        assert_eq!(cropped.origin().x, 5.0);
+        assert_eq!(cropped.origin().y, 95.0);
+        assert_eq!(cropped.get(1, 1), merged.get(2, 2));
+        assert_eq!(
+            merged
+                .crop(&Area::from_bounds(500.0, 0.0, 600.0, 10.0))
+                .ncols(),
+            0
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Overviews of rasters at coarser resolutions, for fast rendering of large areas.
+
+use super::{Aggregation, NODATA, Raster};
+
+/// Raster with its overviews, each with twice the cell size of the previous one.
+#[derive(Debug, Clone)]
+pub struct RasterPyramid {
+    levels: Vec<Raster<f32>>,
+}
+
+impl RasterPyramid {
+    /// Returns all levels, starting with the original raster.
+    pub fn levels(&self) -> &[Raster<f32>] {
+        &self.levels
+    }
+
+    /// Returns the level to draw at the requested cell size in meters.
+    ///
+    /// It is the coarsest level which is not coarser than requested, so that no
+    /// detail is lost. Requests finer than the original raster return the original.
+    pub fn for_resolution(&self, cell_size: f64) -> &Raster<f32> {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.cell_size <= cell_size)
+            .unwrap_or(&self.levels[0])
+    }
+}
+
+impl Raster<f32> {
+    /// Builds the pyramid of up to `levels` overviews of the raster.
+    ///
+    /// Every overview combines blocks of 2x2 cells of the previous level with the
+    /// aggregation, where [`Aggregation::Nearest`] picks the north-west cell with data.
+    /// Cells without data are left out, and blocks without any data have [`NODATA`].
+    /// Building stops early once a level has a single cell.
+    pub fn build_pyramid(&self, levels: usize, aggregation: Aggregation) -> RasterPyramid {
+        let mut pyramid = vec![self.clone()];
+        for _ in 0..levels {
+            let last = &pyramid[pyramid.len() - 1];
+            if last.ncols <= 1 && last.nrows <= 1 {
+                break;
+            }
+            let next = last.overview(aggregation);
+            pyramid.push(next);
+        }
+
+        RasterPyramid { levels: pyramid }
+    }
+
+    fn overview(&self, aggregation: Aggregation) -> Raster<f32> {
+        let mut origin = self.origin.clone();
+        origin.x += self.cell_size / 2.0;
+        origin.y -= self.cell_size / 2.0;
+
+        let mut overview = Raster::new(
+            origin,
+            self.cell_size * 2.0,
+            self.ncols.div_ceil(2),
+            self.nrows.div_ceil(2),
+            NODATA,
+        )
+        .with_nodata(Some(NODATA));
+        for row in 0..overview.nrows {
+            for col in 0..overview.ncols {
+                let values = [(0, 0), (0, 1), (1, 0), (1, 1)]
+                    .into_iter()
+                    .filter_map(|(dr, dc)| self.get(2 * row + dr, 2 * col + dc));
+
+                let (sum, count, min, max, first) = values.fold(
+                    (0.0, 0, f32::MAX, f32::MIN, None),
+                    |(sum, count, min, max, first), v| {
+                        (
+                            sum + v as f64,
+                            count + 1,
+                            min.min(v),
+                            max.max(v),
+                            first.or(Some(v)),
+                        )
+                    },
+                );
+                if count == 0 {
+                    continue;
+                }
+
+                let value = match aggregation {
+                    Aggregation::Mean => (sum / count as f64) as f32,
+                    Aggregation::Min => min,
+                    Aggregation::Max => max,
+                    Aggregation::Nearest => first.unwrap_or(NODATA),
+                };
+                overview.set(row, col, value);
+            }
+        }
+
+        overview
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{Aggregation, Raster},
+    };
+
+    #[test]
+    fn pyramid_levels() {
+        let mut raster = Raster::new(
+            Point {
+                x: 0.5,
+                y: 4.5,
+                data: (),
+            },
+            1.0,
+            5,
+            5,
+            0.0,
+        )
+        .with_nodata(Some(-1.0));
+        for row in 0..5 {
+            for col in 0..5 {
+                raster.set(row, col, (row * 5 + col) as f32);
+            }
+        }
+        raster.set(0, 0, -1.0);
+
+        let pyramid = raster.build_pyramid(10, Aggregation::Mean);
+        let sizes: Vec<_> = pyramid
+            .levels()
+            .iter()
+            .map(|l| (l.ncols(), l.cell_size()))
+            .collect();
+        assert_eq!(sizes, [(5, 1.0), (3, 2.0), (2, 4.0), (1, 8.0)]);
+
+        let first = &pyramid.levels()[1];
+        assert_eq!(first.origin().x, 1.0);
+        assert_eq!(first.origin().y, 4.0);
+        // Cell without data is left out of the mean.
+        assert_eq!(first.get(0, 0), Some((1.0 + 5.0 + 6.0) / 3.0));
+        assert_eq!(first.get(2, 2), Some(24.0));
+
+        let max = raster.build_pyramid(1, Aggregation::Max);
+        assert_eq!(max.levels()[1].get(0, 0), Some(6.0));
+
+        assert_eq!(pyramid.for_resolution(0.5).cell_size(), 1.0);
+        assert_eq!(pyramid.for_resolution(5.0).cell_size(), 4.0);
+        assert_eq!(pyramid.for_resolution(100.0).cell_size(), 8.0);
+    }
+}
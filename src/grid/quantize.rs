@@ -0,0 +1,131 @@
+//! Heights stored as 16-bit integers, which take half of the memory of `f32`.
+
+use super::{NODATA, Raster};
+
+/// Mapping of heights to 16-bit integers, as `height = value * scale + offset`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Quantization {
+    /// Height difference of consecutive values in meters.
+    pub scale: f32,
+    /// Height of value 0 in meters.
+    pub offset: f32,
+}
+
+impl Quantization {
+    /// Heights in decimeters from 0 to 6553.4 m, which covers all of Slovenia.
+    pub const DECIMETERS: Self = Self {
+        scale: 0.1,
+        offset: 0.0,
+    };
+
+    /// Value reserved for cells without data.
+    pub const NODATA: u16 = u16::MAX;
+
+    /// Encodes the height, rounding it to the nearest value.
+    ///
+    /// Heights outside of the range are clamped to the range.
+    pub fn encode(&self, height: f32) -> u16 {
+        ((height - self.offset) / self.scale)
+            .round()
+            .clamp(0.0, (Self::NODATA - 1) as f32) as u16
+    }
+
+    /// Decodes the height from the value.
+    pub fn decode(&self, value: u16) -> f32 {
+        value as f32 * self.scale + self.offset
+    }
+}
+
+impl Default for Quantization {
+    fn default() -> Self {
+        Self::DECIMETERS
+    }
+}
+
+/// Raster with quantized heights, which are decoded on access.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizedRaster {
+    raster: Raster<u16>,
+    quantization: Quantization,
+}
+
+impl QuantizedRaster {
+    /// Returns the raster of the encoded values, with [`Quantization::NODATA`] for cells without data.
+    pub fn raster(&self) -> &Raster<u16> {
+        &self.raster
+    }
+
+    pub fn quantization(&self) -> Quantization {
+        self.quantization
+    }
+
+    /// Returns the decoded height of the cell, or None if the cell has no data.
+    pub fn get(&self, row: usize, col: usize) -> Option<f32> {
+        self.raster
+            .get(row, col)
+            .map(|v| self.quantization.decode(v))
+    }
+
+    /// Returns the decoded height of the cell containing the location.
+    pub fn value_at(&self, x: f64, y: f64) -> Option<f32> {
+        let (row, col) = self.raster.cell_at(x, y)?;
+        self.get(row, col)
+    }
+
+    /// Decodes all heights, with [`NODATA`] for cells without data.
+    pub fn dequantize(&self) -> Raster<f32> {
+        self.raster.map(NODATA, |v| self.quantization.decode(v))
+    }
+}
+
+impl Raster<f32> {
+    /// Encodes the heights with the quantization.
+    pub fn quantize(&self, quantization: Quantization) -> QuantizedRaster {
+        let raster = self.map(Quantization::NODATA, |v| quantization.encode(v));
+
+        QuantizedRaster {
+            raster,
+            quantization,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{NODATA, Raster},
+    };
+
+    use super::Quantization;
+
+    #[test]
+    fn quantized_raster_roundtrip() {
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            3,
+            1,
+            NODATA,
+        )
+        .with_nodata(Some(NODATA));
+        raster.set(0, 0, 2864.04);
+        raster.set(0, 1, -5.0);
+
+        let quantized = raster.quantize(Quantization::DECIMETERS);
+        assert!((quantized.get(0, 0).unwrap() - 2864.0).abs() < 1e-3);
+        // Heights below the range are clamped.
+        assert_eq!(quantized.get(0, 1), Some(0.0));
+        assert_eq!(quantized.get(0, 2), None);
+        assert_eq!(quantized.raster().get(0, 2), None);
+
+        let decoded = quantized.dequantize();
+        assert_eq!(decoded.get(0, 2), None);
+        assert!((decoded.value_at(0.0, 0.0).unwrap() - 2864.0).abs() < 1e-3);
+    }
+}
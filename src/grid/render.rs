@@ -0,0 +1,187 @@
+//! Rendering of rasters into images.
+
+use super::Raster;
+
+/// Color ramp used to map values to colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorRamp {
+    /// Black for the minimum and white for the maximum.
+    #[default]
+    Grayscale,
+    /// Blue, green, yellow and brown to white, commonly used for heights.
+    Terrain,
+    /// Perceptually uniform ramp from dark purple to yellow.
+    Viridis,
+}
+
+impl ColorRamp {
+    /// Returns the color at `t` from 0 to 1.
+    pub fn color(&self, t: f64) -> [u8; 3] {
+        let stops: &[(f64, [u8; 3])] = match self {
+            ColorRamp::Grayscale => &[(0.0, [0, 0, 0]), (1.0, [255, 255, 255])],
+            ColorRamp::Terrain => &[
+                (0.0, [51, 51, 153]),
+                (0.15, [0, 153, 255]),
+                (0.25, [0, 204, 102]),
+                (0.5, [255, 255, 153]),
+                (0.75, [128, 92, 84]),
+                (1.0, [255, 255, 255]),
+            ],
+            ColorRamp::Viridis => &[
+                (0.0, [68, 1, 84]),
+                (0.125, [72, 36, 117]),
+                (0.25, [59, 82, 139]),
+                (0.375, [44, 114, 142]),
+                (0.5, [33, 145, 140]),
+                (0.625, [40, 174, 128]),
+                (0.75, [94, 201, 98]),
+                (0.875, [173, 220, 48]),
+                (1.0, [253, 231, 37]),
+            ],
+        };
+
+        let t = t.clamp(0.0, 1.0);
+        let i = stops.iter().position(|(s, _)| *s >= t).unwrap_or(0).max(1);
+        let ((s0, c0), (s1, c1)) = (stops[i - 1], stops[i]);
+        let f = (t - s0) / (s1 - s0);
+
+        std::array::from_fn(|j| (c0[j] as f64 + (c1[j] as f64 - c0[j] as f64) * f).round() as u8)
+    }
+}
+
+/// Options for rendering rasters.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RenderOptions {
+    pub ramp: ColorRamp,
+    /// Value mapped to the start of the ramp. Defaults to the minimum of the raster.
+    pub min: Option<f64>,
+    /// Value mapped to the end of the ramp. Defaults to the maximum of the raster.
+    pub max: Option<f64>,
+}
+
+/// Renders the raster into RGBA pixels, in row-major order with north on top.
+///
+/// Values are linearly stretched between the minimum and the maximum of the options
+/// and mapped to the colors of the ramp. Cells without data are transparent.
+pub fn to_rgba<T>(raster: &Raster<T>, options: &RenderOptions) -> Vec<u8>
+where
+    T: Copy + PartialEq + Into<f64>,
+{
+    let stretch = Stretch::new(raster, options);
+    raster
+        .data()
+        .iter()
+        .flat_map(|&v| stretch.pixel((raster.nodata() != Some(v)).then(|| v.into())))
+        .collect()
+}
+
+/// Maps values to pixels, with the minimum and maximum resolved for the whole raster.
+pub(crate) struct Stretch {
+    ramp: ColorRamp,
+    min: f64,
+    range: f64,
+}
+
+impl Stretch {
+    pub fn new<T>(raster: &Raster<T>, options: &RenderOptions) -> Self
+    where
+        T: Copy + PartialEq + Into<f64>,
+    {
+        let (data_min, data_max) = raster
+            .data()
+            .iter()
+            .filter(|&&v| raster.nodata() != Some(v))
+            .fold((f64::MAX, f64::MIN), |(min, max), &v| {
+                (min.min(v.into()), max.max(v.into()))
+            });
+        let min = options.min.unwrap_or(data_min);
+        let max = options.max.unwrap_or(data_max);
+
+        Self {
+            ramp: options.ramp,
+            min,
+            range: if max > min { max - min } else { 1.0 },
+        }
+    }
+
+    /// Returns RGBA pixel of the value, which is transparent for missing values.
+    pub fn pixel(&self, value: Option<f64>) -> [u8; 4] {
+        match value {
+            Some(v) => {
+                let [r, g, b] = self.ramp.color((v - self.min) / self.range);
+                [r, g, b, 255]
+            }
+            None => [0; 4],
+        }
+    }
+}
+
+/// Writes the raster as PNG image, rendered with [`to_rgba`].
+///
+/// Every cell is a single pixel.
+#[cfg(feature = "png")]
+pub fn write_png<T>(
+    writer: impl std::io::Write,
+    raster: &Raster<T>,
+    options: &RenderOptions,
+) -> Result<(), png::EncodingError>
+where
+    T: Copy + PartialEq + Into<f64>,
+{
+    let mut encoder = png::Encoder::new(writer, raster.ncols() as u32, raster.nrows() as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&to_rgba(raster, options))?;
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::{ColorRamp, RenderOptions, to_rgba};
+
+    #[test]
+    fn render_stretch_and_nodata() {
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            4,
+            1,
+            0.0f32,
+        )
+        .with_nodata(Some(-1.0));
+        for (col, v) in [100.0, 150.0, 200.0, -1.0].into_iter().enumerate() {
+            raster.set(0, col, v);
+        }
+
+        let pixels = to_rgba(&raster, &RenderOptions::default());
+        assert_eq!(
+            pixels,
+            [
+                0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255, 0, 0, 0, 0
+            ]
+        );
+
+        let options = RenderOptions {
+            ramp: ColorRamp::Viridis,
+            min: Some(0.0),
+            max: Some(100.0),
+        };
+        let pixels = to_rgba(&raster, &options);
+        assert_eq!(pixels[..4], [253, 231, 37, 255]);
+
+        #[cfg(feature = "png")]
+        {
+            let mut buf = vec![];
+            super::write_png(&mut buf, &raster, &options).unwrap();
+            assert_eq!(buf[1..4], *b"PNG");
+        }
+    }
+}
@@ -0,0 +1,174 @@
+//! Resampling of rasters to a different cell size.
+
+use super::{NODATA, Raster};
+use crate::Point;
+
+/// Interpolation used by [`Raster::resample`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResampleMethod {
+    /// Value of the nearest cell when upsampling,
+    /// and mean of the covered cells when downsampling.
+    Nearest,
+    /// Linear interpolation between the neighbouring cells.
+    #[default]
+    Bilinear,
+    /// Cubic convolution, which is smoother, but can overshoot at sharp edges.
+    Bicubic,
+}
+
+impl ResampleMethod {
+    /// Returns the radius of the kernel in cells.
+    fn radius(self) -> f64 {
+        match self {
+            Self::Nearest => 0.5,
+            Self::Bilinear => 1.0,
+            Self::Bicubic => 2.0,
+        }
+    }
+
+    /// Returns the weight of a cell at the distance `t` in cells.
+    fn weight(self, t: f64) -> f64 {
+        let t = t.abs();
+        match self {
+            Self::Nearest => 1.0,
+            Self::Bilinear => (1.0 - t).max(0.0),
+            // Keys' kernel with a = -0.5.
+            Self::Bicubic if t < 1.0 => (1.5 * t - 2.5) * t * t + 1.0,
+            Self::Bicubic if t < 2.0 => ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0,
+            Self::Bicubic => 0.0,
+        }
+    }
+}
+
+impl Raster<f32> {
+    /// Resamples the raster to cells of `cell_size` meters.
+    ///
+    /// The result covers the same extent, with as many cells as needed to cover it.
+    /// When downsampling, the kernel is widened by the ratio of the cell sizes,
+    /// so that every source cell contributes, instead of only the closest ones.
+    /// Cells without data are skipped, and result cells without any contributing
+    /// source cell have [`NODATA`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `cell_size` is not positive and finite.
+    pub fn resample(&self, cell_size: f64, method: ResampleMethod) -> Raster<f32> {
+        assert!(
+            cell_size > 0.0 && cell_size.is_finite(),
+            "cell size must be positive and finite"
+        );
+
+        let width = self.ncols as f64 * self.cell_size;
+        let height = self.nrows as f64 * self.cell_size;
+        let (west, north) = (
+            self.origin.x - self.cell_size / 2.0,
+            self.origin.y + self.cell_size / 2.0,
+        );
+
+        let origin = Point {
+            x: west + cell_size / 2.0,
+            y: north - cell_size / 2.0,
+            data: (),
+        };
+        let ncols = (width / cell_size - 1e-9).ceil().max(0.0) as usize;
+        let nrows = (height / cell_size - 1e-9).ceil().max(0.0) as usize;
+        let mut result =
+            Raster::new(origin, cell_size, ncols, nrows, NODATA).with_nodata(Some(NODATA));
+
+        let scale = (cell_size / self.cell_size).max(1.0);
+        for row in 0..nrows {
+            for col in 0..ncols {
+                let center = result.cell_center(row, col);
+                // Position in the source raster, in cells.
+                let c = (center.x - self.origin.x) / self.cell_size;
+                let r = (self.origin.y - center.y) / self.cell_size;
+                let value = match method {
+                    ResampleMethod::Nearest if scale == 1.0 => self
+                        .get(r.round().max(0.0) as usize, c.round().max(0.0) as usize)
+                        .map(|v| v as f64),
+                    _ => self.convolve(r, c, scale, method),
+                };
+                if let Some(value) = value {
+                    result.set(row, col, value as f32);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Returns the weighted value around the position `(r, c)` in cells.
+    fn convolve(&self, r: f64, c: f64, scale: f64, method: ResampleMethod) -> Option<f64> {
+        let radius = method.radius() * scale;
+        let rows = (r - radius).ceil().max(0.0) as usize..=(r + radius).floor().max(0.0) as usize;
+        let cols = (c - radius).ceil().max(0.0) as usize..=(c + radius).floor().max(0.0) as usize;
+
+        let mut weighted = 0.0;
+        let mut total = 0.0;
+        for row in rows {
+            let wr = method.weight((row as f64 - r) / scale);
+            for col in cols.clone() {
+                let Some(value) = self.get(row, col) else {
+                    continue;
+                };
+                let weight = wr * method.weight((col as f64 - c) / scale);
+                weighted += weight * value as f64;
+                total += weight;
+            }
+        }
+
+        (total > 1e-9).then(|| weighted / total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{Raster, ResampleMethod},
+    };
+
+    #[test]
+    fn resample_plane() {
+        // 1 m grid of a plane, rising towards east.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.5,
+                y: 99.5,
+                data: (),
+            },
+            1.0,
+            100,
+            100,
+            0.0,
+        );
+        for row in 0..100 {
+            for col in 0..100 {
+                raster.set(row, col, col as f32 + 0.5);
+            }
+        }
+
+        for method in [
+            ResampleMethod::Nearest,
+            ResampleMethod::Bilinear,
+            ResampleMethod::Bicubic,
+        ] {
+            let coarse = raster.resample(10.0, method);
+            assert_eq!((coarse.ncols(), coarse.nrows()), (10, 10));
+            assert_eq!(coarse.origin().x, 5.0);
+            // Plane is preserved inside, away from the edges.
+            let value = coarse.value_at(45.0, 45.0).unwrap();
+            assert!((value - 45.0).abs() < 0.51, "{method:?} {value}");
+
+            let fine = raster.resample(0.5, method);
+            assert_eq!((fine.ncols(), fine.nrows()), (200, 200));
+            let value = fine.value_at(50.25, 50.0).unwrap();
+            let expected = if method == ResampleMethod::Nearest {
+                50.5
+            } else {
+                50.25
+            };
+            assert!((value - expected).abs() < 1e-4, "{method:?} {value}");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Slope and aspect of the terrain.
+
+use super::{GradientMethod, NODATA, Raster};
+
+impl Raster<f32> {
+    /// Computes the slope of every cell in degrees, from 0 for flat terrain to 90.
+    ///
+    /// Cells without data have [`NODATA`].
+    pub fn slope(&self, method: GradientMethod) -> Raster<f32> {
+        self.map_gradient(method, |dx, dy| dx.hypot(dy).atan().to_degrees())
+    }
+
+    /// Computes the aspect of every cell, which is the direction the slope faces.
+    ///
+    /// Aspect is in degrees clockwise from north, from 0 to 360.
+    /// Flat cells and cells without data have [`NODATA`].
+    pub fn aspect(&self, method: GradientMethod) -> Raster<f32> {
+        self.map_gradient(method, |dx, dy| {
+            if dx == 0.0 && dy == 0.0 {
+                return NODATA as f64;
+            }
+
+            // Slope faces downhill, against the gradient. Y axis of the gradient points south.
+            (-dx).atan2(dy).to_degrees().rem_euclid(360.0)
+        })
+    }
+
+    fn map_gradient<F>(&self, method: GradientMethod, f: F) -> Raster<f32>
+    where
+        F: Fn(f64, f64) -> f64,
+    {
+        let mut result = Raster::new(
+            self.origin.clone(),
+            self.cell_size,
+            self.ncols,
+            self.nrows,
+            NODATA,
+        )
+        .with_nodata(Some(NODATA));
+        for row in 0..self.nrows {
+            for col in 0..self.ncols {
+                if let Some((dx, dy)) = self.gradient(row, col, method) {
+                    result.set(row, col, f(dx, dy) as f32);
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{GradientMethod, Raster},
+    };
+
+    #[test]
+    fn slope_and_aspect_of_plane() {
+        // Terrain rises towards north-east by 1 m per meter in both directions.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            2.0,
+            5,
+            5,
+            0.0,
+        );
+        for row in 0..5 {
+            for col in 0..5 {
+                raster.set(row, col, 2.0 * (col as f32 - row as f32));
+            }
+        }
+
+        for method in [GradientMethod::Horn, GradientMethod::ZevenbergenThorne] {
+            let slope = raster.slope(method).get(2, 2).unwrap();
+            assert!((slope - 2f32.sqrt().atan().to_degrees()).abs() < 1e-4);
+
+            // Slope faces south-west.
+            let aspect = raster.aspect(method).get(2, 2).unwrap();
+            assert!((aspect - 225.0).abs() < 1e-4, "{aspect}");
+        }
+
+        let flat = Raster::new(raster.origin().clone(), 1.0, 3, 3, 5.0);
+        assert_eq!(flat.slope(GradientMethod::Horn).get(1, 1), Some(0.0));
+        assert_eq!(flat.aspect(GradientMethod::Horn).get(1, 1), None);
+    }
+}
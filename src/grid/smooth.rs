@@ -0,0 +1,228 @@
+//! Smoothing filters for removing noise from the terrain.
+
+use super::Raster;
+
+/// Smallest similarity, the cosine of the angle between normals, of cells
+/// averaged by the feature preserving filter. It corresponds to about 25°.
+const FEATURE_THRESHOLD: f64 = 0.9;
+
+/// Number of iterations of the feature preserving filter.
+const FEATURE_ITERATIONS: usize = 5;
+
+/// Filter used by [`Raster::smooth`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmoothMethod {
+    /// Weighted mean of the neighbourhood, with weights decreasing with the
+    /// distance. `sigma` is the standard deviation in meters, and cells up to
+    /// three sigmas away are included.
+    Gaussian { sigma: f64 },
+    /// Median of the square window with `radius` cells between the center
+    /// and its edge. Removes spikes, while keeping sharp edges.
+    Median { radius: usize },
+    /// Smooths normals of the surface only among cells with similar orientation,
+    /// and fits the heights to the smoothed normals, after Sun et al. Removes noise
+    /// from slopes, while keeping ridges, terraces and breaklines sharp.
+    FeaturePreserving,
+}
+
+impl Raster<f32> {
+    /// Smooths the terrain with the method.
+    ///
+    /// Neighbours without data are left out, and cells without data
+    /// have [`NODATA`](super::NODATA).
+    pub fn smooth(&self, method: SmoothMethod) -> Raster<f32> {
+        match method {
+            SmoothMethod::Gaussian { sigma } => self.gaussian(sigma),
+            SmoothMethod::Median { radius } => self.focal(radius, |w| {
+                let mut values: Vec<_> = w.neighbours().map(|(_, _, v)| v).collect();
+                values.push(w.center());
+
+                let mid = values.len() / 2;
+                let (_, median, _) = values.select_nth_unstable_by(mid, f32::total_cmp);
+                Some(*median)
+            }),
+            SmoothMethod::FeaturePreserving => {
+                let mut smoothed = self.focal(0, |w| Some(w.center()));
+                for _ in 0..FEATURE_ITERATIONS {
+                    smoothed = smoothed.feature_preserving_step();
+                }
+                smoothed
+            }
+        }
+    }
+
+    fn gaussian(&self, sigma: f64) -> Raster<f32> {
+        let sigma = sigma / self.cell_size;
+        let radius = (3.0 * sigma).ceil() as usize;
+        self.focal(radius, |w| {
+            let weight = |dr: isize, dc: isize| {
+                (-((dr * dr + dc * dc) as f64) / (2.0 * sigma * sigma)).exp()
+            };
+
+            let (sum, weights) =
+                w.neighbours()
+                    .fold((w.center() as f64, 1.0), |(sum, weights), (dr, dc, v)| {
+                        let weight = weight(dr, dc);
+                        (sum + weight * v as f64, weights + weight)
+                    });
+            Some((sum / weights) as f32)
+        })
+    }
+
+    /// Filters normals of the faces between the cells, and moves the cells to fit them.
+    ///
+    /// Faces are squares between the centers of four neighbouring cells. Their normals
+    /// don't mix both sides of a breakline, unlike gradients of the cells.
+    fn feature_preserving_step(&self) -> Raster<f32> {
+        let (rows, cols) = (self.nrows.saturating_sub(1), self.ncols.saturating_sub(1));
+        let faces: Vec<_> = (0..rows * cols)
+            .map(|i| self.face(i / cols, i % cols))
+            .collect();
+        let face = |row: usize, col: usize| {
+            (row < rows && col < cols)
+                .then(|| faces[row * cols + col])
+                .flatten()
+        };
+
+        // Similar normals of the neighbouring faces pull the normal of the face.
+        let smoothed: Vec<_> = (0..rows * cols)
+            .map(|i| {
+                let (row, col) = (i / cols, i % cols);
+                let (center, n) = faces[i]?;
+
+                let mut smoothed = [0.0; 3];
+                for dr in -1..=1 {
+                    for dc in -1..=1 {
+                        let (Some(r), Some(c)) =
+                            (row.checked_add_signed(dr), col.checked_add_signed(dc))
+                        else {
+                            continue;
+                        };
+                        let Some((_, m)) = face(r, c) else {
+                            continue;
+                        };
+
+                        let similarity = n[0] * m[0] + n[1] * m[1] + n[2] * m[2];
+                        if similarity > FEATURE_THRESHOLD {
+                            let weight = (similarity - FEATURE_THRESHOLD).powi(2);
+                            for k in 0..3 {
+                                smoothed[k] += weight * m[k];
+                            }
+                        }
+                    }
+                }
+                // Normal of the face is always similar to itself, so the sum is not zero.
+                let gradient = (-smoothed[0] / smoothed[2], -smoothed[1] / smoothed[2]);
+                Some((center, gradient))
+            })
+            .collect();
+
+        // Cell moves to the mean height of the planes of its faces.
+        self.focal(0, |w| {
+            let (row, col) = w.position();
+            let (mut sum, mut count) = (0.0, 0);
+            for (r, c) in [
+                (row, col),
+                (row, col + 1),
+                (row + 1, col),
+                (row + 1, col + 1),
+            ] {
+                let (Some(r), Some(c)) = (r.checked_sub(1), c.checked_sub(1)) else {
+                    continue;
+                };
+                if r >= rows || c >= cols {
+                    continue;
+                }
+                let Some((center, (gx, gy))) = smoothed[r * cols + c] else {
+                    continue;
+                };
+
+                // Offset of the cell from the center of the face.
+                let (dx, dy) = (col as f64 - c as f64 - 0.5, row as f64 - r as f64 - 0.5);
+                sum += center + (gx * dx + gy * dy) * self.cell_size;
+                count += 1;
+            }
+
+            Some(if count > 0 {
+                (sum / count as f64) as f32
+            } else {
+                w.center()
+            })
+        })
+    }
+
+    /// Returns the height of the center and the normal of the face, whose
+    /// north-west corner is the center of the cell. None if any corner has no data.
+    fn face(&self, row: usize, col: usize) -> Option<(f64, [f64; 3])> {
+        let a = self.get(row, col)? as f64;
+        let b = self.get(row, col + 1)? as f64;
+        let c = self.get(row + 1, col)? as f64;
+        let d = self.get(row + 1, col + 1)? as f64;
+
+        let dx = ((b - a) + (d - c)) / (2.0 * self.cell_size);
+        let dy = ((c - a) + (d - b)) / (2.0 * self.cell_size);
+        let length = (dx * dx + dy * dy + 1.0).sqrt();
+        Some((
+            (a + b + c + d) / 4.0,
+            [-dx / length, -dy / length, 1.0 / length],
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::SmoothMethod;
+
+    #[test]
+    fn smoothing_removes_noise() {
+        // Terrace: flat top, a 45° slope and a flat bottom.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            1.0,
+            12,
+            5,
+            0.0,
+        );
+        for row in 0..5 {
+            for col in 0..12 {
+                raster.set(row, col, col.clamp(3, 8) as f32);
+            }
+        }
+        let terrace = raster.clone();
+
+        let mut spiked = raster.clone();
+        spiked.set(2, 5, 9.0);
+        let median = spiked.smooth(SmoothMethod::Median { radius: 1 });
+        assert_eq!(median.get(2, 5), Some(5.0));
+        assert_eq!(median.get(2, 10), Some(8.0));
+
+        // Checkerboard noise on the whole terrace.
+        for row in 0..5 {
+            for col in 0..12 {
+                let noise = if (row + col) % 2 == 0 { 0.1 } else { -0.1 };
+                raster.set(row, col, raster.get(row, col).unwrap() + noise);
+            }
+        }
+        let error = |smoothed: &Raster<f32>, row, col| {
+            (smoothed.get(row, col).unwrap() - terrace.get(row, col).unwrap()).abs()
+        };
+
+        let gaussian = raster.smooth(SmoothMethod::Gaussian { sigma: 1.0 });
+        assert!(error(&gaussian, 2, 5) < 0.05);
+        // Edge of the terrace is rounded.
+        assert!(error(&gaussian, 2, 8) > 0.1);
+
+        let preserving = raster.smooth(SmoothMethod::FeaturePreserving);
+        for row in 0..5 {
+            for col in 0..12 {
+                assert!(error(&preserving, row, col) < 0.01, "{row} {col}");
+            }
+        }
+    }
+}
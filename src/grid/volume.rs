@@ -0,0 +1,109 @@
+//! Volumes of the terrain, for example of quarries and earthworks.
+
+use crate::{Point, qtree::is_inside_ring};
+
+use super::{AlignmentError, Raster};
+
+/// Volumes of material removed and added between two surfaces.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CutFill {
+    /// Volume in m³ where the new surface is lower.
+    pub cut: f64,
+    /// Volume in m³ where the new surface is higher.
+    pub fill: f64,
+}
+
+impl CutFill {
+    /// Returns the net change of the volume in m³, positive if material was added.
+    pub fn net(&self) -> f64 {
+        self.fill - self.cut
+    }
+}
+
+impl Raster<f32> {
+    /// Computes the volume in m³ between the surface and the reference level, where the surface is higher.
+    ///
+    /// Polygon is given by its vertices and is implicitly closed. Cells are included
+    /// if their center is inside the polygon, and cells without data are skipped.
+    pub fn volume_above<U>(&self, reference_level: f32, polygon: &[Point<U>]) -> f64 {
+        self.sum_in_polygon(polygon, |z| (z - reference_level).max(0.0) as f64)
+    }
+
+    /// Computes volumes of cut and fill from this surface to the `other`, e.g. from
+    /// an older to a newer survey.
+    ///
+    /// Polygon and cells are treated as in [`Raster::volume_above`]. Rasters are
+    /// aligned as in [`Raster::zip_map`] and only their overlap is compared.
+    pub fn cut_fill<U>(
+        &self,
+        other: &Raster<f32>,
+        polygon: &[Point<U>],
+    ) -> Result<CutFill, AlignmentError> {
+        let difference = self.zip_map(other, |before, after| after - before)?;
+
+        Ok(CutFill {
+            cut: difference.sum_in_polygon(polygon, |d| (-d).max(0.0) as f64),
+            fill: difference.sum_in_polygon(polygon, |d| d.max(0.0) as f64),
+        })
+    }
+
+    /// Sums `f` of cells inside the polygon, multiplied by the area of a cell.
+    fn sum_in_polygon<U, F>(&self, polygon: &[Point<U>], f: F) -> f64
+    where
+        F: Fn(f32) -> f64,
+    {
+        if polygon.len() < 3 {
+            return 0.0;
+        }
+
+        let mut sum = 0.0;
+        for row in 0..self.nrows {
+            for col in 0..self.ncols {
+                if let Some(z) = self.get(row, col)
+                    && is_inside_ring(
+                        &self.cell_center(row, col),
+                        polygon.iter().map(|v| (v.x, v.y)),
+                    )
+                {
+                    sum += f(z);
+                }
+            }
+        }
+
+        sum * self.cell_size * self.cell_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    #[test]
+    fn volumes_in_polygon() {
+        let origin = Point {
+            x: 0.0,
+            y: 30.0,
+            data: (),
+        };
+        let before = Raster::new(origin.clone(), 10.0, 4, 4, 100.0);
+        let mut after = before.clone();
+        after.set(1, 1, 103.0);
+        after.set(2, 2, 98.0);
+        // Outside of the polygon.
+        after.set(0, 3, 150.0);
+
+        let polygon: Vec<_> = [(5.0, 5.0), (25.0, 5.0), (25.0, 25.0), (5.0, 25.0)]
+            .into_iter()
+            .map(|(x, y)| Point { x, y, data: () })
+            .collect();
+
+        assert_eq!(after.volume_above(100.0, &polygon), 300.0);
+        assert_eq!(after.volume_above(99.0, &polygon), 600.0);
+
+        let change = before.cut_fill(&after, &polygon).unwrap();
+        assert_eq!(change.cut, 200.0);
+        assert_eq!(change.fill, 300.0);
+        assert_eq!(change.net(), 100.0);
+    }
+}
@@ -0,0 +1,331 @@
+//! Interpolation of scattered points with heights onto a regular grid.
+//!
+//! Points, for example LiDAR ground points, are taken from a quad tree,
+//! which finds the neighbours of every cell quickly.
+
+#[cfg(feature = "kriging")]
+pub mod kriging;
+mod tps;
+
+use crate::{
+    Area, Point,
+    grid::{NODATA, Raster},
+    qtree::QuadTree,
+};
+
+pub use tps::thin_plate_spline;
+
+/// Points closer than this are considered to be at the same location.
+const EPSILON: f64 = 1e-6;
+
+/// Extent and resolution of the interpolated raster.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSpec {
+    /// Center of the north-west cell.
+    pub origin: Point,
+    /// Width and height of a cell in meters.
+    pub cell_size: f64,
+    pub ncols: usize,
+    pub nrows: usize,
+}
+
+impl GridSpec {
+    /// Creates the grid of cells covering the area, starting at its north-west corner.
+    pub fn covering(area: &Area, cell_size: f64) -> Self {
+        Self {
+            origin: Point {
+                x: area.min_x() + cell_size / 2.0,
+                y: area.max_y() - cell_size / 2.0,
+                data: (),
+            },
+            cell_size,
+            ncols: ((area.max_x() - area.min_x()) / cell_size).ceil().max(1.0) as usize,
+            nrows: ((area.max_y() - area.min_y()) / cell_size).ceil().max(1.0) as usize,
+        }
+    }
+
+    /// Creates the grid with the same cells as the raster.
+    pub fn of<T: Copy + PartialEq>(raster: &Raster<T>) -> Self {
+        Self {
+            origin: raster.origin().clone(),
+            cell_size: raster.cell_size(),
+            ncols: raster.ncols(),
+            nrows: raster.nrows(),
+        }
+    }
+
+    /// Returns the center of the cell with the index in row-major order.
+    fn center(&self, i: usize) -> Point {
+        Point {
+            x: self.origin.x + (i % self.ncols) as f64 * self.cell_size,
+            y: self.origin.y - (i / self.ncols) as f64 * self.cell_size,
+            data: (),
+        }
+    }
+
+    /// Creates a raster of the grid by calling `f` with the center of every cell.
+    ///
+    /// Cells where `f` returns None have [`NODATA`]. With the `rayon` feature,
+    /// cells are processed on multiple threads.
+    fn map<F>(&self, f: F) -> Raster<f32>
+    where
+        F: Fn(&Point) -> Option<f32> + Sync,
+    {
+        let value = |i| f(&self.center(i));
+        #[cfg(feature = "rayon")]
+        let cells: Vec<_> = {
+            use rayon::prelude::*;
+            (0..self.nrows * self.ncols)
+                .into_par_iter()
+                .map(value)
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let cells: Vec<_> = (0..self.nrows * self.ncols).map(value).collect();
+
+        let mut raster = Raster::new(
+            self.origin.clone(),
+            self.cell_size,
+            self.ncols,
+            self.nrows,
+            NODATA,
+        )
+        .with_nodata(Some(NODATA));
+        for (i, cell) in cells.into_iter().enumerate() {
+            if let Some(value) = cell {
+                raster.set(i / self.ncols, i % self.ncols, value);
+            }
+        }
+        raster
+    }
+}
+
+/// Inverse distance weighted mean of the heights around a location, which is
+/// the kernel of all inverse distance interpolations of the crate.
+///
+/// Points are added one at a time, so that they can come straight from the
+/// queries of the tree. Points further than `max_distance` are skipped, and
+/// a point at the location gives its height exactly.
+pub(crate) struct IdwSum<'a> {
+    location: &'a Point,
+    power: f64,
+    max_distance: f64,
+    weighted: f64,
+    total: f64,
+    exact: Option<f32>,
+}
+
+impl<'a> IdwSum<'a> {
+    pub(crate) fn new(location: &'a Point, power: f64, max_distance: f64) -> Self {
+        Self {
+            location,
+            power,
+            max_distance,
+            weighted: 0.0,
+            total: 0.0,
+            exact: None,
+        }
+    }
+
+    pub(crate) fn add(&mut self, point: &Point<f32>) {
+        if self.exact.is_some() {
+            return;
+        }
+
+        let distance_sq = point.distance_sq(self.location);
+        if distance_sq < EPSILON * EPSILON {
+            self.exact = Some(point.data);
+            return;
+        }
+        if distance_sq > self.max_distance * self.max_distance {
+            return;
+        }
+
+        // Power of 2 is the usual choice, which doesn't need the slower `powf`.
+        let weight = if self.power == 2.0 {
+            1.0 / distance_sq
+        } else {
+            distance_sq.powf(-self.power / 2.0)
+        };
+        self.weighted += weight * point.data as f64;
+        self.total += weight;
+    }
+
+    /// Returns the interpolated height, or None if no point was close enough.
+    pub(crate) fn value(&self) -> Option<f32> {
+        self.exact
+            .or((self.total > 0.0).then(|| (self.weighted / self.total) as f32))
+    }
+}
+
+impl<'b> Extend<&'b Point<f32>> for IdwSum<'_> {
+    fn extend<I: IntoIterator<Item = &'b Point<f32>>>(&mut self, iter: I) {
+        for point in iter {
+            self.add(point);
+        }
+    }
+}
+
+/// Interpolates heights of the points with inverse distance weighting.
+///
+/// Every cell is the mean of the points within `search_radius` meters of its
+/// center, weighted by the inverse distance to the `power`. Higher powers give
+/// more weight to the nearest points, and 2 is the usual choice. Cells without
+/// any point in the radius have [`NODATA`].
+pub fn idw(tree: &QuadTree<f32>, spec: &GridSpec, power: f64, search_radius: f64) -> Raster<f32> {
+    spec.map(|center| {
+        let area = Area::from_bounds(
+            center.x - search_radius,
+            center.y - search_radius,
+            center.x + search_radius,
+            center.y + search_radius,
+        );
+
+        let mut sum = IdwSum::new(center, power, search_radius);
+        let _ = tree.query_with(&area, |p| sum.add(p));
+        sum.value()
+    })
+}
+
+/// Interpolates heights of the points with natural neighbour interpolation.
+///
+/// Values are smooth and stay within the heights of the neighbouring points,
+/// without the bull's eyes of [`idw`] around isolated points. It is computed
+/// with the discrete Sibson method of Park et al.: every cell spreads the height
+/// of its nearest point to all cells closer than that point, and cells average
+/// the heights they receive. Empty tree gives a raster of [`NODATA`].
+///
+/// The work grows with the square of the distances between the points,
+/// so the cells should not be much smaller than the spacing of the points.
+pub fn natural_neighbor(tree: &QuadTree<f32>, spec: &GridSpec) -> Raster<f32> {
+    // Height of the nearest point and the distance to it in cells.
+    let nearest: Vec<_> = (0..spec.nrows * spec.ncols)
+        .map(|i| {
+            let center = spec.center(i);
+            let p = tree.nearest(&center)?;
+            Some((p.data as f64, p.distance(&center) / spec.cell_size))
+        })
+        .collect();
+
+    let mut sums = vec![(0.0, 0u32); spec.nrows * spec.ncols];
+    for (i, cell) in nearest.iter().enumerate() {
+        let Some((value, radius)) = *cell else {
+            continue;
+        };
+        let (row, col) = ((i / spec.ncols) as isize, (i % spec.ncols) as isize);
+
+        let reach = radius.floor() as isize;
+        for dr in -reach..=reach {
+            let r = row + dr;
+            if r < 0 || r >= spec.nrows as isize {
+                continue;
+            }
+            for dc in -reach..=reach {
+                let c = col + dc;
+                if c < 0
+                    || c >= spec.ncols as isize
+                    || ((dr * dr + dc * dc) as f64) > radius * radius
+                {
+                    continue;
+                }
+
+                let (sum, count) = &mut sums[r as usize * spec.ncols + c as usize];
+                *sum += value;
+                *count += 1;
+            }
+        }
+    }
+
+    let mut raster = Raster::new(
+        spec.origin.clone(),
+        spec.cell_size,
+        spec.ncols,
+        spec.nrows,
+        NODATA,
+    )
+    .with_nodata(Some(NODATA));
+    for (i, (sum, count)) in sums.into_iter().enumerate() {
+        if count > 0 {
+            raster.set(i / spec.ncols, i % spec.ncols, (sum / count as f64) as f32);
+        }
+    }
+    raster
+}
+
+/// Solves the linear system `matrix * x = rhs` with Gaussian elimination,
+/// writing `x` into `rhs`. Matrix is square and stored by rows.
+///
+/// None is returned if the matrix is singular.
+fn solve(matrix: &mut [f64], rhs: &mut [f64]) -> Option<()> {
+    let n = rhs.len();
+    for col in 0..n {
+        // Partial pivoting keeps the elimination stable.
+        let pivot = (col..n).max_by(|&a, &b| {
+            matrix[a * n + col]
+                .abs()
+                .total_cmp(&matrix[b * n + col].abs())
+        })?;
+        if matrix[pivot * n + col].abs() < 1e-12 {
+            return None;
+        }
+        if pivot != col {
+            for k in 0..n {
+                matrix.swap(pivot * n + k, col * n + k);
+            }
+            rhs.swap(pivot, col);
+        }
+
+        for row in col + 1..n {
+            let factor = matrix[row * n + col] / matrix[col * n + col];
+            for k in col..n {
+                matrix[row * n + k] -= factor * matrix[col * n + k];
+            }
+            rhs[row] -= factor * rhs[col];
+        }
+    }
+
+    for row in (0..n).rev() {
+        let sum: f64 = (row + 1..n).map(|k| matrix[row * n + k] * rhs[k]).sum();
+        rhs[row] = (rhs[row] - sum) / matrix[row * n + row];
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point, qtree::QuadTree};
+
+    use super::{GridSpec, idw, natural_neighbor};
+
+    #[test]
+    fn interpolate_plane() {
+        // Points every 10 m on a plane rising 1 m per meter towards east.
+        let points: Vec<_> = (0..36)
+            .map(|i| {
+                let (x, y) = ((i % 6) as f64 * 10.0, (i / 6) as f64 * 10.0);
+                Point {
+                    x,
+                    y,
+                    data: x as f32,
+                }
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+        let spec = GridSpec::covering(&Area::from_bounds(0.0, 0.0, 50.0, 50.0), 2.0);
+        assert_eq!((spec.ncols, spec.nrows), (25, 25));
+
+        let raster = idw(&tree, &spec, 2.0, 15.0);
+        // Cells are symmetric between the points, so they get the mean.
+        assert!((raster.value_at(25.0, 25.0).unwrap() - 25.0).abs() < 1e-4);
+        let near = raster.value_at(21.0, 25.0).unwrap();
+        assert!(near > 20.0 && near < 25.0, "{near}");
+        assert_eq!(idw(&tree, &spec, 2.0, 0.5).value_at(25.0, 25.0), None);
+
+        let spec = GridSpec::covering(&Area::from_bounds(0.0, 0.0, 50.0, 50.0), 1.0);
+        let raster = natural_neighbor(&tree, &spec);
+        for (x, y) in [(11.5, 11.5), (25.5, 37.5), (33.5, 17.5)] {
+            let z = raster.value_at(x, y).unwrap();
+            assert!((z - x as f32).abs() < 0.5, "{x} {z}");
+        }
+    }
+}
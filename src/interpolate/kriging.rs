@@ -0,0 +1,324 @@
+//! Ordinary kriging with a fitted variogram.
+//!
+//! Unlike the other interpolations, kriging also estimates the uncertainty
+//! of every cell from the spatial correlation of the heights.
+
+use crate::{
+    Point,
+    grid::{NODATA, Raster},
+    qtree::QuadTree,
+};
+
+use super::{GridSpec, solve};
+
+/// Number of ranges tried when fitting the variogram.
+const FIT_STEPS: usize = 100;
+
+/// Shape of the variogram, rising from the nugget to the sill at the range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VariogramModel {
+    /// Reaches the sill exactly at the range.
+    #[default]
+    Spherical,
+    /// Approaches the sill exponentially, reaching 95 % of it at the range.
+    Exponential,
+    /// Rises slowly near zero, for very smooth surfaces.
+    Gaussian,
+}
+
+/// Semivariance of the heights of point pairs at similar distances.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VariogramBin {
+    /// Mean distance of the pairs in meters.
+    pub distance: f64,
+    /// Half of the mean squared difference of the heights.
+    pub semivariance: f64,
+    /// Number of pairs in the bin.
+    pub pairs: usize,
+}
+
+/// Variogram model describing how the heights differ with the distance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Variogram {
+    pub model: VariogramModel,
+    /// Semivariance at an infinitely small distance, e.g. due to measurement errors.
+    pub nugget: f64,
+    /// Semivariance added to the nugget at the range.
+    pub partial_sill: f64,
+    /// Distance in meters beyond which the heights are not correlated.
+    pub range: f64,
+}
+
+impl Variogram {
+    /// Returns the semivariance at the distance.
+    pub fn semivariance(&self, distance: f64) -> f64 {
+        if distance <= 0.0 {
+            return 0.0;
+        }
+
+        self.nugget + self.partial_sill * self.shape(distance)
+    }
+
+    /// Fits the model to the empirical variogram with least squares, weighted by the pairs.
+    ///
+    /// None is returned if there are no bins.
+    pub fn fit(bins: &[VariogramBin], model: VariogramModel) -> Option<Variogram> {
+        let max_distance = bins.iter().map(|b| b.distance).reduce(f64::max)?;
+
+        let mut best: Option<(f64, Variogram)> = None;
+        for step in 1..=FIT_STEPS {
+            let mut variogram = Variogram {
+                model,
+                nugget: 0.0,
+                partial_sill: 1.0,
+                range: max_distance * step as f64 / FIT_STEPS as f64,
+            };
+
+            // With a fixed range, semivariance is linear in the nugget and the sill.
+            let (mut sw, mut sf, mut sff, mut sg, mut sfg) = (0.0, 0.0, 0.0, 0.0, 0.0);
+            for bin in bins {
+                let (w, f, g) = (
+                    bin.pairs as f64,
+                    variogram.shape(bin.distance),
+                    bin.semivariance,
+                );
+                sw += w;
+                sf += w * f;
+                sff += w * f * f;
+                sg += w * g;
+                sfg += w * f * g;
+            }
+            let det = sw * sff - sf * sf;
+            if det.abs() > f64::EPSILON {
+                variogram.nugget = (sg * sff - sf * sfg) / det;
+                variogram.partial_sill = (sw * sfg - sf * sg) / det;
+            }
+            if variogram.nugget < 0.0 || det.abs() <= f64::EPSILON {
+                variogram.nugget = 0.0;
+                variogram.partial_sill = if sff > 0.0 { sfg / sff } else { 0.0 };
+            }
+            variogram.partial_sill = variogram.partial_sill.max(0.0);
+
+            let error: f64 = bins
+                .iter()
+                .map(|b| {
+                    b.pairs as f64 * (variogram.semivariance(b.distance) - b.semivariance).powi(2)
+                })
+                .sum();
+            if best.as_ref().is_none_or(|(e, _)| error < *e) {
+                best = Some((error, variogram));
+            }
+        }
+
+        best.map(|(_, variogram)| variogram)
+    }
+
+    /// Returns the semivariance without the nugget, relative to the partial sill.
+    fn shape(&self, distance: f64) -> f64 {
+        let h = distance / self.range;
+        match self.model {
+            VariogramModel::Spherical if h >= 1.0 => 1.0,
+            VariogramModel::Spherical => 1.5 * h - 0.5 * h.powi(3),
+            VariogramModel::Exponential => 1.0 - (-3.0 * h).exp(),
+            VariogramModel::Gaussian => 1.0 - (-3.0 * h * h).exp(),
+        }
+    }
+}
+
+/// Computes the empirical variogram of the points with heights as data.
+///
+/// Pairs of points up to `max_distance` apart are grouped into bins `lag` meters
+/// wide, and empty bins are left out. Every pair of points is compared, so large
+/// point sets should be thinned first, e.g. with [`QuadTree::sample`].
+///
+/// # Panics
+///
+/// Panics if `lag` is not positive and finite, or if `max_distance` is negative or not finite.
+pub fn empirical_variogram(
+    points: &[Point<f32>],
+    lag: f64,
+    max_distance: f64,
+) -> Vec<VariogramBin> {
+    assert!(
+        lag > 0.0 && lag.is_finite(),
+        "lag must be positive and finite"
+    );
+    assert!(
+        max_distance >= 0.0 && max_distance.is_finite(),
+        "max distance must be non-negative and finite"
+    );
+
+    // Coincident points fall into the first bin even with zero max distance.
+    let count = ((max_distance / lag).ceil() as usize).max(1);
+    let mut bins = vec![(0.0, 0.0, 0); count];
+    for (i, a) in points.iter().enumerate() {
+        for b in &points[i + 1..] {
+            let distance = a.distance(b);
+            if distance > max_distance {
+                continue;
+            }
+
+            let (d, g, n) = &mut bins[((distance / lag) as usize).min(count - 1)];
+            *d += distance;
+            *g += (a.data as f64 - b.data as f64).powi(2) / 2.0;
+            *n += 1;
+        }
+    }
+
+    bins.into_iter()
+        .filter(|&(_, _, n)| n > 0)
+        .map(|(d, g, n)| VariogramBin {
+            distance: d / n as f64,
+            semivariance: g / n as f64,
+            pairs: n,
+        })
+        .collect()
+}
+
+/// Heights interpolated by kriging, with their uncertainty.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kriged {
+    /// Predicted heights in meters.
+    pub prediction: Raster<f32>,
+    /// Kriging variance of the predictions in m², which grows away from the points.
+    pub variance: Raster<f32>,
+}
+
+/// Interpolates heights of the points with ordinary kriging.
+///
+/// Every cell is predicted from its `neighbours` nearest points, weighted so that
+/// the expected error is the smallest for the variogram. Cells where the kriging
+/// system can't be solved, e.g. because the tree is empty, have [`NODATA`].
+pub fn ordinary_kriging(
+    tree: &QuadTree<f32>,
+    spec: &GridSpec,
+    variogram: &Variogram,
+    neighbours: usize,
+) -> Kriged {
+    let predict = |center: &Point| {
+        let mut nearest = Vec::with_capacity(neighbours);
+        tree.nearest_k(center, neighbours, &mut nearest);
+        let n = nearest.len();
+        if n == 0 {
+            return None;
+        }
+
+        // Semivariances between the points, bordered by the unbiasedness constraint.
+        let size = n + 1;
+        let mut matrix = vec![0.0; size * size];
+        let mut rhs = vec![1.0; size];
+        for (i, a) in nearest.iter().enumerate() {
+            for (j, b) in nearest.iter().enumerate() {
+                matrix[i * size + j] = variogram.semivariance(a.distance(b));
+            }
+            matrix[i * size + n] = 1.0;
+            matrix[n * size + i] = 1.0;
+            rhs[i] = variogram.semivariance(a.distance(center));
+        }
+        let targets = rhs.clone();
+        solve(&mut matrix, &mut rhs)?;
+
+        let prediction: f64 = nearest
+            .iter()
+            .zip(&rhs)
+            .map(|(p, w)| w * p.data as f64)
+            .sum();
+        let variance: f64 = rhs.iter().zip(&targets).map(|(w, g)| w * g).sum();
+        Some((prediction as f32, variance.max(0.0) as f32))
+    };
+
+    let centers: Vec<_> = (0..spec.nrows * spec.ncols)
+        .map(|i| spec.center(i))
+        .collect();
+    #[cfg(feature = "rayon")]
+    let cells: Vec<_> = {
+        use rayon::prelude::*;
+        centers.par_iter().map(predict).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let cells: Vec<_> = centers.iter().map(predict).collect();
+
+    let empty = Raster::new(
+        spec.origin.clone(),
+        spec.cell_size,
+        spec.ncols,
+        spec.nrows,
+        NODATA,
+    )
+    .with_nodata(Some(NODATA));
+    let mut kriged = Kriged {
+        prediction: empty.clone(),
+        variance: empty,
+    };
+    for (i, cell) in cells.into_iter().enumerate() {
+        if let Some((prediction, variance)) = cell {
+            let (row, col) = (i / spec.ncols, i % spec.ncols);
+            kriged.prediction.set(row, col, prediction);
+            kriged.variance.set(row, col, variance);
+        }
+    }
+    kriged
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point, interpolate::GridSpec, qtree::QuadTree};
+
+    use super::{Variogram, VariogramBin, VariogramModel, empirical_variogram, ordinary_kriging};
+
+    #[test]
+    fn kriging_with_fitted_variogram() {
+        let truth = Variogram {
+            model: VariogramModel::Spherical,
+            nugget: 0.5,
+            partial_sill: 4.0,
+            range: 300.0,
+        };
+        let bins: Vec<_> = (1..=20)
+            .map(|i| VariogramBin {
+                distance: i as f64 * 25.0,
+                semivariance: truth.semivariance(i as f64 * 25.0),
+                pairs: 10,
+            })
+            .collect();
+        let fitted = Variogram::fit(&bins, VariogramModel::Spherical).unwrap();
+        assert!((fitted.range - 300.0).abs() < 10.0, "{fitted:?}");
+        assert!((fitted.nugget - 0.5).abs() < 0.1, "{fitted:?}");
+        assert!((fitted.partial_sill - 4.0).abs() < 0.1, "{fitted:?}");
+
+        // Heights alternating between rows of points 10 m apart.
+        let points: Vec<_> = (0..25)
+            .map(|i| {
+                let (x, y) = ((i % 5) as f64 * 10.0, (i / 5) as f64 * 10.0);
+                Point {
+                    x,
+                    y,
+                    data: if i / 5 % 2 == 0 { 100.0 } else { 102.0 },
+                }
+            })
+            .collect();
+        let bins = empirical_variogram(&points, 12.0, 40.0);
+        assert_eq!(bins[0].pairs, 40);
+        assert_eq!(bins[0].semivariance, 1.0);
+
+        let variogram = Variogram {
+            model: VariogramModel::Exponential,
+            nugget: 0.0,
+            partial_sill: 1.0,
+            range: 30.0,
+        };
+        let tree = QuadTree::from_points(points);
+        let spec = GridSpec::covering(&Area::from_bounds(-5.0, -5.0, 75.0, 45.0), 10.0);
+        let kriged = ordinary_kriging(&tree, &spec, &variogram, 8);
+
+        // Kriging honours the points, where it is certain.
+        let at_point = kriged.prediction.value_at(10.0, 10.0).unwrap();
+        assert!((at_point - 102.0).abs() < 1e-3, "{at_point}");
+        assert!(kriged.variance.value_at(10.0, 10.0).unwrap() < 1e-3);
+        // Uncertainty grows away from the points.
+        assert!(kriged.variance.value_at(70.0, 10.0).unwrap() > 0.5);
+    }
+}
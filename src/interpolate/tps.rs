@@ -0,0 +1,103 @@
+//! Thin plate spline interpolation.
+
+use crate::{Point, grid::Raster};
+
+use super::{GridSpec, solve};
+
+/// Interpolates heights of the points with a thin plate spline.
+///
+/// The spline is the smoothest surface passing through the points, which suits
+/// small sets of points, such as surveyed spot heights, better than [`idw`](super::idw).
+/// With `smoothing` above zero, the surface doesn't have to pass exactly through
+/// the points, which dampens their measurement errors.
+///
+/// Every point affects every cell and the system of equations grows with the
+/// square of the points, so it should not be used for more than a few thousand points.
+/// None is returned if there are less than three points or if they all lie on a line.
+pub fn thin_plate_spline(
+    points: &[Point<f32>],
+    spec: &GridSpec,
+    smoothing: f64,
+) -> Option<Raster<f32>> {
+    let first = points.first()?;
+    // Coordinates relative to the first point keep the precision of the projection.
+    let local: Vec<_> = points
+        .iter()
+        .map(|p| (p.x - first.x, p.y - first.y, p.data as f64))
+        .collect();
+
+    // Kernel of the points, bordered by the affine part of the surface.
+    let n = local.len();
+    let size = n + 3;
+    let mut matrix = vec![0.0; size * size];
+    let mut weights = vec![0.0; size];
+    for (i, &(xi, yi, zi)) in local.iter().enumerate() {
+        for (j, &(xj, yj, _)) in local.iter().enumerate() {
+            matrix[i * size + j] = kernel((xi - xj).hypot(yi - yj));
+        }
+        matrix[i * size + i] += smoothing;
+
+        for (k, v) in [1.0, xi, yi].into_iter().enumerate() {
+            matrix[i * size + n + k] = v;
+            matrix[(n + k) * size + i] = v;
+        }
+        weights[i] = zi;
+    }
+    solve(&mut matrix, &mut weights)?;
+
+    Some(spec.map(|center| {
+        let (x, y) = (center.x - first.x, center.y - first.y);
+        let bent: f64 = local
+            .iter()
+            .zip(&weights)
+            .map(|(&(px, py, _), w)| w * kernel((x - px).hypot(y - py)))
+            .sum();
+        Some((bent + weights[n] + weights[n + 1] * x + weights[n + 2] * y) as f32)
+    }))
+}
+
+/// Radial basis function of the thin plate spline.
+fn kernel(r: f64) -> f64 {
+    if r > 0.0 { r * r * r.ln() } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point, interpolate::GridSpec};
+
+    use super::thin_plate_spline;
+
+    #[test]
+    fn spline_through_spot_heights() {
+        let heights = [
+            (462000.0, 101000.0, 300.0),
+            (462100.0, 101000.0, 310.0),
+            (462000.0, 101100.0, 320.0),
+            (462100.0, 101100.0, 330.0),
+            (462050.0, 101050.0, 340.0),
+        ];
+        let points: Vec<_> = heights
+            .iter()
+            .map(|&(x, y, z)| Point { x, y, data: z })
+            .collect();
+        let spec = GridSpec::covering(
+            &Area::from_bounds(461995.0, 100995.0, 462105.0, 101105.0),
+            10.0,
+        );
+
+        let raster = thin_plate_spline(&points, &spec, 0.0).unwrap();
+        for &(x, y, z) in &heights {
+            let got = raster.value_at(x, y).unwrap();
+            assert!((got - z).abs() < 1e-2, "{x} {y} {got}");
+        }
+        // Surface bulges smoothly around the middle point.
+        let between = raster.value_at(462025.0, 101025.0).unwrap();
+        assert!(between > 315.0 && between < 340.0, "{between}");
+
+        // Smoothed surface doesn't reach the outlier in the middle.
+        let smoothed = thin_plate_spline(&points, &spec, 1e5).unwrap();
+        assert!(smoothed.value_at(462050.0, 101050.0).unwrap() < 335.0);
+
+        assert!(thin_plate_spline(&points[..2], &spec, 0.0).is_none());
+    }
+}
@@ -5,7 +5,8 @@
 //!
 //! ## Data format
 //!
-//! The `DEM 0050` is Slovenia split into four regions - NW, NE, SW, SE.
+//! The `DEM 0050` is Slovenia split into four regions - NW, NE, SW, SE,
+//! see [`Area::region`] for their extents.
 //! It contains multiple `.xyz` files which contain data in format:
 //!
 //! ```text
@@ -15,12 +16,32 @@
 //! Point `(x, y)` represents a location on the map in the
 //! [D96/TM format](https://www.e-prostor.gov.si/podrocja/drzavni-koordinatni-sistem/horizontalna-sestavina/),
 //! also known as [EPSG:3794](https://epsg.io/3794).
+//! Points can be converted to and from WGS84 with [`Point::to_wgs84`] and [`Point::from_wgs84`].
 
 mod area;
+mod circle;
+mod error;
+mod plane;
 mod point;
+mod terrain;
 
+#[cfg(feature = "geo")]
+mod geo;
+
+pub mod crs;
 pub mod data;
+#[cfg(feature = "download")]
+pub mod download;
+pub mod grid;
+pub mod interpolate;
+pub mod mesh;
 pub mod qtree;
+pub mod tiles;
+pub mod tin;
 
 pub use area::*;
+pub use circle::*;
+pub use error::*;
+pub use plane::*;
 pub use point::*;
+pub use terrain::*;
@@ -0,0 +1,268 @@
+//! Triangle meshes of the terrain, for 3D printing and rendering.
+
+use std::io;
+
+use crate::{Point, grid::Raster};
+
+/// Triangle mesh of the terrain.
+///
+/// Vertices are relative to the origin, since `f32` isn't precise enough
+/// for D96/TM coordinates. X axis points east, y axis north and z axis up.
+/// Triangles are in counter clockwise order when viewed from above.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mesh {
+    /// Location of the vertex `[0, 0, 0]`.
+    pub origin: Point,
+    pub vertices: Vec<[f32; 3]>,
+    /// Indices of the triangle vertices.
+    pub triangles: Vec<[u32; 3]>,
+}
+
+/// Creates a mesh from heights of the raster.
+///
+/// Vertices are at the centers of the cells. Only every `decimation`-th row and
+/// column is used, so `1` keeps the full resolution, while `2` creates a mesh
+/// with a quarter of the vertices. Cells without data leave holes in the mesh.
+///
+/// # Panics
+///
+/// Panics if `decimation` is zero.
+pub fn from_raster(raster: &Raster<f32>, decimation: usize) -> Mesh {
+    assert!(decimation > 0, "decimation must be positive");
+
+    let origin = raster.origin().clone();
+    let rows: Vec<_> = (0..raster.nrows()).step_by(decimation).collect();
+    let cols: Vec<_> = (0..raster.ncols()).step_by(decimation).collect();
+
+    let mut vertices = vec![];
+    let mut indices = vec![None; rows.len() * cols.len()];
+    for (i, &row) in rows.iter().enumerate() {
+        for (j, &col) in cols.iter().enumerate() {
+            let Some(z) = raster.get(row, col) else {
+                continue;
+            };
+
+            let center = raster.cell_center(row, col);
+            indices[i * cols.len() + j] = Some(vertices.len() as u32);
+            vertices.push([
+                (center.x - origin.x) as f32,
+                (center.y - origin.y) as f32,
+                z,
+            ]);
+        }
+    }
+
+    let mut triangles = vec![];
+    for i in 0..rows.len().saturating_sub(1) {
+        for j in 0..cols.len().saturating_sub(1) {
+            let index = |i, j| indices[i * cols.len() + j];
+            let (tl, tr, bl, br) = (
+                index(i, j),
+                index(i, j + 1),
+                index(i + 1, j),
+                index(i + 1, j + 1),
+            );
+
+            if let (Some(tl), Some(bl), Some(br)) = (tl, bl, br) {
+                triangles.push([tl, bl, br]);
+            }
+            if let (Some(tl), Some(br), Some(tr)) = (tl, br, tr) {
+                triangles.push([tl, br, tr]);
+            }
+        }
+    }
+
+    Mesh {
+        origin,
+        vertices,
+        triangles,
+    }
+}
+
+impl Mesh {
+    /// Writes the mesh in Wavefront OBJ format.
+    pub fn write_obj(&self, mut writer: impl io::Write) -> io::Result<()> {
+        for [x, y, z] in &self.vertices {
+            writeln!(writer, "v {x} {y} {z}")?;
+        }
+        for [a, b, c] in &self.triangles {
+            // Indices in OBJ start with 1.
+            writeln!(writer, "f {} {} {}", a + 1, b + 1, c + 1)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the mesh in binary STL format.
+    pub fn write_stl(&self, mut writer: impl io::Write) -> io::Result<()> {
+        writer.write_all(&[0; 80])?;
+        writer.write_all(&(self.triangles.len() as u32).to_le_bytes())?;
+
+        for triangle in &self.triangles {
+            let [a, b, c] = triangle.map(|i| self.vertices[i as usize]);
+            let normal = normal(a, b, c);
+            for v in [normal, a, b, c].iter().flatten() {
+                writer.write_all(&v.to_le_bytes())?;
+            }
+            writer.write_all(&0u16.to_le_bytes())?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the mesh in binary glTF format (`.glb`).
+    ///
+    /// The y axis of glTF points up, so the vertices are rotated
+    /// and the north of the terrain points in the negative z direction.
+    pub fn write_glb(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let vertices: Vec<_> = self.vertices.iter().map(|&[x, y, z]| [x, z, -y]).collect();
+        let (min, max) = vertices
+            .iter()
+            .fold(([f32::MAX; 3], [f32::MIN; 3]), |(min, max), v| {
+                (
+                    std::array::from_fn(|i| min[i].min(v[i])),
+                    std::array::from_fn(|i| max[i].max(v[i])),
+                )
+            });
+        let (min, max) = if vertices.is_empty() {
+            ([0.0; 3], [0.0; 3])
+        } else {
+            (min, max)
+        };
+
+        let mut bin: Vec<u8> = vertices
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let positions_len = bin.len();
+        bin.extend(
+            self.triangles
+                .iter()
+                .flatten()
+                .flat_map(|i| i.to_le_bytes()),
+        );
+        let indices_len = bin.len() - positions_len;
+
+        let json = format!(
+            concat!(
+                r#"{{"asset":{{"version":"2.0","generator":"hribovje"}},"scene":0,"#,
+                r#""scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0}}],"#,
+                r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0}},"indices":1}}]}}],"#,
+                r#""buffers":[{{"byteLength":{}}}],"#,
+                r#""bufferViews":[{{"buffer":0,"byteOffset":0,"byteLength":{},"target":34962}},"#,
+                r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":34963}}],"#,
+                r#""accessors":[{{"bufferView":0,"componentType":5126,"count":{},"type":"VEC3","#,
+                r#""min":[{},{},{}],"max":[{},{},{}]}},"#,
+                r#"{{"bufferView":1,"componentType":5125,"count":{},"type":"SCALAR"}}]}}"#,
+            ),
+            bin.len(),
+            positions_len,
+            positions_len,
+            indices_len,
+            vertices.len(),
+            min[0],
+            min[1],
+            min[2],
+            max[0],
+            max[1],
+            max[2],
+            self.triangles.len() * 3,
+        );
+
+        // Chunks have to be aligned to 4 bytes.
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+        bin.resize(bin.len().next_multiple_of(4), 0);
+
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        writer.write_all(b"glTF")?;
+        writer.write_all(&2u32.to_le_bytes())?;
+        writer.write_all(&(length as u32).to_le_bytes())?;
+
+        writer.write_all(&(json.len() as u32).to_le_bytes())?;
+        writer.write_all(b"JSON")?;
+        writer.write_all(&json)?;
+
+        writer.write_all(&(bin.len() as u32).to_le_bytes())?;
+        writer.write_all(b"BIN\0")?;
+        writer.write_all(&bin)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the unit normal of the triangle.
+fn normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    let n = [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ];
+
+    let length = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    if length == 0.0 {
+        return [0.0; 3];
+    }
+    n.map(|v| v / length)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::from_raster;
+
+    #[test]
+    fn mesh_from_raster() {
+        let mut raster = Raster::new(
+            Point {
+                x: 1000.0,
+                y: 2000.0,
+                data: (),
+            },
+            1.0,
+            5,
+            5,
+            100.0,
+        )
+        .with_nodata(Some(-1.0));
+        raster.set(4, 4, -1.0);
+
+        let mesh = from_raster(&raster, 1);
+        assert_eq!(mesh.vertices.len(), 24);
+        assert_eq!(mesh.triangles.len(), 30);
+        assert_eq!(mesh.vertices[6], [1.0, -1.0, 100.0]);
+
+        // Triangles face upwards.
+        for [a, b, c] in &mesh.triangles {
+            let [a, b, c] = [a, b, c].map(|&i| mesh.vertices[i as usize]);
+            assert_eq!(super::normal(a, b, c), [0.0, 0.0, 1.0]);
+        }
+
+        let decimated = from_raster(&raster, 2);
+        assert_eq!(decimated.vertices.len(), 8);
+        assert_eq!(decimated.triangles.len(), 6);
+
+        let mut obj = vec![];
+        mesh.write_obj(&mut obj).unwrap();
+        let obj = String::from_utf8(obj).unwrap();
+        assert!(obj.starts_with("v 0 0 100\n"));
+        assert!(obj.contains("\nf 1 6 7\n"));
+
+        let mut stl = vec![];
+        mesh.write_stl(&mut stl).unwrap();
+        assert_eq!(stl.len(), 84 + 30 * 50);
+
+        let mut glb = vec![];
+        mesh.write_glb(&mut glb).unwrap();
+        assert_eq!(glb[..4], *b"glTF");
+        assert_eq!(
+            u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+            glb.len()
+        );
+        assert_eq!(glb.len() % 4, 0);
+    }
+}
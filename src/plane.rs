@@ -0,0 +1,162 @@
+use crate::Point;
+
+/// A plane `z = a * x + b * y + c` in D96/TM coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plane {
+    /// Rise of the plane per meter towards east.
+    pub a: f64,
+    /// Rise of the plane per meter towards north.
+    pub b: f64,
+    /// Height of the plane at the origin of the coordinates.
+    pub c: f64,
+}
+
+impl Plane {
+    /// Returns the height of the plane at the location.
+    pub fn height_at(&self, x: f64, y: f64) -> f64 {
+        self.a * x + self.b * y + self.c
+    }
+
+    /// Returns the slope of the plane in degrees.
+    pub fn slope(&self) -> f64 {
+        self.a.hypot(self.b).atan().to_degrees()
+    }
+}
+
+/// Plane fitted to the points, with the deviations of the points from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaneFit {
+    pub plane: Plane,
+    /// Height of every point above the plane, in the order of the points.
+    pub residuals: Vec<f32>,
+    /// Root mean square of the residuals in meters.
+    pub rmse: f64,
+}
+
+/// Fits a plane to the points with heights as data, with the least squares method.
+///
+/// None is returned if there are less than three points or if they all lie on a line.
+pub fn fit_plane(points: &[Point<f32>]) -> Option<PlaneFit> {
+    let plane = PlaneSums::from_iter(points.iter().map(|p| (p.x, p.y, p.data as f64))).solve()?;
+
+    let residuals: Vec<_> = points
+        .iter()
+        .map(|p| (p.data as f64 - plane.height_at(p.x, p.y)) as f32)
+        .collect();
+    let rmse = (residuals.iter().map(|&r| (r as f64).powi(2)).sum::<f64>()
+        / residuals.len() as f64)
+        .sqrt();
+
+    Some(PlaneFit {
+        plane,
+        residuals,
+        rmse,
+    })
+}
+
+/// Sums of the normal equations of the least squares plane.
+///
+/// Coordinates are relative to the first point, which keeps the precision
+/// with large coordinates of the projection.
+#[derive(Default)]
+pub(crate) struct PlaneSums {
+    origin: Option<(f64, f64)>,
+    n: f64,
+    x: f64,
+    y: f64,
+    z: f64,
+    xx: f64,
+    xy: f64,
+    yy: f64,
+    xz: f64,
+    yz: f64,
+}
+
+impl PlaneSums {
+    pub(crate) fn add(&mut self, x: f64, y: f64, z: f64) {
+        let (x0, y0) = *self.origin.get_or_insert((x, y));
+        let (x, y) = (x - x0, y - y0);
+
+        self.n += 1.0;
+        self.x += x;
+        self.y += y;
+        self.z += z;
+        self.xx += x * x;
+        self.xy += x * y;
+        self.yy += y * y;
+        self.xz += x * z;
+        self.yz += y * z;
+    }
+
+    pub(crate) fn solve(&self) -> Option<Plane> {
+        let (x0, y0) = self.origin?;
+        if self.n < 3.0 {
+            return None;
+        }
+
+        // Covariances around the mean.
+        let (mx, my, mz) = (self.x / self.n, self.y / self.n, self.z / self.n);
+        let sxx = self.xx - self.n * mx * mx;
+        let sxy = self.xy - self.n * mx * my;
+        let syy = self.yy - self.n * my * my;
+        let sxz = self.xz - self.n * mx * mz;
+        let syz = self.yz - self.n * my * mz;
+
+        let det = sxx * syy - sxy * sxy;
+        if det.abs() <= 1e-9 * (sxx * syy).max(f64::MIN_POSITIVE) {
+            return None;
+        }
+
+        let a = (sxz * syy - syz * sxy) / det;
+        let b = (syz * sxx - sxz * sxy) / det;
+        let c = mz - a * (mx + x0) - b * (my + y0);
+        Some(Plane { a, b, c })
+    }
+}
+
+impl FromIterator<(f64, f64, f64)> for PlaneSums {
+    fn from_iter<I: IntoIterator<Item = (f64, f64, f64)>>(iter: I) -> Self {
+        let mut sums = Self::default();
+        for (x, y, z) in iter {
+            sums.add(x, y, z);
+        }
+        sums
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::fit_plane;
+
+    #[test]
+    fn fit_plane_of_points() {
+        // Plane rising 0.1 m per meter to the east, with a bump in the middle.
+        let mut points: Vec<_> = (0..25)
+            .map(|i| {
+                let (x, y) = (462000.0 + (i % 5) as f64, 101000.0 + (i / 5) as f64);
+                Point {
+                    x,
+                    y,
+                    data: (300.0 + 0.1 * (x - 462000.0)) as f32,
+                }
+            })
+            .collect();
+
+        let fit = fit_plane(&points).unwrap();
+        assert!((fit.plane.a - 0.1).abs() < 1e-4);
+        assert!(fit.plane.b.abs() < 1e-4);
+        assert!((fit.plane.height_at(462000.0, 101002.0) - 300.0).abs() < 1e-3);
+        assert!(fit.rmse < 1e-4);
+
+        points[12].data += 1.0;
+        let fit = fit_plane(&points).unwrap();
+        assert!(fit.residuals[12] > 0.9);
+        assert!(fit.residuals[0] < 0.0);
+
+        // Points on a line don't define a plane.
+        assert_eq!(fit_plane(&points[..5]), None);
+    }
+}
@@ -1,16 +1,21 @@
+use crate::crs::TransverseMercator;
+
 /// A point on the map in D96/TM format.
 ///
+/// Coordinates are stored as `f64`, which keeps centimeter precision
+/// across the whole coordinate range of the projection.
 /// Point can hold additional data. By default that data is just unit.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point<T = ()> {
-    pub x: f32,
-    pub y: f32,
+    pub x: f64,
+    pub y: f64,
     pub data: T,
 }
 
 impl<T> Point<T> {
     /// Returns squared distance between two points.
-    pub fn distance_sq<U>(&self, other: &Point<U>) -> f32 {
+    pub fn distance_sq<U>(&self, other: &Point<U>) -> f64 {
         let dx = self.x - other.x;
         let dy = self.y - other.y;
 
@@ -18,7 +23,41 @@ impl<T> Point<T> {
     }
 
     /// Returns distance between two points in meters.
-    pub fn distance<U>(&self, other: &Point<U>) -> f32 {
+    pub fn distance<U>(&self, other: &Point<U>) -> f64 {
         self.distance_sq(other).sqrt()
     }
+
+    /// Converts the point to WGS84 `(lat, lon)` in degrees.
+    pub fn to_wgs84(&self) -> (f64, f64) {
+        TransverseMercator::D96_TM.inverse(self.x, self.y)
+    }
+}
+
+impl Point {
+    /// Creates a point from WGS84 latitude and longitude in degrees.
+    pub fn from_wgs84(lat: f64, lon: f64) -> Self {
+        let (x, y) = TransverseMercator::D96_TM.forward(lat, lon);
+        Self { x, y, data: () }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::{Area, Point};
+
+    #[test]
+    fn serde_roundtrip() {
+        let point = Point {
+            x: 462000.0,
+            y: 101000.0,
+            data: 295.5f32,
+        };
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(json, r#"{"x":462000.0,"y":101000.0,"data":295.5}"#);
+        assert_eq!(serde_json::from_str::<Point<f32>>(&json).unwrap(), point);
+
+        let area = Area::from_bounds(0.0, 0.0, 10.0, 20.0);
+        let json = serde_json::to_string(&area).unwrap();
+        assert_eq!(serde_json::from_str::<Area>(&json).unwrap(), area);
+    }
 }
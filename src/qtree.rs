@@ -4,12 +4,28 @@
 //! for zero allocation query and removal of points in
 //! a single operation.
 
+mod aggregate;
+mod density;
+mod iter;
+mod mapped;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod persist;
+mod stats;
+mod validate;
+
+use std::{cmp::Ordering, collections::BinaryHeap, io, ops::ControlFlow};
+
 use thiserror::Error;
 
-use crate::{Area, Point};
+use crate::{Area, Circle, Point};
 
-// Max points in leaf node
-const MAX_POINTS: usize = 1000;
+pub use aggregate::Aggregate;
+pub use density::density;
+pub use iter::{IntoIter, Iter};
+pub use mapped::MappedQuadTree;
+pub use stats::QuadTreeStats;
+pub use validate::ValidationError;
 
 #[derive(Debug)]
 enum NodeInner<T> {
@@ -28,6 +44,9 @@ enum NodeInner<T> {
 struct Node<T> {
     area: Area,
     inner: NodeInner<T>,
+    /// Statistics of the subtree, cached only in intermediate nodes
+    /// of trees with [`QuadTree::with_cached_aggregates`].
+    aggregate: Option<Aggregate>,
 }
 
 #[derive(Debug, Error)]
@@ -35,6 +54,10 @@ pub enum InsertError {
     /// Inserted point is outside of the tree area.
     #[error("point is outside of the tree area")]
     OutsideArea,
+
+    /// Tree keeps only the first point, and there is already a point with the same coordinates.
+    #[error("point with the same coordinates is already in the tree")]
+    Duplicate,
 }
 
 #[derive(Debug, Error)]
@@ -44,7 +67,115 @@ pub enum QueryError {
     OutsideArea,
 }
 
-pub struct QuadTree<T>(Node<T>);
+/// The error type that can occur when reading a persisted tree.
+#[derive(Debug, Error)]
+pub enum ReadError {
+    #[error("io error")]
+    Io(#[from] io::Error),
+
+    /// Data doesn't start with the expected magic bytes.
+    #[error("invalid magic bytes")]
+    InvalidMagic,
+
+    /// Data was written in a format version this library can't read.
+    #[error("unsupported version {0}")]
+    UnsupportedVersion(u32),
+
+    /// Node at the given index references data that doesn't exist.
+    #[error("invalid node at index {0}")]
+    InvalidNode(u64),
+}
+
+/// Outcome of a query that writes points into a fixed size buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QueryOutcome {
+    /// Number of points written into results.
+    /// In other words, you are interested in `results[..written]`.
+    pub written: usize,
+    /// Whether there were more points in the queried area than
+    /// could fit into results.
+    pub truncated: bool,
+}
+
+/// Tuning parameters of a [`QuadTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuadTreeConfig {
+    /// Max points in a leaf node before it is subdivided.
+    pub leaf_capacity: usize,
+    /// Max depth of the tree. Leaves at this depth are never subdivided,
+    /// which bounds the tree when many points share the same coordinates.
+    pub max_depth: usize,
+}
+
+impl Default for QuadTreeConfig {
+    fn default() -> Self {
+        Self {
+            leaf_capacity: 1000,
+            max_depth: 32,
+        }
+    }
+}
+
+impl QuadTreeConfig {
+    /// Sets the max points in a leaf node.
+    pub fn with_leaf_capacity(mut self, leaf_capacity: usize) -> Self {
+        self.leaf_capacity = leaf_capacity;
+        self
+    }
+
+    /// Sets the max depth of the tree.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Intermediate node with leaf children is merged into a leaf
+    /// once its children hold at most this many points.
+    fn merge_points(&self) -> usize {
+        self.leaf_capacity / 2
+    }
+}
+
+/// How [`QuadTree`] handles a point with the same coordinates
+/// as a point that is already in the tree.
+#[derive(Default)]
+pub enum DuplicatePolicy<T> {
+    /// Keep all the points. Leaves at max depth can grow over the leaf capacity.
+    #[default]
+    Allow,
+    /// Keep only the point that was inserted first.
+    Deduplicate,
+    /// Combine data of the new point into data of the existing point.
+    Aggregate(fn(&mut T, T)),
+}
+
+impl<T> Clone for DuplicatePolicy<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DuplicatePolicy<T> {}
+
+impl<T> std::fmt::Debug for DuplicatePolicy<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Allow => write!(f, "Allow"),
+            Self::Deduplicate => write!(f, "Deduplicate"),
+            Self::Aggregate(_) => write!(f, "Aggregate"),
+        }
+    }
+}
+
+pub struct QuadTree<T> {
+    root: Node<T>,
+    config: QuadTreeConfig,
+    duplicates: DuplicatePolicy<T>,
+    /// Converts data into values of the cached aggregates, None if they are not cached.
+    aggregate_value: Option<fn(&T) -> f64>,
+}
 
 impl<T> QuadTree<T> {
     /// Construct a new quad tree to insert points from specific bounds.
@@ -52,55 +183,296 @@ impl<T> QuadTree<T> {
     /// This tree will only be able to insert and query points inside this area.
     /// Inserting points outside of the provided area will result in an error!
     pub fn new(area: Area) -> Self {
-        Self(Node {
-            area,
-            inner: NodeInner::Leaf { points: vec![] },
-        })
+        Self::with_config(area, QuadTreeConfig::default())
+    }
+
+    /// Same as [`QuadTree::new`], but with custom tuning parameters.
+    pub fn with_config(area: Area, config: QuadTreeConfig) -> Self {
+        Self {
+            root: Node::new_leaf(area),
+            config,
+            duplicates: DuplicatePolicy::Allow,
+            aggregate_value: None,
+        }
+    }
+
+    /// Constructs a new quad tree containing all the points.
+    ///
+    /// Area of the tree is the minimum area that contains all the points.
+    /// The tree is bulk loaded by partitioning the points, which is
+    /// considerably faster than inserting points one by one.
+    /// Points with non-finite coordinates are skipped.
+    pub fn from_points(points: Vec<Point<T>>) -> Self {
+        Self::from_points_with_config(points, QuadTreeConfig::default())
+    }
+
+    /// Same as [`QuadTree::from_points`], but with custom tuning parameters.
+    pub fn from_points_with_config(mut points: Vec<Point<T>>, config: QuadTreeConfig) -> Self {
+        points.retain(|p| p.x.is_finite() && p.y.is_finite());
+        let area = Area::from_points(&points);
+        Self {
+            root: Node::build(area, points, &config, 0),
+            config,
+            duplicates: DuplicatePolicy::Allow,
+            aggregate_value: None,
+        }
+    }
+
+    /// Sets how points with the same coordinates are handled.
+    ///
+    /// Duplicates that are already in the tree are resolved immediately,
+    /// later inserts follow the policy. Default policy is [`DuplicatePolicy::Allow`].
+    pub fn with_duplicate_policy(mut self, policy: DuplicatePolicy<T>) -> Self {
+        self.duplicates = policy;
+        if !matches!(policy, DuplicatePolicy::Allow) {
+            self.root.resolve_duplicates(policy);
+            self.refresh_aggregates(None);
+        }
+        self
+    }
+
+    /// Returns the tuning parameters of the tree.
+    pub fn config(&self) -> &QuadTreeConfig {
+        &self.config
+    }
+
+    /// Returns the area covered by the tree.
+    pub fn area(&self) -> &Area {
+        &self.root.area
     }
 
     /// Returns the number of points in the tree.
     pub fn size(&self) -> usize {
-        self.0.size()
+        self.root.size()
+    }
+
+    /// Returns an iterator over all points in the tree.
+    ///
+    /// Points are yielded leaf by leaf, so consecutive points are close to each other.
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter::new(&self.root, None)
+    }
+
+    /// Returns a lazy iterator over points inside the given area.
+    ///
+    /// Areas outside of the tree yield no points.
+    pub fn iter_in(&self, area: &Area) -> Iter<'_, T> {
+        Iter::new(&self.root, Some(area.clone()))
     }
 
     /// Insert a new point into the tree.
+    ///
+    /// Points with the same coordinates as an existing point
+    /// are handled according to the [`DuplicatePolicy`] of the tree.
     pub fn insert(&mut self, point: Point<T>) -> Result<(), InsertError> {
-        self.0.insert(point)
+        let existing = match self.duplicates {
+            DuplicatePolicy::Allow => None,
+            _ => self.root.find_mut(&point),
+        };
+
+        let location = Area::from_bounds(point.x, point.y, point.x, point.y);
+        match (existing, self.duplicates) {
+            (Some(existing), DuplicatePolicy::Aggregate(aggregate)) => {
+                aggregate(&mut existing.data, point.data);
+            }
+            (Some(_), _) => return Ok(()),
+            (None, _) => self.root.insert(point, &self.config, 0)?,
+        }
+
+        self.refresh_aggregates(Some(&location));
+        Ok(())
+    }
+
+    /// Removes the point with exactly the given coordinates.
+    ///
+    /// If there are multiple points with the same coordinates, only one of them is removed.
+    /// Nodes that become sparse are merged back into a single leaf.
+    pub fn remove<U>(&mut self, point: &Point<U>) -> Option<Point<T>> {
+        let removed = self.root.remove(point, &self.config)?;
+        self.refresh_aggregates(Some(&Area::from_bounds(point.x, point.y, point.x, point.y)));
+        Some(removed)
+    }
+
+    /// Replaces the point at coordinates of `old` with the `new` point.
+    ///
+    /// Returns the replaced point, or `None` if there is no point at coordinates of `old`,
+    /// in which case `new` is not inserted. The tree is left unchanged, and an error
+    /// is returned, if `new` is outside of the tree area, or if the tree uses
+    /// [`DuplicatePolicy::Deduplicate`] and another point is already at coordinates of `new`.
+    /// With [`DuplicatePolicy::Aggregate`], `new` is combined into such a point, same as
+    /// with [`QuadTree::insert`].
+    pub fn update<U>(
+        &mut self,
+        old: &Point<U>,
+        new: Point<T>,
+    ) -> Result<Option<Point<T>>, InsertError> {
+        if !self.root.area.is_point_inside(&new) {
+            return Err(InsertError::OutsideArea);
+        }
+        // Point at the same coordinates as `old` is the one that gets replaced.
+        if matches!(self.duplicates, DuplicatePolicy::Deduplicate)
+            && (new.x, new.y) != (old.x, old.y)
+            && self.root.find_mut(&new).is_some()
+        {
+            return Err(InsertError::Duplicate);
+        }
+
+        let Some(removed) = self.remove(old) else {
+            return Ok(None);
+        };
+        self.insert(new)?;
+
+        Ok(Some(removed))
+    }
+
+    /// Merges sparse nodes and releases unused memory.
+    ///
+    /// Removals already merge nodes once they hold at most half of the leaf capacity.
+    /// Compacting merges every subtree that fits into a single leaf,
+    /// which reduces memory usage and traversal depth after heavy removals.
+    pub fn compact(&mut self) {
+        self.root.compact(&self.config);
+        self.refresh_aggregates(None);
     }
 
     /// Queries points inside the given area and removes them.
     ///
     /// Points are removed from the tree and put into `results`.
-    /// The method returns number of points that have been written to results.
-    /// In other words, you are interested in `results[..return_value]`
-    ///
-    /// Warning: If there are more points in the area than length of the results,
-    /// this method will panic.
+    /// If there are more points in the area than length of the results,
+    /// the query stops once results are full and the outcome is marked as truncated.
+    /// Points that didn't fit stay in the tree.
     pub fn query_remove(
         &mut self,
         area: &Area,
         results: &mut [Point<T>],
-    ) -> Result<usize, QueryError> {
+    ) -> Result<QueryOutcome, QueryError> {
         let mut idx = 0;
 
-        self.0.query(
-            area,
-            |points, idx| (points.swap_remove(idx), false),
-            results,
-            &mut idx,
-        )?;
+        let flow = self
+            .root
+            .query_remove(area, results, &mut idx, &self.config)?;
+        self.refresh_aggregates(Some(area));
+
+        Ok(QueryOutcome {
+            written: idx,
+            truncated: flow.is_break(),
+        })
+    }
+
+    /// Calls `f` for every point inside the given area.
+    ///
+    /// Unlike [`QuadTree::query`], this doesn't require a results buffer
+    /// and can't run out of space.
+    pub fn query_with<F>(&self, area: &Area, mut f: F) -> Result<(), QueryError>
+    where
+        F: FnMut(&Point<T>),
+    {
+        if !self.root.area.intersects(area) {
+            return Err(QueryError::OutsideArea);
+        }
+
+        let _ = self.root.visit(area, &mut |p| {
+            f(p);
+            ControlFlow::Continue(())
+        });
+        Ok(())
+    }
 
-        Ok(idx)
+    /// Returns the number of points inside the given area.
+    ///
+    /// Points are only counted, not copied. Areas outside of the tree contain no points.
+    pub fn count(&self, area: &Area) -> usize {
+        let mut count = 0;
+        let _ = self.root.visit(area, &mut |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+
+        count
+    }
+
+    /// Returns whether there are no points inside the given area.
+    ///
+    /// Traversal stops at the first point found.
+    pub fn is_empty_in(&self, area: &Area) -> bool {
+        self.root
+            .visit(area, &mut |_| ControlFlow::Break(()))
+            .is_continue()
     }
 
     /// Finds the point nearest to the given point.
     ///
-    /// Point by which you query, has to be in the area of the tree.
+    /// Point by which you query can be outside of the area of the tree,
+    /// for example a GPS fix just outside the edge of the data.
     /// If the tree is empty, None is returned.
-    pub fn nearest<'a, U>(&'a self, point: &Point<U>) -> Result<Option<&'a Point<T>>, QueryError> {
-        self.0
-            .nearest(point)
-            .map(|opt_point| opt_point.map(|(_, p)| p))
+    pub fn nearest<'a, U>(&'a self, point: &Point<U>) -> Option<&'a Point<T>> {
+        let mut best = None;
+        self.root.nearest(point, f64::INFINITY, &mut best);
+
+        best.map(|(_, p)| p)
+    }
+
+    /// Finds the nearest point, which is at most `max_distance` away.
+    ///
+    /// Returns None if there is no such point. Unlike [`QuadTree::nearest`],
+    /// the point can be outside of the tree area. Only the nodes within
+    /// the distance are visited, which makes the search cheap for sparse data.
+    pub fn nearest_within<'a, U>(
+        &'a self,
+        point: &Point<U>,
+        max_distance: f64,
+    ) -> Option<&'a Point<T>> {
+        let mut best = None;
+        self.root
+            .nearest(point, max_distance * max_distance, &mut best);
+
+        best.map(|(_, p)| p)
+    }
+
+    /// Finds `k` points nearest to the given point.
+    ///
+    /// Found points are written into `results`, ordered from the nearest to the farthest.
+    /// Previous content of `results` is cleared. If the tree contains less than `k` points,
+    /// all of them are returned. Point by which you query can be outside of the area of the tree.
+    pub fn nearest_k<'a, U>(&'a self, point: &Point<U>, k: usize, results: &mut Vec<&'a Point<T>>) {
+        results.clear();
+        if k == 0 {
+            return;
+        }
+
+        // Best-first search. Nodes are ordered by the distance to their area, which
+        // is a lower bound for the distance of any point inside of them. When a point
+        // is popped from the heap, no other unvisited point can be closer to the query.
+        let mut heap = BinaryHeap::new();
+        heap.push(Candidate {
+            distance: 0.0,
+            item: CandidateItem::Node(&self.root),
+        });
+
+        while let Some(Candidate { item, .. }) = heap.pop() {
+            match item {
+                CandidateItem::Point(p) => {
+                    results.push(p);
+                    if results.len() == k {
+                        break;
+                    }
+                }
+                CandidateItem::Node(node) => match &node.inner {
+                    NodeInner::Leaf { points } => {
+                        heap.extend(points.iter().map(|p| Candidate {
+                            distance: p.distance_sq(point),
+                            item: CandidateItem::Point(p),
+                        }));
+                    }
+                    NodeInner::Intermediate { nw, ne, sw, se } => {
+                        heap.extend([nw, ne, sw, se].into_iter().map(|child| Candidate {
+                            distance: child.area.distance_sq(point),
+                            item: CandidateItem::Node(child),
+                        }));
+                    }
+                },
+            }
+        }
     }
 }
 
@@ -108,26 +480,255 @@ impl<T: Clone> QuadTree<T> {
     /// Queries points inside the given area.
     ///
     /// Points are cloned from the tree and put into `results`.
-    /// The method returns number of points that have been written to results.
-    /// In other words, you are interested in `results[..return_value]`
+    /// If there are more points in the area than length of the results,
+    /// the query stops once results are full and the outcome is marked as truncated.
+    pub fn query(&self, area: &Area, results: &mut [Point<T>]) -> Result<QueryOutcome, QueryError> {
+        if !self.root.area.intersects(area) {
+            return Err(QueryError::OutsideArea);
+        }
+
+        let mut idx = 0;
+        let flow = self.root.visit(area, &mut |p| {
+            if idx == results.len() {
+                return ControlFlow::Break(());
+            }
+
+            results[idx] = p.clone();
+            idx += 1;
+            ControlFlow::Continue(())
+        });
+
+        Ok(QueryOutcome {
+            written: idx,
+            truncated: flow.is_break(),
+        })
+    }
+
+    /// Queries points inside the given area and returns them in a new vector.
+    ///
+    /// This is an allocating alternative to [`QuadTree::query`], which is
+    /// useful when the number of points in the area is not known in advance.
+    pub fn query_vec(&self, area: &Area) -> Result<Vec<Point<T>>, QueryError> {
+        let mut results = vec![];
+        self.query_with(area, |p| results.push(p.clone()))?;
+
+        Ok(results)
+    }
+
+    /// Returns at most `max_points` points, spread roughly uniformly over the area.
+    ///
+    /// Tree is descended level by level, for as long as the nodes intersecting
+    /// the area fit into `max_points`, and each of them contributes an equal share
+    /// of the points. If all the points in the area fit, they are all returned.
+    /// This is meant for rendering overviews, where only a few points per pixel matter.
+    pub fn sample(&self, area: &Area, max_points: usize) -> Vec<Point<T>> {
+        if max_points == 0 || !self.root.area.intersects(area) {
+            return vec![];
+        }
+
+        let mut level = vec![&self.root];
+        loop {
+            let mut next = Vec::with_capacity(level.len() * 4);
+            let mut expanded = false;
+            for node in &level {
+                match &node.inner {
+                    NodeInner::Intermediate { nw, ne, sw, se } => {
+                        expanded = true;
+                        next.extend(
+                            [nw, ne, sw, se]
+                                .into_iter()
+                                .map(|child| &**child)
+                                .filter(|child| child.area.intersects(area)),
+                        );
+                    }
+                    NodeInner::Leaf { .. } => next.push(*node),
+                }
+            }
+            if next.len() > max_points {
+                break;
+            }
+            level = next;
+
+            if !expanded {
+                let mut points = vec![];
+                let flow = self.root.visit(area, &mut |p| {
+                    if points.len() == max_points {
+                        return ControlFlow::Break(());
+                    }
+                    points.push(p.clone());
+                    ControlFlow::Continue(())
+                });
+                if flow.is_continue() {
+                    return points;
+                }
+                break;
+            }
+        }
+
+        // Leaves are small enough to take evenly strided points from them,
+        // intermediate nodes contribute only their first point inside the area.
+        let quota = max_points / level.len();
+        let mut results = Vec::with_capacity(max_points);
+        for node in level {
+            match &node.inner {
+                NodeInner::Leaf { points } => {
+                    let inside: Vec<_> =
+                        points.iter().filter(|p| area.is_point_inside(p)).collect();
+                    let step = inside.len().div_ceil(quota).max(1);
+                    results.extend(inside.into_iter().step_by(step).cloned());
+                }
+                NodeInner::Intermediate { .. } => {
+                    let _ = node.visit(area, &mut |p| {
+                        results.push(p.clone());
+                        ControlFlow::Break(())
+                    });
+                }
+            }
+        }
+        results
+    }
+
+    /// Queries points within `radius` meters of the `center`.
+    ///
+    /// Points are cloned from the tree and put into `results`.
+    /// If there are more points in the area than length of the results,
+    /// the query stops once results are full and the outcome is marked as truncated.
+    pub fn query_radius<U>(
+        &self,
+        center: &Point<U>,
+        radius: f64,
+        results: &mut [Point<T>],
+    ) -> Result<QueryOutcome, QueryError> {
+        let circle = Circle {
+            center: Point {
+                x: center.x,
+                y: center.y,
+                data: (),
+            },
+            radius,
+        };
+        self.query_circle(&circle, results)
+    }
+
+    /// Queries points inside the circle.
     ///
-    /// Warning: If there are more points in the area than length of the results,
-    /// this method will panic.
-    pub fn query(&mut self, area: &Area, results: &mut [Point<T>]) -> Result<usize, QueryError> {
+    /// Only nodes intersecting the circle are visited.
+    /// Points are cloned from the tree and put into `results`.
+    /// If there are more points in the circle than length of the results,
+    /// the query stops once results are full and the outcome is marked as truncated.
+    pub fn query_circle(
+        &self,
+        circle: &Circle,
+        results: &mut [Point<T>],
+    ) -> Result<QueryOutcome, QueryError> {
+        if !circle.intersects_area(&self.root.area) {
+            return Err(QueryError::OutsideArea);
+        }
+
         let mut idx = 0;
-        self.0.query(
-            area,
-            |points, idx| (points[idx].clone(), true),
+        let flow = self.root.visit(circle, &mut |p| {
+            if idx == results.len() {
+                return ControlFlow::Break(());
+            }
+
+            results[idx] = p.clone();
+            idx += 1;
+            ControlFlow::Continue(())
+        });
+
+        Ok(QueryOutcome {
+            written: idx,
+            truncated: flow.is_break(),
+        })
+    }
+
+    /// Queries points inside the polygon.
+    ///
+    /// Polygon is given by its vertices and is implicitly closed.
+    /// Polygons with less than three vertices contain no points. Only nodes
+    /// intersecting the bounding box of the polygon are visited.
+    /// Points are cloned from the tree and put into `results`.
+    /// If there are more points in the polygon than length of the results,
+    /// the query stops once results are full and the outcome is marked as truncated.
+    pub fn query_polygon<U>(
+        &self,
+        polygon: &[Point<U>],
+        results: &mut [Point<T>],
+    ) -> Result<QueryOutcome, QueryError> {
+        if polygon.len() < 3 {
+            return Ok(QueryOutcome {
+                written: 0,
+                truncated: false,
+            });
+        }
+
+        let (min_x, min_y, max_x, max_y) = polygon.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), p| {
+                (
+                    min_x.min(p.x),
+                    min_y.min(p.y),
+                    max_x.max(p.x),
+                    max_y.max(p.y),
+                )
+            },
+        );
+        let area = Area::from_bounds(min_x, min_y, max_x, max_y);
+        self.query_filtered(
+            &area,
+            |p| is_inside_ring(p, polygon.iter().map(|v| (v.x, v.y))),
             results,
-            &mut idx,
-        )?;
+        )
+    }
 
-        Ok(idx)
+    /// Queries points inside the area, for which `filter` returns true.
+    pub(crate) fn query_filtered<F>(
+        &self,
+        area: &Area,
+        filter: F,
+        results: &mut [Point<T>],
+    ) -> Result<QueryOutcome, QueryError>
+    where
+        F: Fn(&Point<T>) -> bool,
+    {
+        if !self.root.area.intersects(area) {
+            return Err(QueryError::OutsideArea);
+        }
+
+        let mut idx = 0;
+        let flow = self.root.visit(area, &mut |p| {
+            if !filter(p) {
+                return ControlFlow::Continue(());
+            }
+            if idx == results.len() {
+                return ControlFlow::Break(());
+            }
+
+            results[idx] = p.clone();
+            idx += 1;
+            ControlFlow::Continue(())
+        });
+
+        Ok(QueryOutcome {
+            written: idx,
+            truncated: flow.is_break(),
+        })
+    }
+}
+
+impl<T> FromIterator<Point<T>> for QuadTree<T> {
+    fn from_iter<I: IntoIterator<Item = Point<T>>>(iter: I) -> Self {
+        Self::from_points(iter.into_iter().collect())
     }
 }
 
 impl<T> Node<T> {
-    fn insert(&mut self, point: Point<T>) -> Result<(), InsertError> {
+    fn insert(
+        &mut self,
+        point: Point<T>,
+        config: &QuadTreeConfig,
+        depth: usize,
+    ) -> Result<(), InsertError> {
         if !self.area.is_point_inside(&point) {
             return Err(InsertError::OutsideArea);
         }
@@ -135,23 +736,21 @@ impl<T> Node<T> {
         match &mut self.inner {
             NodeInner::Intermediate { nw, ne, sw, se } => {
                 if nw.area.is_point_inside(&point) {
-                    nw.insert(point)
+                    nw.insert(point, config, depth + 1)
                 } else if ne.area.is_point_inside(&point) {
-                    ne.insert(point)
+                    ne.insert(point, config, depth + 1)
                 } else if sw.area.is_point_inside(&point) {
-                    sw.insert(point)
-                } else if se.area.is_point_inside(&point) {
-                    se.insert(point)
+                    sw.insert(point, config, depth + 1)
                 } else {
-                    unreachable!(
-                        "Invalid tree! Point is in tree area, but in any of the subdivisions."
-                    );
+                    // Subdivisions cover the whole area, so this fails only
+                    // if the point is outside of the tree area.
+                    se.insert(point, config, depth + 1)
                 }
             }
             NodeInner::Leaf { points } => {
                 points.push(point);
-                if points.len() > MAX_POINTS {
-                    self.subdivide();
+                if points.len() > config.leaf_capacity && depth < config.max_depth {
+                    self.subdivide(config, depth)?;
                 }
 
                 Ok(())
@@ -159,34 +758,35 @@ impl<T> Node<T> {
         }
     }
 
-    fn query<F>(
+    /// Moves points inside the `area` into `results`.
+    ///
+    /// Returns [`ControlFlow::Break`] if results are full and there are
+    /// more points left in the area.
+    fn query_remove(
         &mut self,
         area: &Area,
-        get_point: F,
         results: &mut [Point<T>],
         idx: &mut usize,
-    ) -> Result<(), QueryError>
-    where
-        F: Fn(&mut Vec<Point<T>>, usize) -> (Point<T>, bool) + Copy,
-    {
+        config: &QuadTreeConfig,
+    ) -> Result<ControlFlow<()>, QueryError> {
         if !self.area.intersects(area) {
             return Err(QueryError::OutsideArea);
         }
 
         match &mut self.inner {
             NodeInner::Intermediate { nw, ne, sw, se } => {
-                if nw.area.intersects(area) {
-                    nw.query(area, get_point, results, idx)?;
-                }
-                if ne.area.intersects(area) {
-                    ne.query(area, get_point, results, idx)?;
-                }
-                if sw.area.intersects(area) {
-                    sw.query(area, get_point, results, idx)?;
-                }
-                if se.area.intersects(area) {
-                    se.query(area, get_point, results, idx)?;
+                let mut flow = ControlFlow::Continue(());
+                for child in [nw, ne, sw, se] {
+                    if child.area.intersects(area) {
+                        flow = child.query_remove(area, results, idx, config)?;
+                        if flow.is_break() {
+                            break;
+                        }
+                    }
                 }
+
+                self.merge(config.merge_points());
+                return Ok(flow);
             }
             NodeInner::Leaf { points } => {
                 let mut i = 0;
@@ -195,115 +795,268 @@ impl<T> Node<T> {
                         i += 1;
                         continue;
                     }
+                    if *idx == results.len() {
+                        return Ok(ControlFlow::Break(()));
+                    }
 
-                    let (point, increase_i) = get_point(points, i);
-                    results[*idx] = point;
+                    results[*idx] = points.swap_remove(i);
                     *idx += 1;
-
-                    if increase_i {
-                        i += 1
-                    }
                 }
             }
         }
 
-        Ok(())
+        Ok(ControlFlow::Continue(()))
     }
 
-    fn nearest<U>(&self, point: &Point<U>) -> Result<Option<(f32, &Point<T>)>, QueryError> {
+    /// Removes a single point with exactly the given coordinates.
+    fn remove<U>(&mut self, point: &Point<U>, config: &QuadTreeConfig) -> Option<Point<T>> {
         if !self.area.is_point_inside(point) {
-            return Err(QueryError::OutsideArea);
+            return None;
+        }
+
+        let removed = match &mut self.inner {
+            // Points on the border of children could be in any of them.
+            NodeInner::Intermediate { nw, ne, sw, se } => [nw, ne, sw, se]
+                .into_iter()
+                .find_map(|c| c.remove(point, config)),
+            NodeInner::Leaf { points } => {
+                let idx = points
+                    .iter()
+                    .position(|p| p.x == point.x && p.y == point.y)?;
+                Some(points.swap_remove(idx))
+            }
+        };
+
+        if removed.is_some() {
+            self.merge(config.merge_points());
+        }
+        removed
+    }
+
+    /// Finds a point with exactly the given coordinates.
+    fn find_mut<U>(&mut self, point: &Point<U>) -> Option<&mut Point<T>> {
+        if !self.area.is_point_inside(point) {
+            return None;
+        }
+
+        match &mut self.inner {
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                [nw, ne, sw, se].into_iter().find_map(|c| c.find_mut(point))
+            }
+            NodeInner::Leaf { points } => {
+                points.iter_mut().find(|p| p.x == point.x && p.y == point.y)
+            }
+        }
+    }
+
+    /// Resolves duplicates in every leaf according to the policy.
+    ///
+    /// Points with the same coordinates always end up in the same leaf,
+    /// so leaves can be resolved independently.
+    fn resolve_duplicates(&mut self, policy: DuplicatePolicy<T>) {
+        match &mut self.inner {
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                for child in [nw, ne, sw, se] {
+                    child.resolve_duplicates(policy);
+                }
+            }
+            NodeInner::Leaf { points } => {
+                let mut resolved: Vec<Point<T>> = Vec::with_capacity(points.len());
+                for p in points.drain(..) {
+                    let existing = resolved.iter_mut().find(|r| r.x == p.x && r.y == p.y);
+                    match (existing, policy) {
+                        (Some(existing), DuplicatePolicy::Aggregate(aggregate)) => {
+                            aggregate(&mut existing.data, p.data)
+                        }
+                        (Some(_), _) => (),
+                        (None, _) => resolved.push(p),
+                    }
+                }
+                *points = resolved;
+            }
+        }
+    }
+
+    /// Merges children into a single leaf if they are all leaves
+    /// and hold at most `limit` points.
+    fn merge(&mut self, limit: usize) {
+        let NodeInner::Intermediate { nw, ne, sw, se } = &mut self.inner else {
+            return;
+        };
+
+        let mut size = 0;
+        for child in [&**nw, &**ne, &**sw, &**se] {
+            match &child.inner {
+                NodeInner::Leaf { points } => size += points.len(),
+                NodeInner::Intermediate { .. } => return,
+            }
+        }
+        if size > limit {
+            return;
+        }
+
+        let mut merged = Vec::with_capacity(size);
+        for child in [nw, ne, sw, se] {
+            if let NodeInner::Leaf { points } = &mut child.inner {
+                merged.append(points);
+            }
         }
+        self.inner = NodeInner::Leaf { points: merged };
+    }
 
-        let res = match &self.inner {
+    /// Merges subtrees bottom up and releases unused leaf capacity.
+    fn compact(&mut self, config: &QuadTreeConfig) {
+        match &mut self.inner {
+            NodeInner::Leaf { points } => points.shrink_to_fit(),
             NodeInner::Intermediate { nw, ne, sw, se } => {
-                let mut res = None;
+                for child in [nw, ne, sw, se] {
+                    child.compact(config);
+                }
+                self.merge(config.leaf_capacity);
+            }
+        }
+    }
 
-                if nw.area.is_point_inside(point) {
-                    res = min_point(res, nw.nearest(point)?);
+    /// Calls `f` for every point inside the `area`, until `f` breaks.
+    fn visit<S, F>(&self, area: &S, f: &mut F) -> ControlFlow<()>
+    where
+        S: Shape,
+        F: FnMut(&Point<T>) -> ControlFlow<()>,
+    {
+        match &self.inner {
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                for child in [nw, ne, sw, se] {
+                    if area.intersects_area(&child.area) {
+                        child.visit(area, f)?;
+                    }
                 }
-                if ne.area.is_point_inside(point) {
-                    res = min_point(res, nw.nearest(point)?);
+            }
+            NodeInner::Leaf { points } => {
+                for p in points.iter().filter(|p| area.contains(p)) {
+                    f(p)?;
                 }
-                if sw.area.is_point_inside(point) {
-                    res = min_point(res, nw.nearest(point)?);
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Branch and bound search for the nearest point, which is at most
+    /// `sqrt(max_distance_sq)` away.
+    ///
+    /// `best` holds the best candidate found so far together with its squared distance.
+    /// Children are visited closest first and skipped if their area is farther
+    /// away than the current best candidate.
+    fn nearest<'a, U>(
+        &'a self,
+        point: &Point<U>,
+        max_distance_sq: f64,
+        best: &mut Option<(f64, &'a Point<T>)>,
+    ) {
+        match &self.inner {
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                let mut children =
+                    [nw, ne, sw, se].map(|child| (child.area.distance_sq(point), child));
+                children.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+                for (distance, child) in children {
+                    let too_far = match best {
+                        Some((best_distance, _)) => distance >= *best_distance,
+                        None => distance > max_distance_sq,
+                    };
+                    if too_far {
+                        // Children are sorted, so all remaining are too far away.
+                        break;
+                    }
+
+                    child.nearest(point, max_distance_sq, best);
                 }
-                if se.area.is_point_inside(point) {
-                    res = min_point(res, nw.nearest(point)?);
+            }
+            NodeInner::Leaf { points } => {
+                for p in points {
+                    let distance = p.distance_sq(point);
+                    if distance <= max_distance_sq {
+                        *best = min_point(best.take(), Some((distance, p)));
+                    }
                 }
+            }
+        }
+    }
+
+    /// Builds the subtree from points, which all have to be inside the area.
+    ///
+    /// Points are partitioned into quadrants in the same way as [`Node::insert`]
+    /// would place them, but without growing and splitting leaves one by one.
+    fn build(area: Area, mut points: Vec<Point<T>>, config: &QuadTreeConfig, depth: usize) -> Self {
+        if points.len() <= config.leaf_capacity || depth >= config.max_depth {
+            return Self {
+                area,
+                inner: NodeInner::Leaf { points },
+                aggregate: None,
+            };
+        }
 
-                res
+        let areas = subdivide_area(&area);
+        let mut parts: [Vec<Point<T>>; 4] = Default::default();
+        for p in points.drain(..) {
+            if let Some(quadrant) = quadrant_of(&areas, &p) {
+                parts[quadrant].push(p);
             }
-            NodeInner::Leaf { points } => points.iter().fold(None, |acc, p| {
-                let distance = p.distance_sq(point);
-                min_point(acc, Some((distance, p)))
-            }),
-        };
+        }
 
-        Ok(res)
+        let [nw_area, ne_area, sw_area, se_area] = areas;
+        let [nw, ne, sw, se] = parts;
+        Self {
+            area,
+            inner: NodeInner::Intermediate {
+                nw: Box::new(Node::build(nw_area, nw, config, depth + 1)),
+                ne: Box::new(Node::build(ne_area, ne, config, depth + 1)),
+                sw: Box::new(Node::build(sw_area, sw, config, depth + 1)),
+                se: Box::new(Node::build(se_area, se, config, depth + 1)),
+            },
+            aggregate: None,
+        }
     }
 
     fn new_leaf(area: Area) -> Self {
         Self {
             area,
             inner: NodeInner::Leaf { points: vec![] },
+            aggregate: None,
         }
     }
 
-    fn subdivide(&mut self) {
-        let area = &self.area;
-
-        // Radius is created with a small epsilon to handle numerical error.
-        // This means that areas overlap a bit, but that's fine. We are using
-        // if/else for insertion, which means point is inserted only in one subsection.
-        let r = area.radius / 2.0 + 0.01;
-
-        let nw_area = Area {
-            center: Point {
-                x: area.center.x - r,
-                y: area.center.y - r,
-                data: (),
-            },
-            radius: r,
-        };
-        let ne_area = Area {
-            center: Point {
-                x: area.center.x + r,
-                y: area.center.y - r,
-                data: (),
-            },
-            radius: r,
-        };
-        let sw_area = Area {
-            center: Point {
-                x: area.center.x - r,
-                y: area.center.y + r,
-                data: (),
-            },
-            radius: r,
-        };
-        let se_area = Area {
-            center: Point {
-                x: area.center.x + r,
-                y: area.center.y + r,
-                data: (),
-            },
-            radius: r,
+    /// Splits the leaf into four children and moves its points into them.
+    ///
+    /// Intermediate nodes are left unchanged.
+    fn subdivide(&mut self, config: &QuadTreeConfig, depth: usize) -> Result<(), InsertError> {
+        let NodeInner::Leaf { points } = &mut self.inner else {
+            return Ok(());
         };
+        let points = std::mem::take(points);
 
-        let mut curr_leaf = NodeInner::Intermediate {
+        let [nw_area, ne_area, sw_area, se_area] = subdivide_area(&self.area);
+        self.inner = NodeInner::Intermediate {
             nw: Box::new(Node::new_leaf(nw_area)),
             ne: Box::new(Node::new_leaf(ne_area)),
             sw: Box::new(Node::new_leaf(sw_area)),
             se: Box::new(Node::new_leaf(se_area)),
         };
-        std::mem::swap(&mut curr_leaf, &mut self.inner);
 
-        let NodeInner::Leaf { points } = curr_leaf else {
-            panic!("subdivide called on non-leaf node");
-        };
         for p in points {
-            self.insert(p).expect("subdivide became invalid");
+            self.insert(p, config, depth)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the number of nodes and points in the subtree.
+    fn counts(&self) -> (usize, usize) {
+        match &self.inner {
+            NodeInner::Leaf { points } => (1, points.len()),
+            NodeInner::Intermediate { nw, ne, sw, se } => [nw, ne, sw, se]
+                .iter()
+                .map(|child| child.counts())
+                .fold((1, 0), |(n, p), (cn, cp)| (n + cn, p + cp)),
         }
     }
 
@@ -317,10 +1070,111 @@ impl<T> Node<T> {
     }
 }
 
+/// Item of the best-first search in [`QuadTree::nearest_k`].
+enum CandidateItem<'a, T> {
+    Node(&'a Node<T>),
+    Point(&'a Point<T>),
+}
+
+/// Heap entry ordered in reverse by distance, which makes
+/// [`BinaryHeap`] behave as a min-heap.
+struct Candidate<'a, T> {
+    distance: f64,
+    item: CandidateItem<'a, T>,
+}
+
+impl<T> PartialEq for Candidate<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Candidate<'_, T> {}
+
+impl<T> PartialOrd for Candidate<'_, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Candidate<'_, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance)
+    }
+}
+
+/// Shape by which the tree can be traversed.
+trait Shape {
+    fn intersects_area(&self, area: &Area) -> bool;
+    fn contains<T>(&self, point: &Point<T>) -> bool;
+}
+
+impl Shape for Area {
+    fn intersects_area(&self, area: &Area) -> bool {
+        self.intersects(area)
+    }
+
+    fn contains<T>(&self, point: &Point<T>) -> bool {
+        self.is_point_inside(point)
+    }
+}
+
+impl Shape for Circle {
+    fn intersects_area(&self, area: &Area) -> bool {
+        Circle::intersects_area(self, area)
+    }
+
+    fn contains<T>(&self, point: &Point<T>) -> bool {
+        Circle::contains(self, point)
+    }
+}
+
+/// Returns weather the point is inside the ring of `(x, y)` vertices, using the even-odd rule.
+///
+/// Ring is implicitly closed, repeating the first vertex at the end is allowed.
+pub(crate) fn is_inside_ring<T, I>(point: &Point<T>, ring: I) -> bool
+where
+    I: IntoIterator<Item = (f64, f64)>,
+    I::IntoIter: Clone,
+{
+    let ring = ring.into_iter();
+    let Some(mut prev) = ring.clone().last() else {
+        return false;
+    };
+
+    let mut inside = false;
+    for curr in ring {
+        if (curr.1 > point.y) != (prev.1 > point.y) {
+            let x = curr.0 + (point.y - curr.1) * (prev.0 - curr.0) / (prev.1 - curr.1);
+            if point.x < x {
+                inside = !inside;
+            }
+        }
+        prev = curr;
+    }
+
+    inside
+}
+
+/// Returns index of the quadrant, which contains the point.
+///
+/// Only points with non-finite coordinates are in none of the quadrants.
+fn quadrant_of<T>(areas: &[Area; 4], point: &Point<T>) -> Option<usize> {
+    areas.iter().position(|a| a.is_point_inside(point))
+}
+
+/// Splits the area into four overlapping quadrants: nw, ne, sw, se.
+fn subdivide_area(area: &Area) -> [Area; 4] {
+    // Extents are created with a small epsilon to handle numerical error.
+    // This means that areas overlap a bit, but that's fine. We are using
+    // if/else for insertion, which means point is inserted only in one subsection.
+    area.expand(0.02).quadrants()
+}
+
 fn min_point<'a, T>(
-    a: Option<(f32, &'a Point<T>)>,
-    b: Option<(f32, &'a Point<T>)>,
-) -> Option<(f32, &'a Point<T>)> {
+    a: Option<(f64, &'a Point<T>)>,
+    b: Option<(f64, &'a Point<T>)>,
+) -> Option<(f64, &'a Point<T>)> {
     match (a, b) {
         (None, None) => None,
         (None, Some(x)) => Some(x),
@@ -334,3 +1188,647 @@ fn min_point<'a, T>(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point};
+
+    use super::{DuplicatePolicy, InsertError, QuadTree, QuadTreeConfig};
+
+    /// Deterministic pseudo random points, so that tests don't need extra dependencies.
+    fn random_points(n: usize, seed: u64) -> Vec<Point<f32>> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 40) as f64 / (1u64 << 24) as f64
+        };
+
+        (0..n)
+            .map(|i| Point {
+                x: next() * 1000.0,
+                y: next() * 1000.0,
+                data: i as f32,
+            })
+            .collect()
+    }
+
+    fn build_tree(points: &[Point<f32>]) -> QuadTree<f32> {
+        let mut tree = QuadTree::new(Area::square(
+            Point {
+                x: 500.0,
+                y: 500.0,
+                data: (),
+            },
+            500.0,
+        ));
+        for p in points {
+            tree.insert(p.clone()).unwrap();
+        }
+
+        tree
+    }
+
+    fn brute_force_nearest<'a>(points: &'a [Point<f32>], query: &Point<f32>) -> &'a Point<f32> {
+        points
+            .iter()
+            .min_by(|a, b| a.distance_sq(query).total_cmp(&b.distance_sq(query)))
+            .unwrap()
+    }
+
+    #[test]
+    fn nearest_matches_brute_force() {
+        for seed in 0..10 {
+            let points = random_points(3000, seed);
+            let tree = build_tree(&points);
+
+            for query in random_points(100, seed + 100) {
+                let got = tree.nearest(&query).unwrap();
+                let expected = brute_force_nearest(&points, &query);
+                assert_eq!(got.distance_sq(&query), expected.distance_sq(&query));
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_in_sibling_quadrant() {
+        // Enough points in the far corner to force a subdivision.
+        let mut points = random_points(2000, 3)
+            .into_iter()
+            .map(|p| Point {
+                x: p.x / 10.0,
+                y: p.y / 10.0,
+                data: p.data,
+            })
+            .collect::<Vec<_>>();
+        points.push(Point {
+            x: 501.0,
+            y: 400.0,
+            data: -1.0,
+        });
+        let tree = build_tree(&points);
+
+        // Query is in the north west quadrant, but the nearest point is in north east.
+        let query = Point {
+            x: 499.0,
+            y: 400.0,
+            data: (),
+        };
+        assert_eq!(tree.nearest(&query).unwrap().data, -1.0);
+    }
+
+    #[test]
+    fn sample_spreads_points() {
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point {
+                x: (i % 100) as f64,
+                y: (i / 100) as f64,
+                data: i,
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+
+        let area = Area::from_bounds(0.0, 0.0, 99.0, 99.0);
+        let sample = tree.sample(&area, 200);
+        assert!(sample.len() > 50 && sample.len() <= 200, "{}", sample.len());
+        for quadrant in area.quadrants() {
+            assert!(sample.iter().any(|p| quadrant.is_point_inside(p)));
+        }
+
+        // Small areas return all of their points.
+        let small = Area::from_bounds(10.0, 10.0, 12.0, 12.0);
+        assert_eq!(tree.sample(&small, 200).len(), 9);
+        assert!(tree.sample(&small, 0).is_empty());
+    }
+
+    #[test]
+    fn nearest_outside_tree_area() {
+        let points = random_points(2000, 5);
+        let tree = build_tree(&points);
+
+        // GPS fix just outside of the south west corner of the data.
+        let query = Point {
+            x: -3.0,
+            y: -4.0,
+            data: 0.0,
+        };
+        let expected = brute_force_nearest(&points, &query);
+        assert_eq!(tree.nearest(&query), Some(expected));
+
+        let mut results = vec![];
+        tree.nearest_k(&query, 3, &mut results);
+        assert_eq!(results[0], expected);
+    }
+
+    #[test]
+    fn query_vec_matches_query_with() {
+        let points = random_points(5000, 21);
+        let tree = build_tree(&points);
+
+        let area = Area::square(
+            Point {
+                x: 300.0,
+                y: 600.0,
+                data: (),
+            },
+            120.0,
+        );
+        let got = tree.query_vec(&area).unwrap();
+        assert!(got.iter().all(|p| area.is_point_inside(p)));
+        assert_eq!(
+            got.len(),
+            points.iter().filter(|p| area.is_point_inside(p)).count()
+        );
+
+        let mut count = 0;
+        tree.query_with(&area, |_| count += 1).unwrap();
+        assert_eq!(count, got.len());
+    }
+
+    #[test]
+    fn count_matches_brute_force() {
+        let points = random_points(5000, 23);
+        let tree = build_tree(&points);
+
+        let mut area = Area::square(
+            Point {
+                x: 700.0,
+                y: 200.0,
+                data: (),
+            },
+            150.0,
+        );
+        let expected = points.iter().filter(|p| area.is_point_inside(p)).count();
+        assert!(expected > 0);
+        assert_eq!(tree.count(&area), expected);
+        assert!(!tree.is_empty_in(&area));
+
+        area = Area::square(area.center, 0.0001);
+        assert_eq!(tree.count(&area), 0);
+        assert!(tree.is_empty_in(&area));
+    }
+
+    #[test]
+    fn query_truncates_when_results_are_full() {
+        let points = random_points(5000, 31);
+        let mut tree = build_tree(&points);
+        let area = Area::square(
+            Point {
+                x: 500.0,
+                y: 500.0,
+                data: (),
+            },
+            100.0,
+        );
+        let in_area = points.iter().filter(|p| area.is_point_inside(p)).count();
+
+        let mut results = vec![
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: 0.0
+            };
+            in_area
+        ];
+        let outcome = tree.query(&area, &mut results).unwrap();
+        assert_eq!(outcome.written, in_area);
+        assert!(!outcome.truncated);
+
+        let outcome = tree.query_remove(&area, &mut results[..10]).unwrap();
+        assert_eq!(outcome.written, 10);
+        assert!(outcome.truncated);
+        assert_eq!(tree.size(), points.len() - 10);
+    }
+
+    #[test]
+    fn iterators_yield_all_points() {
+        let points = random_points(5000, 43);
+        let tree = build_tree(&points);
+
+        let mut expected: Vec<_> = points.iter().map(|p| p.data).collect();
+        expected.sort_by(f32::total_cmp);
+
+        let mut got: Vec<_> = tree.iter().map(|p| p.data).collect();
+        got.sort_by(f32::total_cmp);
+        assert_eq!(got, expected);
+
+        let area = Area::square(
+            Point {
+                x: 250.0,
+                y: 750.0,
+                data: (),
+            },
+            200.0,
+        );
+        assert_eq!(tree.iter_in(&area).count(), tree.count(&area));
+        assert!(tree.iter_in(&area).all(|p| area.is_point_inside(p)));
+
+        let mut got: Vec<_> = tree.into_iter().map(|p| p.data).collect();
+        got.sort_by(f32::total_cmp);
+        assert_eq!(got, expected);
+    }
+
+    #[test]
+    fn remove_and_update_points() {
+        let points = random_points(5000, 47);
+        let mut tree = build_tree(&points);
+        let (nodes, _) = tree.root.counts();
+
+        let moved = Point {
+            x: 1.0,
+            y: 2.0,
+            data: -1.0,
+        };
+        let old = tree.update(&points[0], moved.clone()).unwrap();
+        assert_eq!(old.as_ref(), Some(&points[0]));
+        assert_eq!(tree.remove(&points[0]), None);
+        assert_eq!(tree.remove(&moved), Some(moved));
+
+        for p in &points[1..4500] {
+            assert_eq!(tree.remove(p).as_ref(), Some(p));
+        }
+        assert_eq!(tree.size(), 500);
+        assert!(tree.root.counts().0 < nodes);
+        assert_eq!(tree.iter().count(), 500);
+
+        // Moving onto another point would drop the moved point.
+        let mut tree =
+            build_tree(&points[..10]).with_duplicate_policy(DuplicatePolicy::Deduplicate);
+        let err = tree.update(&points[0], points[1].clone()).unwrap_err();
+        assert!(matches!(err, InsertError::Duplicate));
+        assert_eq!(tree.size(), 10);
+        let moved = tree.update(&points[0], points[0].clone()).unwrap();
+        assert_eq!(moved.as_ref(), Some(&points[0]));
+        assert_eq!(tree.size(), 10);
+    }
+
+    #[test]
+    fn compact_merges_sparse_nodes() {
+        let points = random_points(5000, 53);
+        let mut tree = build_tree(&points);
+
+        let area = Area::square(
+            Point {
+                x: 500.0,
+                y: 500.0,
+                data: (),
+            },
+            470.0,
+        );
+        let mut results = vec![
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: 0.0
+            };
+            points.len()
+        ];
+        let outcome = tree.query_remove(&area, &mut results).unwrap();
+        let remaining = points.len() - outcome.written;
+        assert!(remaining <= QuadTreeConfig::default().leaf_capacity);
+
+        tree.compact();
+        assert_eq!(tree.root.counts(), (1, remaining));
+        assert_eq!(tree.iter().count(), remaining);
+    }
+
+    #[test]
+    fn config_bounds_subdivision() {
+        let config = QuadTreeConfig::default()
+            .with_leaf_capacity(10)
+            .with_max_depth(4);
+
+        let mut points = random_points(500, 59);
+        // Identical points can never be separated by subdivision.
+        points.extend((0..100).map(|i| Point {
+            x: 123.0,
+            y: 456.0,
+            data: i as f32,
+        }));
+        let tree = QuadTree::from_points_with_config(points.clone(), config);
+        assert_eq!(tree.size(), points.len());
+        assert!(tree.root.counts().0 <= (4usize.pow(5) - 1) / 3);
+
+        let mut inserted = QuadTree::with_config(tree.root.area.clone(), config);
+        for p in points {
+            inserted.insert(p).unwrap();
+        }
+        assert_eq!(inserted.size(), tree.size());
+        assert!(inserted.root.counts().0 <= (4usize.pow(5) - 1) / 3);
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        let duplicates = |n| {
+            (0..n).map(|i| Point {
+                x: 10.0,
+                y: 20.0,
+                data: i as f32,
+            })
+        };
+        let area = Area::square(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            100.0,
+        );
+
+        let mut tree = QuadTree::new(area.clone());
+        duplicates(3000).for_each(|p| tree.insert(p).unwrap());
+        assert_eq!(tree.size(), 3000);
+
+        let tree = tree.with_duplicate_policy(DuplicatePolicy::Deduplicate);
+        assert_eq!(tree.size(), 1);
+        assert_eq!(tree.iter().next().unwrap().data, 0.0);
+
+        let mut tree = QuadTree::new(area).with_duplicate_policy(DuplicatePolicy::Aggregate(
+            |acc: &mut f32, h| *acc = acc.max(h),
+        ));
+        duplicates(3000).for_each(|p| tree.insert(p).unwrap());
+        assert_eq!(tree.size(), 1);
+        assert_eq!(tree.iter().next().unwrap().data, 2999.0);
+    }
+
+    #[test]
+    fn rectangular_tree_and_query() {
+        let points: Vec<_> = random_points(5000, 61)
+            .into_iter()
+            .map(|p| Point { y: p.y / 5.0, ..p })
+            .collect();
+        let mut tree = QuadTree::new(Area::from_bounds(0.0, 0.0, 1000.0, 200.0));
+        for p in &points {
+            tree.insert(p.clone()).unwrap();
+        }
+
+        let area = Area::from_bounds(100.0, 50.0, 700.0, 80.0);
+        let got = tree.query_vec(&area).unwrap();
+        assert_eq!(
+            got.len(),
+            points.iter().filter(|p| area.is_point_inside(p)).count()
+        );
+        assert!(
+            got.iter()
+                .all(|p| p.x >= 100.0 && p.y >= 50.0 && p.y <= 80.0)
+        );
+
+        let mut buf = vec![];
+        tree.write_to(&mut buf).unwrap();
+        let read = QuadTree::read_from(&buf[..]).unwrap();
+        assert_eq!(read.query_vec(&area).unwrap(), got);
+    }
+
+    #[test]
+    fn query_polygon_matches_brute_force() {
+        let points = random_points(5000, 67);
+        let tree = build_tree(&points);
+
+        // Concave "L" shape.
+        let polygon = [
+            (100.0, 100.0),
+            (600.0, 100.0),
+            (600.0, 300.0),
+            (300.0, 300.0),
+            (300.0, 800.0),
+            (100.0, 800.0),
+        ]
+        .map(|(x, y)| Point { x, y, data: () });
+        let expected = points
+            .iter()
+            .filter(|p| {
+                let in_bottom = p.x > 100.0 && p.x < 600.0 && p.y > 100.0 && p.y < 300.0;
+                let in_left = p.x > 100.0 && p.x < 300.0 && p.y > 100.0 && p.y < 800.0;
+                in_bottom || in_left
+            })
+            .count();
+
+        let mut results = vec![
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: 0.0
+            };
+            points.len()
+        ];
+        let outcome = tree.query_polygon(&polygon, &mut results).unwrap();
+        assert!(!outcome.truncated);
+        assert_eq!(outcome.written, expected);
+    }
+
+    #[test]
+    fn tree_is_shared_across_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<QuadTree<f32>>();
+
+        let points = random_points(5000, 41);
+        let tree = std::sync::Arc::new(build_tree(&points));
+
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let tree = std::sync::Arc::clone(&tree);
+                std::thread::spawn(move || {
+                    let area = Area::square(
+                        Point {
+                            x: 200.0 * (i + 1) as f64,
+                            y: 500.0,
+                            data: (),
+                        },
+                        80.0,
+                    );
+                    let mut results = vec![
+                        Point {
+                            x: 0.0,
+                            y: 0.0,
+                            data: 0.0
+                        };
+                        tree.size()
+                    ];
+                    let outcome = tree.query(&area, &mut results).unwrap();
+                    assert!(!outcome.truncated);
+                    assert_eq!(outcome.written, tree.count(&area));
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    #[test]
+    fn query_radius_matches_brute_force() {
+        let points = random_points(5000, 11);
+        let tree = build_tree(&points);
+
+        let mut results = vec![
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: 0.0
+            };
+            points.len()
+        ];
+        for center in random_points(20, 12) {
+            let n = tree
+                .query_radius(&center, 50.0, &mut results)
+                .unwrap()
+                .written;
+
+            let mut got: Vec<_> = results[..n].iter().map(|p| p.data).collect();
+            got.sort_by(f32::total_cmp);
+            let expected: Vec<_> = points
+                .iter()
+                .filter(|p| p.distance(&center) <= 50.0)
+                .map(|p| p.data)
+                .collect();
+            assert_eq!(got, expected);
+        }
+    }
+
+    #[test]
+    fn from_points_matches_insert() {
+        let points = random_points(10_000, 51);
+        let bulk: QuadTree<_> = points.iter().cloned().collect();
+        assert_eq!(bulk.size(), points.len());
+
+        let mut inserted = QuadTree::new(Area::from_points(&points));
+        for p in &points {
+            inserted.insert(p.clone()).unwrap();
+        }
+
+        for query in random_points(50, 52) {
+            let got = bulk.nearest(&query).unwrap();
+            let expected = inserted.nearest(&query).unwrap();
+            assert_eq!(got.distance_sq(&query), expected.distance_sq(&query));
+        }
+    }
+
+    #[test]
+    fn nearest_k_matches_brute_force() {
+        let points = random_points(5000, 42);
+        let tree = build_tree(&points);
+
+        let mut results = vec![];
+        for query in random_points(50, 7) {
+            tree.nearest_k(&query, 10, &mut results);
+
+            let mut expected: Vec<_> = points.iter().map(|p| p.distance_sq(&query)).collect();
+            expected.sort_by(f64::total_cmp);
+
+            let got: Vec<_> = results.iter().map(|p| p.distance_sq(&query)).collect();
+            assert_eq!(got, expected[..10]);
+        }
+    }
+
+    mod properties {
+        use proptest::prelude::*;
+
+        use crate::{Area, Point};
+
+        use super::super::{QuadTree, QuadTreeConfig};
+
+        fn points() -> impl Strategy<Value = Vec<Point<usize>>> {
+            prop::collection::vec((0.0..1000.0, 0.0..1000.0), 1..300).prop_map(|coords| {
+                coords
+                    .into_iter()
+                    .enumerate()
+                    .map(|(data, (x, y))| Point { x, y, data })
+                    .collect()
+            })
+        }
+
+        /// Tree with small leaves, so that it is subdivided even for few points.
+        fn tree(points: &[Point<usize>]) -> QuadTree<usize> {
+            let config = QuadTreeConfig::default().with_leaf_capacity(4);
+            let mut tree =
+                QuadTree::with_config(Area::from_bounds(0.0, 0.0, 1000.0, 1000.0), config);
+            for p in points {
+                tree.insert(p.clone()).unwrap();
+            }
+            tree
+        }
+
+        fn sorted_data<'a>(points: impl IntoIterator<Item = &'a Point<usize>>) -> Vec<usize> {
+            let mut data: Vec<_> = points.into_iter().map(|p| p.data).collect();
+            data.sort();
+            data
+        }
+
+        proptest! {
+            #[test]
+            fn inserted_points_are_retrievable(points in points()) {
+                let tree = tree(&points);
+                prop_assert_eq!(tree.debug_validate(), Ok(()));
+                prop_assert_eq!(tree.size(), points.len());
+                prop_assert_eq!(sorted_data(&tree), sorted_data(&points));
+            }
+
+            #[test]
+            fn query_matches_brute_force(
+                points in points(),
+                (x, y, w, h) in (0.0..1000.0, 0.0..1000.0, 0.0..500.0, 0.0..500.0),
+            ) {
+                let tree = tree(&points);
+                let area = Area::from_bounds(x, y, x + w, y + h);
+                let expected = points.iter().filter(|p| area.is_point_inside(p));
+                prop_assert_eq!(
+                    sorted_data(&tree.query_vec(&area).unwrap()),
+                    sorted_data(expected)
+                );
+            }
+
+            #[test]
+            fn nearest_matches_brute_force(
+                points in points(),
+                (x, y) in (0.0..1000.0, 0.0..1000.0),
+            ) {
+                let tree = tree(&points);
+                let query = Point { x, y, data: () };
+                let expected = points
+                    .iter()
+                    .map(|p| p.distance_sq(&query))
+                    .min_by(f64::total_cmp)
+                    .unwrap();
+                let nearest = tree.nearest(&query).unwrap();
+                prop_assert_eq!(nearest.distance_sq(&query), expected);
+            }
+
+            #[test]
+            fn nearest_within_matches_brute_force(
+                points in points(),
+                (x, y, max_distance) in (-200.0..1200.0, -200.0..1200.0, 0.0..300.0),
+            ) {
+                let tree = tree(&points);
+                let query = Point { x, y, data: () };
+                let expected = points
+                    .iter()
+                    .map(|p| p.distance_sq(&query))
+                    .filter(|d| *d <= max_distance * max_distance)
+                    .min_by(f64::total_cmp);
+                let nearest = tree.nearest_within(&query, max_distance);
+                prop_assert_eq!(nearest.map(|p| p.distance_sq(&query)), expected);
+            }
+
+            #[test]
+            fn size_is_consistent_after_removals(
+                points in points(),
+                removed in prop::collection::vec(any::<prop::sample::Index>(), 0..100),
+            ) {
+                let mut tree = tree(&points);
+                let mut remaining = points.clone();
+                for index in removed {
+                    if remaining.is_empty() {
+                        break;
+                    }
+                    let p = remaining.swap_remove(index.index(remaining.len()));
+                    prop_assert!(tree.remove(&p).is_some());
+                }
+
+                prop_assert_eq!(tree.debug_validate(), Ok(()));
+                prop_assert_eq!(tree.size(), remaining.len());
+                prop_assert_eq!(sorted_data(&tree), sorted_data(&remaining));
+            }
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! Statistics of the point data over an area.
+
+use crate::Area;
+
+use super::{Node, NodeInner, QuadTree};
+
+/// Statistics of the data of points inside an area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Aggregate {
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    /// Number of points.
+    pub count: usize,
+}
+
+impl Aggregate {
+    /// Returns statistics of a single value.
+    pub(crate) fn of(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            mean: value,
+            count: 1,
+        }
+    }
+
+    /// Returns statistics of the values of both aggregates.
+    pub(crate) fn merge(self, other: Self) -> Self {
+        let count = self.count + other.count;
+        let weight = other.count as f64 / count as f64;
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+            // Running mean doesn't overflow, unlike the sum.
+            mean: self.mean + (other.mean - self.mean) * weight,
+            count,
+        }
+    }
+}
+
+impl<T: Copy + Into<f64>> QuadTree<T> {
+    /// Returns minimum, maximum and mean of the data of points inside the area.
+    ///
+    /// Statistics are computed during the traversal, so points are not copied.
+    /// Subtrees fully inside the area use their cached statistics,
+    /// see [`QuadTree::with_cached_aggregates`].
+    /// If there are no points inside the area, None is returned.
+    pub fn aggregate(&self, area: &Area) -> Option<Aggregate> {
+        let mut result = None;
+        self.root
+            .aggregate(area, |data: &T| (*data).into(), &mut result);
+        result
+    }
+
+    /// Caches statistics of the data in every intermediate node.
+    ///
+    /// Cached statistics are kept up to date on every change of the tree,
+    /// which makes [`QuadTree::aggregate`] over large areas take logarithmic time,
+    /// at the cost of slightly slower inserts and removals.
+    pub fn with_cached_aggregates(mut self) -> Self {
+        self.aggregate_value = Some(|data: &T| (*data).into());
+        self.refresh_aggregates(None);
+        self
+    }
+}
+
+impl<T> QuadTree<T> {
+    /// Recomputes cached aggregates of the nodes intersecting the area,
+    /// or of all nodes if the area is not given.
+    pub(super) fn refresh_aggregates(&mut self, area: Option<&Area>) {
+        if let Some(value) = self.aggregate_value {
+            self.root.refresh_aggregates(area, value);
+        }
+    }
+}
+
+impl<T> Node<T> {
+    fn aggregate(&self, area: &Area, value: fn(&T) -> f64, result: &mut Option<Aggregate>) {
+        if let Some(cached) = self.aggregate
+            && area.contains_area(&self.area)
+        {
+            *result = merge(*result, Some(cached));
+            return;
+        }
+
+        match &self.inner {
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                for child in [nw, ne, sw, se] {
+                    if area.intersects(&child.area) {
+                        child.aggregate(area, value, result);
+                    }
+                }
+            }
+            NodeInner::Leaf { points } => {
+                for p in points.iter().filter(|p| area.is_point_inside(p)) {
+                    *result = merge(*result, Some(Aggregate::of(value(&p.data))));
+                }
+            }
+        }
+    }
+
+    fn refresh_aggregates(&mut self, area: Option<&Area>, value: fn(&T) -> f64) {
+        self.aggregate = match &mut self.inner {
+            NodeInner::Leaf { .. } => None,
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                let mut result = None;
+                for child in [nw, ne, sw, se] {
+                    if area.is_none_or(|area| area.intersects(&child.area)) {
+                        child.refresh_aggregates(area, value);
+                    }
+                    result = merge(result, child.summary(value));
+                }
+                result
+            }
+        };
+    }
+
+    /// Returns statistics of the subtree, which are cached for intermediate nodes.
+    fn summary(&self, value: fn(&T) -> f64) -> Option<Aggregate> {
+        match &self.inner {
+            NodeInner::Leaf { points } => points
+                .iter()
+                .map(|p| Aggregate::of(value(&p.data)))
+                .reduce(Aggregate::merge),
+            NodeInner::Intermediate { .. } => self.aggregate,
+        }
+    }
+}
+
+fn merge(a: Option<Aggregate>, b: Option<Aggregate>) -> Option<Aggregate> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.merge(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point, qtree::QuadTree};
+
+    #[test]
+    fn aggregate_in_area() {
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point {
+                x: (i % 100) as f64,
+                y: (i / 100) as f64,
+                data: (i % 100 + i / 100) as f32,
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+
+        let agg = tree
+            .aggregate(&Area::from_bounds(10.0, 20.0, 19.0, 39.0))
+            .unwrap();
+        assert_eq!(agg.count, 200);
+        assert_eq!((agg.min, agg.max), (30.0, 58.0));
+        assert!((agg.mean - 44.0).abs() < 1e-9, "{}", agg.mean);
+
+        let outside = Area::from_bounds(200.0, 200.0, 300.0, 300.0);
+        assert_eq!(tree.aggregate(&outside), None);
+    }
+
+    #[test]
+    fn cached_aggregates_follow_changes() {
+        let points: Vec<_> = (0..20_000)
+            .map(|i| Point {
+                x: ((i * 7919) % 1000) as f64,
+                y: ((i * 104729) % 997) as f64,
+                data: (i % 1000) as f32,
+            })
+            .collect();
+        let mut cached = QuadTree::from_points(points.clone()).with_cached_aggregates();
+        let mut plain = QuadTree::from_points(points);
+
+        let check = |cached: &QuadTree<f32>, plain: &QuadTree<f32>| {
+            for area in [
+                Area::from_bounds(0.0, 0.0, 1000.0, 1000.0),
+                Area::from_bounds(100.0, 200.0, 700.0, 650.0),
+                Area::from_bounds(400.0, 400.0, 410.0, 410.0),
+            ] {
+                let (a, b) = (cached.aggregate(&area), plain.aggregate(&area));
+                let (a, b) = (a.unwrap(), b.unwrap());
+                assert_eq!((a.min, a.max, a.count), (b.min, b.max, b.count));
+                assert!((a.mean - b.mean).abs() < 1e-6);
+            }
+        };
+        check(&cached, &plain);
+
+        for tree in [&mut cached, &mut plain] {
+            for i in 0..500 {
+                let p = Point {
+                    x: i as f64,
+                    y: 500.0,
+                    data: 5000.0,
+                };
+                tree.insert(p).unwrap();
+            }
+            let empty = Point {
+                x: 0.0,
+                y: 0.0,
+                data: 0.0,
+            };
+            let mut removed = vec![empty; 5000];
+            let area = Area::from_bounds(0.0, 0.0, 300.0, 600.0);
+            tree.query_remove(&area, &mut removed).unwrap();
+            tree.compact();
+        }
+        check(&cached, &plain);
+        assert_eq!(
+            cached
+                .aggregate(&Area::from_bounds(0.0, 0.0, 1000.0, 1000.0))
+                .unwrap()
+                .max,
+            5000.0
+        );
+    }
+}
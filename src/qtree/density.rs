@@ -0,0 +1,105 @@
+//! Density rasters of the points in the tree.
+
+use crate::{Area, Point, grid::Raster};
+
+use super::{Node, NodeInner, QuadTree};
+
+/// Counts points of the tree in cells of `resolution` meters, covering the area.
+///
+/// North-west cell starts at the north-west corner of the area, and the raster
+/// has as many cells as needed to cover the whole area. Subtrees that fall
+/// into a single cell are counted without visiting their points, and cached
+/// counts are used if the tree has them, see [`QuadTree::with_cached_aggregates`].
+/// Useful for checking coverage of the imported data, where gaps have zero points.
+///
+/// # Panics
+///
+/// Panics if `resolution` is not positive and finite.
+pub fn density<T>(tree: &QuadTree<T>, area: &Area, resolution: f64) -> Raster<u32> {
+    assert!(
+        resolution > 0.0 && resolution.is_finite(),
+        "resolution must be positive and finite"
+    );
+
+    let ncols = (2.0 * area.half_width / resolution).ceil().max(1.0) as usize;
+    let nrows = (2.0 * area.half_height / resolution).ceil().max(1.0) as usize;
+    let origin = Point {
+        x: area.min_x() + resolution / 2.0,
+        y: area.max_y() - resolution / 2.0,
+        data: (),
+    };
+
+    let mut raster = Raster::new(origin, resolution, ncols, nrows, 0);
+    if tree.root.area.intersects(area) {
+        count(&tree.root, area, &mut raster);
+    }
+    raster
+}
+
+fn count<T>(node: &Node<T>, area: &Area, raster: &mut Raster<u32>) {
+    if area.contains_area(&node.area) {
+        let min = raster.cell_at(node.area.min_x(), node.area.min_y());
+        let max = raster.cell_at(node.area.max_x(), node.area.max_y());
+        if let (Some((row, col)), Some(max)) = (min, max)
+            && (row, col) == max
+        {
+            let size = node.aggregate.map_or_else(|| node.size(), |a| a.count);
+            add(raster, row, col, size);
+            return;
+        }
+    }
+
+    match &node.inner {
+        NodeInner::Intermediate { nw, ne, sw, se } => {
+            for child in [nw, ne, sw, se] {
+                if area.intersects(&child.area) {
+                    count(child, area, raster);
+                }
+            }
+        }
+        NodeInner::Leaf { points } => {
+            for p in points.iter().filter(|p| area.is_point_inside(p)) {
+                if let Some((row, col)) = raster.cell_at(p.x, p.y) {
+                    add(raster, row, col, 1);
+                }
+            }
+        }
+    }
+}
+
+fn add(raster: &mut Raster<u32>, row: usize, col: usize, count: usize) {
+    let current = raster.get(row, col).unwrap_or_default();
+    raster.set(row, col, current.saturating_add(count as u32));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Area, Point,
+        qtree::{QuadTree, QuadTreeConfig},
+    };
+
+    use super::density;
+
+    #[test]
+    fn density_counts_points_per_cell() {
+        // Dense 100 x 100 grid with a hole in the middle.
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point {
+                x: (i % 100) as f64 + 0.5,
+                y: (i / 100) as f64 + 0.5,
+                data: (),
+            })
+            .filter(|p| !(40.0..60.0).contains(&p.x) || !(40.0..60.0).contains(&p.y))
+            .collect();
+        let config = QuadTreeConfig::default().with_leaf_capacity(16);
+        let tree = QuadTree::from_points_with_config(points.clone(), config);
+
+        let area = Area::from_bounds(0.0, 0.0, 100.0, 100.0);
+        let raster = density(&tree, &area, 10.0);
+        assert_eq!((raster.ncols(), raster.nrows()), (10, 10));
+        assert_eq!(raster.data().iter().sum::<u32>(), points.len() as u32);
+        assert_eq!(raster.value_at(5.0, 5.0), Some(100));
+        assert_eq!(raster.value_at(45.0, 55.0), Some(0));
+    }
+}
@@ -0,0 +1,110 @@
+//! Iterators over points of the quad tree.
+//!
+//! Nodes are traversed depth first in order nw, ne, sw, se,
+//! so consecutive points are spatially close to each other.
+
+use std::{slice, vec};
+
+use crate::{Area, Point};
+
+use super::{Node, NodeInner, QuadTree};
+
+/// Iterator over references to points of a [`QuadTree`].
+///
+/// Created by [`QuadTree::iter`] and [`QuadTree::iter_in`].
+pub struct Iter<'a, T> {
+    area: Option<Area>,
+    stack: Vec<&'a Node<T>>,
+    points: slice::Iter<'a, Point<T>>,
+}
+
+impl<'a, T> Iter<'a, T> {
+    pub(super) fn new(root: &'a Node<T>, area: Option<Area>) -> Self {
+        let stack = match &area {
+            Some(area) if !root.area.intersects(area) => vec![],
+            _ => vec![root],
+        };
+
+        Self {
+            area,
+            stack,
+            points: [].iter(),
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a Point<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            for point in self.points.by_ref() {
+                if self.area.as_ref().is_none_or(|a| a.is_point_inside(point)) {
+                    return Some(point);
+                }
+            }
+
+            let node = self.stack.pop()?;
+            match &node.inner {
+                NodeInner::Leaf { points } => self.points = points.iter(),
+                NodeInner::Intermediate { nw, ne, sw, se } => {
+                    // Pushed in reverse, so that nw is visited first.
+                    for child in [se, sw, ne, nw] {
+                        if self.area.as_ref().is_none_or(|a| child.area.intersects(a)) {
+                            self.stack.push(child);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Owning iterator over points of a [`QuadTree`].
+///
+/// Created by [`QuadTree::into_iter`](IntoIterator::into_iter).
+pub struct IntoIter<T> {
+    stack: Vec<Node<T>>,
+    points: vec::IntoIter<Point<T>>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = Point<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(point) = self.points.next() {
+                return Some(point);
+            }
+
+            let node = self.stack.pop()?;
+            match node.inner {
+                NodeInner::Leaf { points } => self.points = points.into_iter(),
+                NodeInner::Intermediate { nw, ne, sw, se } => {
+                    self.stack.extend([*se, *sw, *ne, *nw]);
+                }
+            }
+        }
+    }
+}
+
+impl<T> IntoIterator for QuadTree<T> {
+    type Item = Point<T>;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            stack: vec![self.root],
+            points: vec![].into_iter(),
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a QuadTree<T> {
+    type Item = &'a Point<T>;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
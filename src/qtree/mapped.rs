@@ -0,0 +1,289 @@
+//! Read-only quad tree that is queried directly over the persisted format.
+
+use std::ops::ControlFlow;
+
+use crate::{Area, Point};
+
+use super::{
+    QueryError, ReadError,
+    persist::{HEADER_SIZE, Header, KIND_INTERMEDIATE, KIND_LEAF, Layout, NodeOrder, RawNode},
+};
+
+/// Immutable quad tree backed by bytes in the format written by
+/// [`QuadTree::write_to`](super::QuadTree::write_to).
+///
+/// Nodes and points are decoded on the fly during the queries, which means
+/// that the tree doesn't have to be loaded onto the heap. Combined with a memory
+/// mapped file (see `open`, available with the `mmap` feature), this allows
+/// querying trees that are larger than the available memory.
+pub struct MappedQuadTree<B> {
+    bytes: B,
+    layout: Layout,
+    node_count: u64,
+    point_count: u64,
+}
+
+impl<B: AsRef<[u8]>> MappedQuadTree<B> {
+    /// Constructs the tree over the given bytes.
+    ///
+    /// The whole node table is validated, so that the queries can't
+    /// access data out of bounds. Points are not validated.
+    pub fn from_bytes(bytes: B) -> Result<Self, ReadError> {
+        let buf = bytes.as_ref();
+        let header: &[u8; HEADER_SIZE] = buf
+            .get(..HEADER_SIZE)
+            .and_then(|h| h.try_into().ok())
+            .ok_or(ReadError::Io(std::io::ErrorKind::UnexpectedEof.into()))?;
+        let header = Header::from_bytes(header)?;
+
+        let layout = header.layout;
+        let expected_len = (header.node_count as u128) * layout.node_size() as u128
+            + (header.point_count as u128) * layout.point_size() as u128
+            + layout.header_size() as u128;
+        if (buf.len() as u128) < expected_len {
+            return Err(ReadError::Io(std::io::ErrorKind::UnexpectedEof.into()));
+        }
+        if header.node_count == 0 {
+            return Err(ReadError::InvalidNode(0));
+        }
+
+        let tree = Self {
+            bytes,
+            layout,
+            node_count: header.node_count,
+            point_count: header.point_count,
+        };
+        let mut order = NodeOrder::new(tree.node_count);
+        for idx in 0..tree.node_count {
+            tree.validate_node(idx, &mut order)?;
+        }
+
+        Ok(tree)
+    }
+
+    /// Returns the number of points in the tree.
+    pub fn size(&self) -> usize {
+        self.point_count as usize
+    }
+
+    /// Calls `f` for every point inside the given area.
+    pub fn query_with<F>(&self, area: &Area, mut f: F) -> Result<(), QueryError>
+    where
+        F: FnMut(Point<f32>),
+    {
+        if !self.node(0).area.intersects(area) {
+            return Err(QueryError::OutsideArea);
+        }
+
+        let _ = self.visit(0, area, &mut |p| {
+            f(p);
+            ControlFlow::Continue(())
+        });
+        Ok(())
+    }
+
+    /// Queries points inside the given area and returns them in a new vector.
+    pub fn query_vec(&self, area: &Area) -> Result<Vec<Point<f32>>, QueryError> {
+        let mut results = vec![];
+        self.query_with(area, |p| results.push(p))?;
+
+        Ok(results)
+    }
+
+    /// Finds the point nearest to the given point.
+    ///
+    /// Point by which you query can be outside of the area of the tree.
+    /// If the tree is empty, None is returned.
+    pub fn nearest<U>(&self, point: &Point<U>) -> Option<Point<f32>> {
+        let mut best = None;
+        self.nearest_in(0, point, &mut best);
+
+        best.map(|(_, p)| p)
+    }
+
+    fn validate_node(&self, idx: u64, order: &mut NodeOrder) -> Result<(), ReadError> {
+        let node = self.node(idx);
+        let valid = order.check(idx, &node)
+            && match node.kind {
+                KIND_LEAF => node
+                    .first
+                    .checked_add(node.count)
+                    .is_some_and(|end| end <= self.point_count),
+                // Children are checked by the order.
+                KIND_INTERMEDIATE => true,
+                _ => false,
+            };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(ReadError::InvalidNode(idx))
+        }
+    }
+
+    fn node(&self, idx: u64) -> RawNode {
+        let size = self.layout.node_size();
+        let offset = self.layout.header_size() + idx as usize * size;
+        self.layout
+            .node(&self.bytes.as_ref()[offset..offset + size])
+    }
+
+    fn point(&self, idx: u64) -> Point<f32> {
+        let size = self.layout.point_size();
+        let offset = self.layout.header_size()
+            + self.node_count as usize * self.layout.node_size()
+            + idx as usize * size;
+        self.layout
+            .point(&self.bytes.as_ref()[offset..offset + size])
+    }
+
+    fn visit<F>(&self, idx: u64, area: &Area, f: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(Point<f32>) -> ControlFlow<()>,
+    {
+        let node = self.node(idx);
+        match node.kind {
+            KIND_LEAF => {
+                for i in node.first..node.first + node.count {
+                    let p = self.point(i);
+                    if area.is_point_inside(&p) {
+                        f(p)?;
+                    }
+                }
+            }
+            _ => {
+                for child in node.first..node.first + 4 {
+                    if self.node(child).area.intersects(area) {
+                        self.visit(child, area, f)?;
+                    }
+                }
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn nearest_in<U>(&self, idx: u64, point: &Point<U>, best: &mut Option<(f64, Point<f32>)>) {
+        let node = self.node(idx);
+        match node.kind {
+            KIND_LEAF => {
+                for i in node.first..node.first + node.count {
+                    let p = self.point(i);
+                    let distance = p.distance_sq(point);
+                    if best.as_ref().is_none_or(|(d, _)| distance < *d) {
+                        *best = Some((distance, p));
+                    }
+                }
+            }
+            _ => {
+                let mut children = [0, 1, 2, 3].map(|i| {
+                    let child = node.first + i;
+                    (self.node(child).area.distance_sq(point), child)
+                });
+                children.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+                for (distance, child) in children {
+                    if matches!(best, Some((best_distance, _)) if distance >= *best_distance) {
+                        break;
+                    }
+
+                    self.nearest_in(child, point, best);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl MappedQuadTree<memmap2::Mmap> {
+    /// Memory maps the file written by [`QuadTree::write_to`](super::QuadTree::write_to).
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the tree is in use.
+    /// See [`memmap2::Mmap::map`] for details.
+    pub unsafe fn open(path: impl AsRef<std::path::Path>) -> Result<Self, ReadError> {
+        let file = std::fs::File::open(path)?;
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        Self::from_bytes(mmap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Area, Point,
+        qtree::{QuadTree, ReadError},
+    };
+
+    use super::MappedQuadTree;
+
+    #[test]
+    fn mapped_matches_heap_tree() {
+        let points: Vec<_> = (0..20_000)
+            .map(|i| Point {
+                x: (i % 200) as f64 * 1.5,
+                y: (i / 200) as f64 * 2.5,
+                data: i as f32,
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+
+        let mut buf = vec![];
+        tree.write_to(&mut buf).unwrap();
+        let mapped = MappedQuadTree::from_bytes(buf).unwrap();
+        assert_eq!(mapped.size(), tree.size());
+
+        let area = Area::square(
+            Point {
+                x: 120.0,
+                y: 100.0,
+                data: (),
+            },
+            20.0,
+        );
+        assert_eq!(
+            mapped.query_vec(&area).unwrap(),
+            tree.query_vec(&area).unwrap()
+        );
+
+        let query = Point {
+            x: 77.7,
+            y: 33.3,
+            data: (),
+        };
+        assert_eq!(mapped.nearest(&query).as_ref(), tree.nearest(&query));
+    }
+
+    #[test]
+    fn invalid_children() {
+        let points: Vec<_> = (0..2000)
+            .map(|i| Point {
+                x: (i % 50) as f64,
+                y: (i / 50) as f64,
+                data: i as f32,
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+        let mut buf = vec![];
+        tree.write_to(&mut buf).unwrap();
+
+        assert_eq!(buf[72], 1, "root is an intermediate node");
+
+        // Children of the root overflow the node index.
+        let mut overflow = buf.clone();
+        overflow[80..88].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(matches!(
+            MappedQuadTree::from_bytes(overflow),
+            Err(ReadError::InvalidNode(0))
+        ));
+
+        // Root points at itself.
+        buf[80..88].copy_from_slice(&0u64.to_le_bytes());
+        assert!(matches!(
+            MappedQuadTree::from_bytes(buf),
+            Err(ReadError::InvalidNode(0))
+        ));
+    }
+}
@@ -0,0 +1,121 @@
+//! Bulk loading of the quad tree on multiple threads.
+//!
+//! Points are partitioned into quadrants in parallel and the subtrees
+//! of the quadrants are built as separate rayon tasks.
+
+use rayon::prelude::*;
+
+use crate::{Area, Point};
+
+use super::{
+    DuplicatePolicy, Node, NodeInner, QuadTree, QuadTreeConfig, quadrant_of, subdivide_area,
+};
+
+/// Subtrees with fewer points are built on a single thread,
+/// since splitting them into tasks costs more than it saves.
+const SEQUENTIAL_THRESHOLD: usize = 1 << 16;
+
+impl<T: Send> QuadTree<T> {
+    /// Same as [`QuadTree::from_points`], but the tree is built on multiple threads.
+    ///
+    /// The resulting tree is the same as the one built by [`QuadTree::from_points`].
+    pub fn from_points_parallel(points: Vec<Point<T>>) -> Self {
+        Self::from_points_parallel_with_config(points, QuadTreeConfig::default())
+    }
+
+    /// Same as [`QuadTree::from_points_parallel`], but with custom tuning parameters.
+    pub fn from_points_parallel_with_config(
+        mut points: Vec<Point<T>>,
+        config: QuadTreeConfig,
+    ) -> Self {
+        points.retain(|p| p.x.is_finite() && p.y.is_finite());
+        let area = Area::from_points(&points);
+        Self {
+            root: Node::build_parallel(area, points, &config, 0),
+            config,
+            duplicates: DuplicatePolicy::Allow,
+            aggregate_value: None,
+        }
+    }
+}
+
+impl<T: Send> Node<T> {
+    fn build_parallel(
+        area: Area,
+        points: Vec<Point<T>>,
+        config: &QuadTreeConfig,
+        depth: usize,
+    ) -> Self {
+        if points.len() <= SEQUENTIAL_THRESHOLD
+            || points.len() <= config.leaf_capacity
+            || depth >= config.max_depth
+        {
+            return Node::build(area, points, config, depth);
+        }
+
+        // Fold and reduce keep the order of the points, same as the sequential build.
+        let areas = subdivide_area(&area);
+        let [nw, ne, sw, se] = points
+            .into_par_iter()
+            .fold(<[Vec<Point<T>>; 4]>::default, |mut parts, p| {
+                if let Some(quadrant) = quadrant_of(&areas, &p) {
+                    parts[quadrant].push(p);
+                }
+                parts
+            })
+            .reduce(<[Vec<Point<T>>; 4]>::default, |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    a.extend(b);
+                }
+                a
+            });
+
+        let [nw_area, ne_area, sw_area, se_area] = areas;
+        let ((nw, ne), (sw, se)) = rayon::join(
+            || {
+                rayon::join(
+                    || Node::build_parallel(nw_area, nw, config, depth + 1),
+                    || Node::build_parallel(ne_area, ne, config, depth + 1),
+                )
+            },
+            || {
+                rayon::join(
+                    || Node::build_parallel(sw_area, sw, config, depth + 1),
+                    || Node::build_parallel(se_area, se, config, depth + 1),
+                )
+            },
+        );
+
+        Self {
+            area,
+            inner: NodeInner::Intermediate {
+                nw: Box::new(nw),
+                ne: Box::new(ne),
+                sw: Box::new(sw),
+                se: Box::new(se),
+            },
+            aggregate: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, qtree::QuadTree};
+
+    #[test]
+    fn parallel_build_matches_sequential() {
+        let points: Vec<_> = (0..200_000i64)
+            .map(|i| Point {
+                x: ((i * 7919) % 1000) as f64,
+                y: ((i * 104729) % 997) as f64,
+                data: i,
+            })
+            .collect();
+
+        let sequential = QuadTree::from_points(points.clone());
+        let parallel = QuadTree::from_points_parallel(points);
+        assert_eq!(parallel.stats(), sequential.stats());
+        assert!(parallel.iter().eq(sequential.iter()));
+    }
+}
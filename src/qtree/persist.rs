@@ -0,0 +1,594 @@
+//! Binary format for persisting the quad tree.
+//!
+//! The format is designed so that it can be queried without deserializing it,
+//! which is used by the memory mapped tree. All values are little endian.
+//!
+//! ```text
+//! header (32 bytes):
+//!     magic        [u8; 8] = "HRIBQTRE"
+//!     version      u32     = 4
+//!     reserved     u32
+//!     node_count   u64
+//!     point_count  u64
+//! config (8 bytes):
+//!     leaf_capacity  u32   saturated to u32::MAX
+//!     max_depth      u32   saturated to u32::MAX
+//! nodes (node_count * 56 bytes), in breadth first order, root first:
+//!     center_x     f64
+//!     center_y     f64
+//!     half_width   f64
+//!     half_height  f64
+//!     kind         u32     0 = leaf, 1 = intermediate
+//!     reserved     u32
+//!     first        u64     leaf: index of the first point, intermediate: index of nw child
+//!     count        u64     leaf: number of points, intermediate: 0
+//! points (point_count * 20 bytes):
+//!     x            f64
+//!     y            f64
+//!     height       f32
+//! ```
+//!
+//! Children of an intermediate node are stored consecutively in order nw, ne, sw, se.
+//! Points of a leaf are stored consecutively.
+//!
+//! Version 3 files don't store the config. When reading them, the default config
+//! is raised to fit the stored leaves, so that the tree keeps its invariants.
+//!
+//! Versions 1 and 2 only support square nodes, which store a single `radius`
+//! instead of `half_width` and `half_height`, making nodes 48 bytes. Version 1 additionally
+//! stores coordinates and radius as `f32` and has no reserved field in nodes,
+//! which makes nodes 32 bytes and points 12 bytes. Both are still supported for reading.
+
+use std::{collections::VecDeque, io};
+
+use crate::{Area, Point};
+
+use super::{DuplicatePolicy, Node, NodeInner, QuadTree, QuadTreeConfig, ReadError};
+
+pub(super) const MAGIC: [u8; 8] = *b"HRIBQTRE";
+pub(super) const VERSION: u32 = 4;
+
+pub(super) const HEADER_SIZE: usize = 32;
+const CONFIG_SIZE: usize = 8;
+
+pub(super) const KIND_LEAF: u32 = 0;
+pub(super) const KIND_INTERMEDIATE: u32 = 1;
+
+/// Sizes and offsets of the structures, which depend on the version of the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Layout {
+    /// Size of a single coordinate, 4 bytes for `f32` and 8 bytes for `f64`.
+    coord_size: usize,
+    /// Whether nodes store separate half width and half height, or a single radius.
+    rectangles: bool,
+    /// Whether the header is followed by the config of the tree.
+    config: bool,
+}
+
+impl Layout {
+    const CURRENT: Self = Self {
+        coord_size: 8,
+        rectangles: true,
+        config: true,
+    };
+
+    fn for_version(version: u32) -> Result<Self, ReadError> {
+        match version {
+            1 => Ok(Self {
+                coord_size: 4,
+                rectangles: false,
+                config: false,
+            }),
+            2 => Ok(Self {
+                coord_size: 8,
+                rectangles: false,
+                config: false,
+            }),
+            3 => Ok(Self {
+                config: false,
+                ..Self::CURRENT
+            }),
+            VERSION => Ok(Self::CURRENT),
+            _ => Err(ReadError::UnsupportedVersion(version)),
+        }
+    }
+
+    /// Size of the header, including the config. Nodes start right after it.
+    pub fn header_size(&self) -> usize {
+        if self.config {
+            HEADER_SIZE + CONFIG_SIZE
+        } else {
+            HEADER_SIZE
+        }
+    }
+
+    /// Number of coordinates describing the area of a node.
+    fn area_coords(&self) -> usize {
+        if self.rectangles { 4 } else { 3 }
+    }
+
+    pub fn node_size(&self) -> usize {
+        (self.area_coords() + 1) * self.coord_size + 16
+    }
+
+    pub fn point_size(&self) -> usize {
+        2 * self.coord_size + 4
+    }
+
+    fn coord_at(&self, buf: &[u8], offset: usize) -> f64 {
+        if self.coord_size == 4 {
+            f32_at(buf, offset) as f64
+        } else {
+            f64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+        }
+    }
+
+    pub fn node(&self, buf: &[u8]) -> RawNode {
+        let c = self.coord_size;
+        let center = Point {
+            x: self.coord_at(buf, 0),
+            y: self.coord_at(buf, c),
+            data: (),
+        };
+        let area = if self.rectangles {
+            Area {
+                center,
+                half_width: self.coord_at(buf, 2 * c),
+                half_height: self.coord_at(buf, 3 * c),
+            }
+        } else {
+            Area::square(center, self.coord_at(buf, 2 * c))
+        };
+
+        let kind = self.area_coords() * c;
+        RawNode {
+            area,
+            kind: u32::from_le_bytes(buf[kind..kind + 4].try_into().unwrap()),
+            first: u64_at(buf, kind + c),
+            count: u64_at(buf, kind + c + 8),
+        }
+    }
+
+    pub fn point(&self, buf: &[u8]) -> Point<f32> {
+        Point {
+            x: self.coord_at(buf, 0),
+            y: self.coord_at(buf, self.coord_size),
+            data: f32_at(buf, 2 * self.coord_size),
+        }
+    }
+}
+
+/// Node as it is stored in the file.
+pub(super) struct RawNode {
+    pub area: Area,
+    pub kind: u32,
+    pub first: u64,
+    pub count: u64,
+}
+
+impl RawNode {
+    /// Writes the node in the current version of the format.
+    fn write(&self, writer: &mut impl io::Write) -> io::Result<()> {
+        writer.write_all(&self.area.center.x.to_le_bytes())?;
+        writer.write_all(&self.area.center.y.to_le_bytes())?;
+        writer.write_all(&self.area.half_width.to_le_bytes())?;
+        writer.write_all(&self.area.half_height.to_le_bytes())?;
+        writer.write_all(&self.kind.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&self.first.to_le_bytes())?;
+        writer.write_all(&self.count.to_le_bytes())?;
+
+        Ok(())
+    }
+}
+
+/// Checks that the nodes are stored in breadth first order, as written by [`QuadTree::write_to`].
+///
+/// Children of intermediate nodes must follow each other in the same order as
+/// their parents, which gives every node except the root exactly one parent.
+/// That prevents cycles and subtrees shared between several parents.
+pub(super) struct NodeOrder {
+    next_child: u64,
+    node_count: u64,
+}
+
+impl NodeOrder {
+    pub fn new(node_count: u64) -> Self {
+        Self {
+            next_child: 1,
+            node_count,
+        }
+    }
+
+    /// Returns whether the node is at its expected place. Nodes must be checked in order.
+    pub fn check(&mut self, idx: u64, node: &RawNode) -> bool {
+        // Node that is not a child of any previous node is unreachable.
+        if idx >= self.next_child {
+            return false;
+        }
+
+        if node.kind == KIND_INTERMEDIATE {
+            let end = node.first.checked_add(4);
+            if node.first != self.next_child || end.is_none_or(|end| end > self.node_count) {
+                return false;
+            }
+            self.next_child += 4;
+        }
+
+        true
+    }
+}
+
+/// Parsed file header.
+pub(super) struct Header {
+    pub layout: Layout,
+    pub node_count: u64,
+    pub point_count: u64,
+}
+
+impl Header {
+    pub fn from_bytes(buf: &[u8; HEADER_SIZE]) -> Result<Self, ReadError> {
+        if buf[..8] != MAGIC {
+            return Err(ReadError::InvalidMagic);
+        }
+
+        let version = u32::from_le_bytes(buf[8..12].try_into().unwrap());
+
+        Ok(Self {
+            layout: Layout::for_version(version)?,
+            node_count: u64_at(buf, 16),
+            point_count: u64_at(buf, 24),
+        })
+    }
+}
+
+fn f32_at(buf: &[u8], offset: usize) -> f32 {
+    f32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+impl QuadTree<f32> {
+    /// Writes the tree into the writer in a compact binary format.
+    ///
+    /// Points are expected to contain height as data.
+    /// If writing to file, you should wrap it into
+    /// [BufWriter](https://doc.rust-lang.org/std/io/struct.BufWriter.html)
+    /// to improve the performance.
+    pub fn write_to(&self, mut writer: impl io::Write) -> io::Result<()> {
+        let (node_count, point_count) = self.root.counts();
+
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&0u32.to_le_bytes())?;
+        writer.write_all(&(node_count as u64).to_le_bytes())?;
+        writer.write_all(&(point_count as u64).to_le_bytes())?;
+        for value in [self.config.leaf_capacity, self.config.max_depth] {
+            let value = u32::try_from(value).unwrap_or(u32::MAX);
+            writer.write_all(&value.to_le_bytes())?;
+        }
+
+        // Nodes are written in breadth first order. Because children are pushed
+        // into the queue together, they end up next to each other.
+        let mut queue = VecDeque::from([&self.root]);
+        let mut next_node = 1;
+        let mut next_point = 0;
+        while let Some(node) = queue.pop_front() {
+            let raw = match &node.inner {
+                NodeInner::Leaf { points } => {
+                    let raw = RawNode {
+                        area: node.area.clone(),
+                        kind: KIND_LEAF,
+                        first: next_point,
+                        count: points.len() as u64,
+                    };
+                    next_point += points.len() as u64;
+                    raw
+                }
+                NodeInner::Intermediate { nw, ne, sw, se } => {
+                    queue.extend([nw, ne, sw, se].map(|child| &**child));
+                    let raw = RawNode {
+                        area: node.area.clone(),
+                        kind: KIND_INTERMEDIATE,
+                        first: next_node,
+                        count: 0,
+                    };
+                    next_node += 4;
+                    raw
+                }
+            };
+            raw.write(&mut writer)?;
+        }
+
+        // Points are written in the same order as the leaves.
+        let mut queue = VecDeque::from([&self.root]);
+        while let Some(node) = queue.pop_front() {
+            match &node.inner {
+                NodeInner::Leaf { points } => {
+                    for p in points {
+                        writer.write_all(&p.x.to_le_bytes())?;
+                        writer.write_all(&p.y.to_le_bytes())?;
+                        writer.write_all(&p.data.to_le_bytes())?;
+                    }
+                }
+                NodeInner::Intermediate { nw, ne, sw, se } => {
+                    queue.extend([nw, ne, sw, se].map(|child| &**child));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the tree written by [`QuadTree::write_to`].
+    ///
+    /// Config of the tree is restored, so that inserting into it behaves the same.
+    ///
+    /// Files written by older versions of the crate, which stored coordinates
+    /// as `f32`, are also supported. They can be migrated by reading them and
+    /// writing them again with [`QuadTree::write_to`].
+    ///
+    /// If reading from file, you should wrap it into
+    /// [BufReader](https://doc.rust-lang.org/std/io/struct.BufReader.html)
+    /// to improve the performance.
+    pub fn read_from(mut reader: impl io::Read) -> Result<Self, ReadError> {
+        let mut buf = [0u8; HEADER_SIZE];
+        reader.read_exact(&mut buf)?;
+        let header = Header::from_bytes(&buf)?;
+        let layout = header.layout;
+
+        let mut config = None;
+        if layout.config {
+            let mut buf = [0u8; CONFIG_SIZE];
+            reader.read_exact(&mut buf)?;
+            let value = |offset: usize| {
+                u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap()) as usize
+            };
+            config = Some(QuadTreeConfig {
+                leaf_capacity: value(0),
+                max_depth: value(4),
+            });
+        }
+
+        // Capacity is limited, so that corrupted header can't cause a huge allocation.
+        let mut nodes = Vec::with_capacity(header.node_count.min(1 << 20) as usize);
+        let mut buf = vec![0u8; layout.node_size()];
+        let mut order = NodeOrder::new(header.node_count);
+        for idx in 0..header.node_count {
+            reader.read_exact(&mut buf)?;
+            let node = layout.node(&buf);
+            if !order.check(idx, &node) {
+                return Err(ReadError::InvalidNode(idx));
+            }
+            nodes.push(node);
+        }
+
+        let mut points = Vec::with_capacity(header.point_count.min(1 << 24) as usize);
+        let mut buf = vec![0u8; layout.point_size()];
+        for _ in 0..header.point_count {
+            reader.read_exact(&mut buf)?;
+            points.push(layout.point(&buf));
+        }
+
+        if nodes.is_empty() {
+            return Err(ReadError::InvalidNode(0));
+        }
+
+        let root = build_node(&nodes, &points, 0)?;
+        let config = config.unwrap_or_else(|| fitting_config(&root));
+        Ok(Self {
+            root,
+            config,
+            duplicates: DuplicatePolicy::Allow,
+            aggregate_value: None,
+        })
+    }
+}
+
+/// Returns the default config, raised to fit the leaves of the tree.
+fn fitting_config(root: &Node<f32>) -> QuadTreeConfig {
+    fn visit(node: &Node<f32>, depth: usize, config: &mut QuadTreeConfig) {
+        config.max_depth = config.max_depth.max(depth);
+        match &node.inner {
+            NodeInner::Leaf { points } => {
+                config.leaf_capacity = config.leaf_capacity.max(points.len());
+            }
+            NodeInner::Intermediate { nw, ne, sw, se } => {
+                for child in [nw, ne, sw, se] {
+                    visit(child, depth + 1, config);
+                }
+            }
+        }
+    }
+
+    let mut config = QuadTreeConfig::default();
+    visit(root, 0, &mut config);
+    config
+}
+
+fn build_node(nodes: &[RawNode], points: &[Point<f32>], idx: u64) -> Result<Node<f32>, ReadError> {
+    let raw = &nodes[idx as usize];
+
+    let inner = match raw.kind {
+        KIND_LEAF => {
+            let points = raw
+                .first
+                .checked_add(raw.count)
+                .and_then(|end| points.get(raw.first as usize..end as usize))
+                .ok_or(ReadError::InvalidNode(idx))?;
+            NodeInner::Leaf {
+                points: points.to_vec(),
+            }
+        }
+        // Order of the nodes is already checked, so the children are after the parent.
+        KIND_INTERMEDIATE
+            if raw.first > idx
+                && raw
+                    .first
+                    .checked_add(4)
+                    .is_some_and(|end| end <= nodes.len() as u64) =>
+        {
+            let [nw, ne, sw, se] =
+                [0, 1, 2, 3].map(|i| build_node(nodes, points, raw.first + i).map(Box::new));
+            NodeInner::Intermediate {
+                nw: nw?,
+                ne: ne?,
+                sw: sw?,
+                se: se?,
+            }
+        }
+        _ => return Err(ReadError::InvalidNode(idx)),
+    };
+
+    Ok(Node {
+        area: raw.area.clone(),
+        inner,
+        aggregate: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Area, Point,
+        qtree::{QuadTree, QuadTreeConfig, ReadError},
+    };
+
+    #[test]
+    fn write_read_roundtrip() {
+        let points: Vec<_> = (0..5000)
+            .map(|i| Point {
+                x: (i % 100) as f64,
+                y: (i / 100) as f64,
+                data: i as f32 * 0.5,
+            })
+            .collect();
+        let tree = QuadTree::from_points(points);
+
+        let mut buf = vec![];
+        tree.write_to(&mut buf).unwrap();
+        let read = QuadTree::read_from(&buf[..]).unwrap();
+
+        assert_eq!(read.size(), tree.size());
+        let area = Area::square(
+            Point {
+                x: 50.0,
+                y: 25.0,
+                data: (),
+            },
+            10.0,
+        );
+        assert_eq!(
+            read.query_vec(&area).unwrap(),
+            tree.query_vec(&area).unwrap()
+        );
+
+        let mut corrupted = buf.clone();
+        corrupted[0] = b'X';
+        assert!(QuadTree::read_from(&corrupted[..]).is_err());
+    }
+
+    #[test]
+    fn read_invalid_nodes() {
+        // Root and four leaves of a tree without points.
+        let tree = QuadTree::<f32>::new(Area::from_bounds(0.0, 0.0, 10.0, 10.0));
+        let mut buf = vec![];
+        tree.write_to(&mut buf).unwrap();
+        let node = |idx: usize| 40 + idx * 56;
+        let first = |idx: usize| node(idx) + 40..node(idx) + 48;
+
+        let mut root = buf[..node(1)].to_vec();
+        root[16..24].copy_from_slice(&1u64.to_le_bytes());
+        root[first(0)].copy_from_slice(&u64::MAX.to_le_bytes());
+        root[node(0) + 32..node(0) + 36].copy_from_slice(&1u32.to_le_bytes());
+        assert!(matches!(
+            QuadTree::read_from(&root[..]),
+            Err(ReadError::InvalidNode(0))
+        ));
+
+        // Second intermediate node shares the children of the root.
+        let mut shared = vec![];
+        let mut tree = QuadTree::with_config(
+            Area::from_bounds(0.0, 0.0, 10.0, 10.0),
+            QuadTreeConfig::default().with_leaf_capacity(1),
+        );
+        for (x, y) in [(1.0, 1.0), (2.0, 2.0), (9.0, 9.0)] {
+            tree.insert(Point { x, y, data: 0.0 }).unwrap();
+        }
+        tree.write_to(&mut shared).unwrap();
+        assert!(QuadTree::read_from(&shared[..]).is_ok());
+        let intermediate = (1..5).find(|&idx| shared[node(idx) + 32] == 1).unwrap();
+        shared[first(intermediate)].copy_from_slice(&1u64.to_le_bytes());
+        assert!(matches!(
+            QuadTree::read_from(&shared[..]),
+            Err(ReadError::InvalidNode(idx)) if idx == intermediate as u64
+        ));
+    }
+
+    #[test]
+    fn write_read_config() {
+        let config = QuadTreeConfig::default()
+            .with_leaf_capacity(2000)
+            .with_max_depth(6);
+        let mut tree = QuadTree::with_config(Area::from_bounds(0.0, 0.0, 100.0, 100.0), config);
+        // Single leaf, which is over the default capacity.
+        for i in 0..1500 {
+            tree.insert(Point {
+                x: (i % 50) as f64 * 2.0,
+                y: (i / 50) as f64 * 3.0,
+                data: i as f32,
+            })
+            .unwrap();
+        }
+
+        let mut buf = vec![];
+        tree.write_to(&mut buf).unwrap();
+        let read = QuadTree::read_from(&buf[..]).unwrap();
+        assert_eq!(read.config, config);
+        assert_eq!(read.debug_validate(), Ok(()));
+
+        // Version 3 has no config, so it is fitted to the leaves.
+        let mut v3 = buf[..32].to_vec();
+        v3[8..12].copy_from_slice(&3u32.to_le_bytes());
+        v3.extend(&buf[40..]);
+        let read = QuadTree::read_from(&v3[..]).unwrap();
+        assert_eq!(read.size(), tree.size());
+        assert_eq!(read.debug_validate(), Ok(()));
+    }
+
+    #[test]
+    fn read_version_1() {
+        // Single leaf with two points and f32 coordinates.
+        let mut buf = b"HRIBQTRE".to_vec();
+        buf.extend(1u32.to_le_bytes());
+        buf.extend(0u32.to_le_bytes());
+        buf.extend(1u64.to_le_bytes());
+        buf.extend(2u64.to_le_bytes());
+        buf.extend([5.0f32, 5.0, 5.0].iter().flat_map(|v| v.to_le_bytes()));
+        buf.extend(0u32.to_le_bytes());
+        buf.extend(0u64.to_le_bytes());
+        buf.extend(2u64.to_le_bytes());
+        buf.extend(
+            [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes()),
+        );
+
+        let tree = QuadTree::read_from(&buf[..]).unwrap();
+        let nearest = tree.nearest(&Point {
+            x: 4.0,
+            y: 4.0,
+            data: (),
+        });
+        assert_eq!(
+            nearest,
+            Some(&Point {
+                x: 4.0,
+                y: 5.0,
+                data: 6.0,
+            })
+        );
+    }
+}
@@ -0,0 +1,116 @@
+//! Structure and memory statistics of the quad tree.
+
+use std::mem::size_of;
+
+use crate::Point;
+
+use super::{Node, NodeInner, QuadTree};
+
+/// Statistics about the structure of a [`QuadTree`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuadTreeStats {
+    /// Number of all nodes, including leaves.
+    pub node_count: usize,
+    /// Number of leaf nodes.
+    pub leaf_count: usize,
+    /// Number of points in the tree.
+    pub point_count: usize,
+    /// Depth of the deepest leaf. Root is at depth 0.
+    pub max_depth: usize,
+    /// Average depth of the leaves.
+    pub average_depth: f64,
+    /// Histogram of points per leaf.
+    ///
+    /// Bucket 0 counts empty leaves and bucket `i > 0` counts leaves
+    /// with `2^(i-1)..2^i` points.
+    pub points_per_leaf: Vec<usize>,
+    /// Approximate number of bytes the tree allocates on the heap.
+    ///
+    /// Heap memory owned by the point data is not included.
+    pub heap_bytes: usize,
+}
+
+impl<T> QuadTree<T> {
+    /// Collects statistics about the structure of the tree.
+    ///
+    /// The whole tree is traversed, so this is as expensive as iterating over all points.
+    pub fn stats(&self) -> QuadTreeStats {
+        let mut stats = QuadTreeStats::default();
+        let mut depth_sum = 0;
+        collect(&self.root, 0, &mut stats, &mut depth_sum);
+
+        if stats.leaf_count > 0 {
+            stats.average_depth = depth_sum as f64 / stats.leaf_count as f64;
+        }
+        stats
+    }
+}
+
+fn collect<T>(node: &Node<T>, depth: usize, stats: &mut QuadTreeStats, depth_sum: &mut usize) {
+    stats.node_count += 1;
+    match &node.inner {
+        NodeInner::Leaf { points } => {
+            stats.leaf_count += 1;
+            stats.point_count += points.len();
+            stats.max_depth = stats.max_depth.max(depth);
+            stats.heap_bytes += points.capacity() * size_of::<Point<T>>();
+            *depth_sum += depth;
+
+            let bucket = (usize::BITS - points.len().leading_zeros()) as usize;
+            if stats.points_per_leaf.len() <= bucket {
+                stats.points_per_leaf.resize(bucket + 1, 0);
+            }
+            stats.points_per_leaf[bucket] += 1;
+        }
+        NodeInner::Intermediate { nw, ne, sw, se } => {
+            for child in [nw, ne, sw, se] {
+                stats.heap_bytes += size_of::<Node<T>>();
+                collect(child, depth + 1, stats, depth_sum);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Area, Point, qtree::QuadTree};
+
+    #[test]
+    fn stats_of_subdivided_tree() {
+        let mut tree = QuadTree::new(Area::square(
+            Point {
+                x: 50.0,
+                y: 50.0,
+                data: (),
+            },
+            50.0,
+        ));
+        assert_eq!(tree.stats().points_per_leaf, vec![1]);
+
+        // Points only in a single quadrant, so the other three leaves stay empty.
+        for i in 0..1500 {
+            let p = Point {
+                x: (i % 40) as f64 + 0.5,
+                y: (i / 40) as f64 + 60.0,
+                data: (),
+            };
+            tree.insert(p).unwrap();
+        }
+
+        let stats = tree.stats();
+        assert_eq!(stats.point_count, 1500);
+        assert_eq!(
+            stats.node_count,
+            stats.leaf_count + (stats.leaf_count - 1) / 3
+        );
+        assert_eq!(
+            stats.points_per_leaf.iter().sum::<usize>(),
+            stats.leaf_count
+        );
+        assert!(stats.points_per_leaf[0] >= 3);
+        assert!(stats.max_depth >= 2);
+        assert!(stats.average_depth > 1.0 && stats.average_depth <= stats.max_depth as f64);
+        assert!(stats.heap_bytes >= 1500 * size_of::<Point>());
+    }
+}
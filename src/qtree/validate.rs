@@ -0,0 +1,111 @@
+//! Checking of the structural invariants of the quad tree.
+
+use thiserror::Error;
+
+use super::{Node, NodeInner, QuadTree, QuadTreeConfig, subdivide_area};
+
+/// Broken invariant of the quad tree, returned by [`QuadTree::debug_validate`].
+#[derive(Debug, Error, PartialEq)]
+pub enum ValidationError {
+    /// Point is stored in a leaf, which doesn't contain it.
+    #[error("point ({x}, {y}) is outside of its leaf at depth {depth}")]
+    PointOutsideLeaf { x: f64, y: f64, depth: usize },
+
+    /// Areas of the children are not the quadrants of their parent.
+    #[error("children at depth {depth} don't match the quadrants of their parent")]
+    InvalidQuadrants { depth: usize },
+
+    /// Leaf holds more points than allowed and is not at the maximum depth.
+    #[error("leaf at depth {depth} holds {count} points")]
+    LeafOverCapacity { depth: usize, count: usize },
+
+    /// Tree is deeper than the maximum depth.
+    #[error("node at depth {depth} exceeds the maximum depth")]
+    TooDeep { depth: usize },
+}
+
+impl<T> QuadTree<T> {
+    /// Checks the structural invariants of the tree.
+    ///
+    /// The tree always upholds them, so this is meant for tests and debugging
+    /// of code, which works with the tree. The whole tree is traversed.
+    pub fn debug_validate(&self) -> Result<(), ValidationError> {
+        validate(&self.root, &self.config, 0)
+    }
+}
+
+fn validate<T>(
+    node: &Node<T>,
+    config: &QuadTreeConfig,
+    depth: usize,
+) -> Result<(), ValidationError> {
+    if depth > config.max_depth {
+        return Err(ValidationError::TooDeep { depth });
+    }
+
+    match &node.inner {
+        NodeInner::Leaf { points } => {
+            if points.len() > config.leaf_capacity && depth < config.max_depth {
+                return Err(ValidationError::LeafOverCapacity {
+                    depth,
+                    count: points.len(),
+                });
+            }
+
+            match points.iter().find(|p| !node.area.is_point_inside(p)) {
+                Some(p) => Err(ValidationError::PointOutsideLeaf {
+                    x: p.x,
+                    y: p.y,
+                    depth,
+                }),
+                None => Ok(()),
+            }
+        }
+        NodeInner::Intermediate { nw, ne, sw, se } => {
+            let children = [nw, ne, sw, se];
+            let quadrants = subdivide_area(&node.area);
+            if children.iter().zip(&quadrants).any(|(c, q)| c.area != *q) {
+                return Err(ValidationError::InvalidQuadrants { depth });
+            }
+
+            children
+                .into_iter()
+                .try_for_each(|child| validate(child, config, depth + 1))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Area, Point,
+        qtree::{NodeInner, QuadTree},
+    };
+
+    use super::ValidationError;
+
+    #[test]
+    fn detects_point_outside_leaf() {
+        let mut tree = QuadTree::new(Area::from_bounds(0.0, 0.0, 10.0, 10.0));
+        tree.insert(Point {
+            x: 1.0,
+            y: 1.0,
+            data: (),
+        })
+        .unwrap();
+        assert_eq!(tree.debug_validate(), Ok(()));
+
+        // Invariants can only be broken from the inside.
+        if let NodeInner::Leaf { points } = &mut tree.root.inner {
+            points[0].x = 20.0;
+        }
+        assert_eq!(
+            tree.debug_validate(),
+            Err(ValidationError::PointOutsideLeaf {
+                x: 20.0,
+                y: 1.0,
+                depth: 0
+            })
+        );
+    }
+}
@@ -0,0 +1,494 @@
+mod horizon;
+mod insolation;
+mod inundation;
+mod peaks;
+mod route;
+mod store;
+
+use crate::{Area, Point, interpolate::IdwSum, qtree::QuadTree};
+
+pub use horizon::HorizonPoint;
+pub use insolation::insolation;
+pub use inundation::inundation;
+pub use peaks::{Peak, find_peaks};
+pub use route::{CostPath, HikingTime, PathCost, isochrone, isochrone_outline, least_cost_path};
+pub use store::TerrainStore;
+
+/// Number of nearest points used for the interpolation.
+const NEIGHBOURS: usize = 4;
+
+/// Distance between samples of the line of sight in meters.
+const LOS_STEP: f64 = 1.0;
+
+/// Mean radius of the Earth in meters.
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+/// Sample of an elevation profile.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProfilePoint {
+    /// Location of the sample.
+    pub point: Point,
+    /// Distance along the path from its start in meters.
+    pub distance: f64,
+    /// Interpolated height in meters, None if there is no data at the location.
+    pub elevation: Option<f32>,
+    /// Grade from the previous sample as rise over run, e.g. `0.1` is 10 % uphill.
+    ///
+    /// None for the first sample or if any of the elevations is missing.
+    pub grade: Option<f32>,
+}
+
+/// Result of the line of sight query.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LosResult {
+    /// Weather the terrain doesn't block the line of sight.
+    pub visible: bool,
+    /// The first location, looking from the start, where the terrain blocks
+    /// the line of sight, with terrain height as data. None if visible.
+    pub obstruction: Option<Point<f32>>,
+    /// The smallest height of the line of sight above the terrain in meters.
+    /// Negative if the line of sight is blocked.
+    pub clearance: f64,
+}
+
+/// Terrain model that answers questions about heights on the map.
+///
+/// Heights are interpolated with inverse distance weighting from
+/// the nearest points of the underlying quad tree.
+pub struct Terrain {
+    tree: QuadTree<f32>,
+    max_distance: f64,
+}
+
+impl Terrain {
+    /// Default maximum distance of points used for the interpolation,
+    /// which fits the 5 m grid of the `DEM 0050` data.
+    pub const DEFAULT_MAX_DISTANCE: f64 = 10.0;
+
+    /// Creates terrain from the quad tree, which contains heights as data.
+    pub fn new(tree: QuadTree<f32>) -> Self {
+        Self {
+            tree,
+            max_distance: Self::DEFAULT_MAX_DISTANCE,
+        }
+    }
+
+    /// Sets the maximum distance of points used for the interpolation.
+    ///
+    /// Locations without any point in this distance have no elevation,
+    /// which prevents extrapolating over gaps in the data.
+    pub fn with_max_distance(mut self, max_distance: f64) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Returns the underlying quad tree.
+    pub fn tree(&self) -> &QuadTree<f32> {
+        &self.tree
+    }
+
+    /// Returns the interpolated height at the given location in meters.
+    ///
+    /// If there are no points close enough, None is returned.
+    pub fn elevation_at(&self, x: f64, y: f64) -> Option<f32> {
+        let point = Point { x, y, data: () };
+
+        let mut neighbours = Vec::with_capacity(NEIGHBOURS);
+        self.tree.nearest_k(&point, NEIGHBOURS, &mut neighbours);
+
+        let mut sum = IdwSum::new(&point, 2.0, self.max_distance);
+        sum.extend(neighbours);
+        sum.value()
+    }
+
+    /// Returns interpolated heights at many locations, in the order of the points.
+    ///
+    /// Same as calling [`Terrain::elevation_at`] for every point, but faster
+    /// for large batches. Locations are visited along the Z-order curve, so that
+    /// nearby queries walk the same nodes of the tree while they are still in the cache.
+    /// With the `rayon` feature, queries are split between multiple threads.
+    pub fn elevations<T>(&self, points: &[Point<T>]) -> Vec<Option<f32>> {
+        if points.is_empty() {
+            return vec![];
+        }
+
+        let bounds = Area::from_points(points);
+        let mut order: Vec<_> = (0..points.len()).collect();
+        order.sort_by_cached_key(|&i| z_order(&bounds, &points[i]));
+        let locations: Vec<_> = order.iter().map(|&i| (points[i].x, points[i].y)).collect();
+
+        #[cfg(feature = "rayon")]
+        let sorted: Vec<_> = {
+            use rayon::prelude::*;
+            locations
+                .par_iter()
+                .map(|&(x, y)| self.elevation_at(x, y))
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let sorted: Vec<_> = locations
+            .iter()
+            .map(|&(x, y)| self.elevation_at(x, y))
+            .collect();
+
+        let mut elevations = vec![None; points.len()];
+        for (i, elevation) in order.into_iter().zip(sorted) {
+            elevations[i] = elevation;
+        }
+        elevations
+    }
+
+    /// Replaces elevations of the points with interpolated heights.
+    ///
+    /// Points without terrain data at their location keep their elevation.
+    pub fn replace_elevations(&self, points: &mut [Point<Option<f32>>]) {
+        let elevations = self.elevations(points);
+        for (p, elevation) in points.iter_mut().zip(elevations) {
+            if elevation.is_some() {
+                p.data = elevation;
+            }
+        }
+    }
+
+    /// Checks weather the terrain blocks the line of sight between two locations.
+    ///
+    /// Data of the points is the height of the observer and the target above the
+    /// terrain, for example the height of an antenna mast. Terrain is sampled every
+    /// meter along the line, and the curvature of the Earth is taken into account.
+    /// Samples without terrain data are skipped.
+    ///
+    /// None is returned if any of the points has no terrain data.
+    pub fn line_of_sight(&self, a: &Point<f32>, b: &Point<f32>) -> Option<LosResult> {
+        let start = self.elevation_at(a.x, a.y)? as f64 + a.data as f64;
+        let end = self.elevation_at(b.x, b.y)? as f64 + b.data as f64;
+
+        let length = a.distance(b);
+        let steps = (length / LOS_STEP).ceil() as usize;
+
+        let mut result = LosResult {
+            visible: true,
+            obstruction: None,
+            clearance: f64::INFINITY,
+        };
+        for i in 1..steps {
+            let t = i as f64 / steps as f64;
+            let (x, y) = (a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+            let Some(terrain) = self.elevation_at(x, y) else {
+                continue;
+            };
+
+            // Earth curves away from the straight line between the points.
+            let bulge = (length * t) * (length * (1.0 - t)) / (2.0 * EARTH_RADIUS);
+            let clearance = start + (end - start) * t - (terrain as f64 + bulge);
+            result.clearance = result.clearance.min(clearance);
+
+            if clearance < 0.0 && result.visible {
+                result.visible = false;
+                result.obstruction = Some(Point {
+                    x,
+                    y,
+                    data: terrain,
+                });
+            }
+        }
+
+        Some(result)
+    }
+
+    /// Samples elevations along the path.
+    ///
+    /// Every vertex of the path is sampled, and segments between the vertices
+    /// are split evenly, so that the samples are at most `sample_distance` meters apart.
+    /// Empty path returns empty profile.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_distance` is not positive.
+    pub fn profile<T>(&self, path: &[Point<T>], sample_distance: f64) -> Vec<ProfilePoint> {
+        assert!(sample_distance > 0.0, "sample distance must be positive");
+        let Some(first) = path.first() else {
+            return vec![];
+        };
+
+        let mut profile = vec![];
+        let mut push = |point: Point, distance: f64| {
+            let elevation = self.elevation_at(point.x, point.y);
+            let grade = profile.last().and_then(|prev: &ProfilePoint| {
+                let run = distance - prev.distance;
+                let rise = elevation? - prev.elevation?;
+                (run > 0.0).then(|| rise / run as f32)
+            });
+            profile.push(ProfilePoint {
+                point,
+                distance,
+                elevation,
+                grade,
+            });
+        };
+
+        push(
+            Point {
+                x: first.x,
+                y: first.y,
+                data: (),
+            },
+            0.0,
+        );
+
+        let mut distance = 0.0;
+        for segment in path.windows(2) {
+            let (a, b) = (&segment[0], &segment[1]);
+            let length = a.distance(b);
+            let steps = (length / sample_distance).ceil().max(1.0) as usize;
+
+            for i in 1..=steps {
+                let t = i as f64 / steps as f64;
+                let point = Point {
+                    x: a.x + (b.x - a.x) * t,
+                    y: a.y + (b.y - a.y) * t,
+                    data: (),
+                };
+                push(point, distance + length * t);
+            }
+            distance += length;
+        }
+
+        profile
+    }
+
+    /// Samples elevations along the straight line from `a` to `b`, returning
+    /// pairs of distance from `a` in meters and elevation.
+    ///
+    /// Unlike [`Terrain::profile`], samples are exactly `sample_step` meters apart,
+    /// so that sections of different lengths can be compared. The last sample
+    /// is at `b` and can be closer to the previous one. Elevations are None at
+    /// locations without terrain data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `sample_step` is not positive.
+    pub fn cross_section<T, U>(
+        &self,
+        a: &Point<T>,
+        b: &Point<U>,
+        sample_step: f64,
+    ) -> Vec<(f64, Option<f32>)> {
+        assert!(sample_step > 0.0, "sample step must be positive");
+
+        let length = a.distance(b);
+        let mut distances: Vec<_> = (0..)
+            .map(|i| i as f64 * sample_step)
+            .take_while(|&d| d < length)
+            .collect();
+        distances.push(length);
+
+        let points: Vec<_> = distances
+            .iter()
+            .map(|&d| {
+                let t = if length > 0.0 { d / length } else { 0.0 };
+                Point {
+                    x: a.x + (b.x - a.x) * t,
+                    y: a.y + (b.y - a.y) * t,
+                    data: (),
+                }
+            })
+            .collect();
+
+        distances
+            .into_iter()
+            .zip(self.elevations(&points))
+            .collect()
+    }
+}
+
+/// Returns position of the point on the Z-order curve over the bounds.
+fn z_order<T>(bounds: &Area, point: &Point<T>) -> u64 {
+    let scale = |v: f64, min: f64, max: f64| {
+        let t = if max > min {
+            (v - min) / (max - min)
+        } else {
+            0.0
+        };
+        (t * u32::MAX as f64) as u32
+    };
+    let x = scale(point.x, bounds.min_x(), bounds.max_x());
+    let y = scale(point.y, bounds.min_y(), bounds.max_y());
+
+    // Interleaves bits of the coordinates.
+    let spread = |v: u32| {
+        let mut v = v as u64;
+        v = (v | (v << 16)) & 0x0000_FFFF_0000_FFFF;
+        v = (v | (v << 8)) & 0x00FF_00FF_00FF_00FF;
+        v = (v | (v << 4)) & 0x0F0F_0F0F_0F0F_0F0F;
+        v = (v | (v << 2)) & 0x3333_3333_3333_3333;
+        (v | (v << 1)) & 0x5555_5555_5555_5555
+    };
+    spread(x) | (spread(y) << 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, qtree::QuadTree};
+
+    use super::Terrain;
+
+    fn plane() -> Terrain {
+        // 5 m grid of a plane.
+        let points: Vec<_> = (0..400)
+            .map(|i| {
+                let (x, y) = ((i % 20) as f64 * 5.0, (i / 20) as f64 * 5.0);
+                Point {
+                    x,
+                    y,
+                    data: (x + 2.0 * y) as f32,
+                }
+            })
+            .collect();
+        Terrain::new(QuadTree::from_points(points))
+    }
+
+    #[test]
+    fn elevation_at_interpolates() {
+        let terrain = plane();
+
+        assert_eq!(terrain.elevation_at(10.0, 20.0), Some(50.0));
+
+        // Center of a cell is equally distant from the corners.
+        let got = terrain.elevation_at(12.5, 22.5).unwrap();
+        assert!((got - 57.5).abs() < 1e-4, "{got}");
+
+        let got = terrain.elevation_at(11.0, 21.0).unwrap();
+        assert!(got > 50.0 && got < 57.5, "{got}");
+
+        assert_eq!(terrain.elevation_at(-100.0, 0.0), None);
+
+        let sparse = Terrain::new(QuadTree::from_points(vec![
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: 1.0,
+            },
+            Point {
+                x: 100.0,
+                y: 100.0,
+                data: 1.0,
+            },
+        ]));
+        assert_eq!(sparse.elevation_at(50.0, 50.0), None);
+        assert_eq!(
+            sparse.with_max_distance(100.0).elevation_at(50.0, 50.0),
+            Some(1.0)
+        );
+    }
+
+    #[test]
+    fn elevations_match_single_queries() {
+        let terrain = plane();
+        let points: Vec<_> = (0..500)
+            .map(|i| Point {
+                x: ((i * 37) % 130) as f64 - 10.0,
+                y: ((i * 53) % 110) as f64 - 5.0,
+                data: (),
+            })
+            .collect();
+
+        let expected: Vec<_> = points
+            .iter()
+            .map(|p| terrain.elevation_at(p.x, p.y))
+            .collect();
+        assert_eq!(terrain.elevations(&points), expected);
+        assert!(expected.contains(&None));
+        assert!(terrain.elevations::<()>(&[]).is_empty());
+    }
+
+    #[test]
+    fn profile_samples_path() {
+        let terrain = plane();
+        let path = [(0.0, 0.0), (0.0, 20.0), (15.0, 20.0)].map(|(x, y)| Point { x, y, data: () });
+
+        let profile = terrain.profile(&path, 5.0);
+        let distances: Vec<_> = profile.iter().map(|p| p.distance).collect();
+        assert_eq!(distances, [0.0, 5.0, 10.0, 15.0, 20.0, 25.0, 30.0, 35.0]);
+
+        // Going north the height rises by 2 m per meter, going east by 1 m.
+        assert_eq!(profile[0].grade, None);
+        assert_eq!(profile[0].elevation, Some(0.0));
+        assert_eq!(profile[4].elevation, Some(40.0));
+        assert_eq!(profile[4].grade, Some(2.0));
+        assert_eq!(profile[7].elevation, Some(55.0));
+        assert_eq!(profile[7].grade, Some(1.0));
+
+        assert!(terrain.profile::<()>(&[], 5.0).is_empty());
+    }
+
+    #[test]
+    fn cross_section_has_fixed_step() {
+        let terrain = plane();
+        let a = Point {
+            x: 10.0,
+            y: 0.0,
+            data: (),
+        };
+        let b = Point {
+            x: 10.0,
+            y: 12.0,
+            data: (),
+        };
+
+        let section = terrain.cross_section(&a, &b, 5.0);
+        assert_eq!(
+            section[..3],
+            [(0.0, Some(10.0)), (5.0, Some(20.0)), (10.0, Some(30.0))]
+        );
+        // The last sample is between the grid points.
+        let (distance, elevation) = section[3];
+        assert_eq!(distance, 12.0);
+        assert!(elevation.is_some_and(|z| z > 30.0 && z < 40.0));
+
+        assert_eq!(terrain.cross_section(&a, &a, 5.0), [(0.0, Some(10.0))]);
+    }
+
+    #[test]
+    fn line_of_sight_over_ridge() {
+        // Ridge along x = 50, 20 m above the flat terrain.
+        let points: Vec<_> = (0..441)
+            .map(|i| {
+                let (x, y) = ((i % 21) as f64 * 5.0, (i / 21) as f64 * 5.0);
+                Point {
+                    x,
+                    y,
+                    data: if x == 50.0 { 20.0 } else { 0.0 },
+                }
+            })
+            .collect();
+        let terrain = Terrain::new(QuadTree::from_points(points));
+
+        let observer = |x, height| Point {
+            x,
+            y: 50.0,
+            data: height,
+        };
+
+        let blocked = terrain
+            .line_of_sight(&observer(0.0, 2.0), &observer(100.0, 2.0))
+            .unwrap();
+        assert!(!blocked.visible);
+        let obstruction = blocked.obstruction.unwrap();
+        assert!(obstruction.x > 45.0 && obstruction.x < 50.0);
+        assert!(blocked.clearance < -15.0);
+
+        let visible = terrain
+            .line_of_sight(&observer(0.0, 30.0), &observer(100.0, 30.0))
+            .unwrap();
+        assert!(visible.visible);
+        assert_eq!(visible.obstruction, None);
+        assert!((visible.clearance - 10.0).abs() < 0.01);
+
+        assert_eq!(
+            terrain.line_of_sight(&observer(0.0, 2.0), &observer(500.0, 2.0)),
+            None
+        );
+    }
+}
@@ -0,0 +1,153 @@
+//! Skyline of the terrain seen from a point.
+
+use crate::Point;
+
+use super::{EARTH_RADIUS, LOS_STEP, Terrain};
+
+/// Growth of the step between samples of the horizon, relative to the distance
+/// from the observer. Distant terrain is sampled sparsely, since it spans smaller angles.
+const HORIZON_STEP_RATIO: f64 = 0.005;
+
+/// Highest point of the terrain in one direction.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HorizonPoint {
+    /// Direction in degrees clockwise from north.
+    pub azimuth: f64,
+    /// Angle of the horizon above the observer's level in degrees.
+    /// Negative if the terrain falls away, e.g. looking into a valley.
+    pub angle: f64,
+    /// Location of the terrain forming the horizon, with its height as data.
+    /// None if there is no terrain data in this direction.
+    pub location: Option<Point<f32>>,
+    /// Horizontal distance of the horizon from the observer in meters.
+    pub distance: f64,
+}
+
+impl Terrain {
+    /// Computes the horizon seen from the observer, for example to draw a panorama.
+    ///
+    /// Data of the observer is its height above the terrain. Directions start at
+    /// north and are `azimuth_step` degrees apart. Terrain is traced up to the
+    /// edge of the quad tree, and the curvature of the Earth is taken into account.
+    ///
+    /// None is returned if the observer has no terrain data.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `azimuth_step` is not positive.
+    pub fn horizon(&self, observer: &Point<f32>, azimuth_step: f64) -> Option<Vec<HorizonPoint>> {
+        assert!(azimuth_step > 0.0, "azimuth step must be positive");
+
+        let height = self.elevation_at(observer.x, observer.y)? as f64 + observer.data as f64;
+        let count = (360.0 / azimuth_step).ceil() as usize;
+        let azimuths: Vec<_> = (0..count).map(|i| i as f64 * azimuth_step).collect();
+
+        #[cfg(feature = "rayon")]
+        let horizon = {
+            use rayon::prelude::*;
+            azimuths
+                .par_iter()
+                .map(|&azimuth| self.horizon_point(observer, height, azimuth))
+                .collect()
+        };
+        #[cfg(not(feature = "rayon"))]
+        let horizon = azimuths
+            .iter()
+            .map(|&azimuth| self.horizon_point(observer, height, azimuth))
+            .collect();
+
+        Some(horizon)
+    }
+
+    fn horizon_point<T>(&self, observer: &Point<T>, height: f64, azimuth: f64) -> HorizonPoint {
+        let (dx, dy) = (azimuth.to_radians().sin(), azimuth.to_radians().cos());
+        let area = self.tree.area();
+
+        let mut result = HorizonPoint {
+            azimuth,
+            angle: -90.0,
+            location: None,
+            distance: 0.0,
+        };
+
+        let mut distance = LOS_STEP;
+        loop {
+            let (x, y) = (observer.x + dx * distance, observer.y + dy * distance);
+            let point = Point { x, y, data: () };
+            if !area.is_point_inside(&point) {
+                break;
+            }
+
+            if let Some(terrain) = self.elevation_at(x, y) {
+                // Earth curves away from the observer's level.
+                let drop = distance * distance / (2.0 * EARTH_RADIUS);
+                let angle = (terrain as f64 - drop - height)
+                    .atan2(distance)
+                    .to_degrees();
+                if angle > result.angle {
+                    result.angle = angle;
+                    result.location = Some(Point {
+                        x,
+                        y,
+                        data: terrain,
+                    });
+                    result.distance = distance;
+                }
+            }
+
+            distance += LOS_STEP.max(distance * HORIZON_STEP_RATIO);
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, qtree::QuadTree};
+
+    use super::Terrain;
+
+    #[test]
+    fn horizon_sees_the_hill() {
+        // Flat terrain on a 20 m grid with a 500 m high hill at (3000, 2000).
+        let points: Vec<_> = (0..201 * 201)
+            .map(|i| {
+                let (x, y) = ((i % 201) as f64 * 20.0, (i / 201) as f64 * 20.0);
+                let r2 = (x - 3000.0).powi(2) + (y - 2000.0).powi(2);
+                Point {
+                    x,
+                    y,
+                    data: (500.0 * (-r2 / (2.0 * 300.0f64.powi(2))).exp()) as f32,
+                }
+            })
+            .collect();
+        let terrain = Terrain::new(QuadTree::from_points(points)).with_max_distance(30.0);
+
+        let observer = Point {
+            x: 1000.0,
+            y: 2000.0,
+            data: 2.0,
+        };
+        let horizon = terrain.horizon(&observer, 90.0).unwrap();
+        assert_eq!(horizon.len(), 4);
+
+        let east = &horizon[1];
+        assert_eq!(east.azimuth, 90.0);
+        assert!(east.angle > 13.0 && east.angle < 16.0, "{}", east.angle);
+        let peak = east.location.as_ref().unwrap();
+        assert!(peak.x > 2500.0 && peak.x < 3100.0, "{}", peak.x);
+
+        // Flat terrain is slightly below the observer.
+        let west = &horizon[3];
+        assert!(west.angle < 0.0 && west.angle > -1.0, "{}", west.angle);
+
+        let outside = Point {
+            x: -100.0,
+            y: 0.0,
+            data: 0.0,
+        };
+        assert_eq!(terrain.horizon(&outside, 1.0), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Clear-sky solar radiation over the terrain.
+
+use std::{f64::consts::PI, ops::RangeInclusive};
+
+use crate::grid::{GradientMethod, Raster};
+
+/// Solar constant in W/m².
+const SOLAR_CONSTANT: f64 = 1361.0;
+
+/// Fraction of the direct radiation passing the atmosphere in zenith on a clear day.
+const TRANSMITTANCE: f64 = 0.7;
+
+/// Number of azimuth sectors of the horizon, computed for every cell.
+const HORIZON_SECTORS: usize = 36;
+
+/// Growth of the distance between horizon samples, which are sparser far away.
+const HORIZON_STEP_GROWTH: f64 = 1.1;
+
+/// Number of days sampled from the date range, so that long ranges stay fast.
+const MAX_SAMPLED_DAYS: u32 = 24;
+
+/// Time between sun positions in hours.
+const TIME_STEP: f64 = 0.5;
+
+/// Position of the sun with the time it represents.
+struct SunPosition {
+    /// Angle above the horizon in radians.
+    elevation: f64,
+    /// Direction in radians clockwise from north.
+    azimuth: f64,
+    /// Hours represented by the position.
+    hours: f64,
+}
+
+/// Estimates clear-sky solar radiation per cell in kWh/m², summed over the days of the year.
+///
+/// `days` are days of the year from 1 to 365, and `latitude` is the latitude
+/// of the raster in degrees, which is constant for small areas like Slovenia.
+/// Direct radiation takes into account slope, aspect and shading by the terrain
+/// within the raster, while diffuse radiation is scaled by the visible part of the sky.
+/// The atmosphere is modeled with a constant transmittance, so cloudiness is not included.
+///
+/// Every cell traces its horizon, which is expensive for large rasters.
+/// With the `rayon` feature, cells are processed on multiple threads.
+pub fn insolation(raster: &Raster<f32>, days: RangeInclusive<u32>, latitude: f64) -> Raster<f32> {
+    let sun = sun_positions(days, latitude.to_radians());
+    let slope = raster.slope(GradientMethod::Horn);
+    let aspect = raster.aspect(GradientMethod::Horn);
+
+    raster.focal(0, |w| {
+        let (row, col) = w.position();
+        let slope = (slope.get(row, col)? as f64).to_radians();
+        let aspect = aspect.get(row, col).unwrap_or(0.0) as f64;
+        let horizon = horizon_angles(raster, row, col);
+
+        let mut energy = 0.0;
+        for position in &sun {
+            let air_mass = 1.0 / position.elevation.sin().max(0.05);
+            let beam = SOLAR_CONSTANT * TRANSMITTANCE.powf(air_mass);
+            // Diffuse radiation on a horizontal surface, after Liu and Jordan.
+            let diffuse = 0.3 * (SOLAR_CONSTANT - beam) * position.elevation.sin();
+            let sky_view = (1.0 + slope.cos()) / 2.0;
+            energy += diffuse * sky_view * position.hours;
+
+            let sector = ((position.azimuth / (2.0 * PI) * HORIZON_SECTORS as f64).round()
+                as usize)
+                % HORIZON_SECTORS;
+            if horizon[sector] >= position.elevation {
+                continue;
+            }
+
+            let incidence = slope.cos() * position.elevation.sin()
+                + slope.sin()
+                    * position.elevation.cos()
+                    * (position.azimuth - aspect.to_radians()).cos();
+            energy += beam * incidence.max(0.0) * position.hours;
+        }
+
+        Some((energy / 1000.0) as f32)
+    })
+}
+
+/// Returns positions of the sun above the horizon during the days.
+fn sun_positions(days: RangeInclusive<u32>, latitude: f64) -> Vec<SunPosition> {
+    let count = days.end().saturating_sub(*days.start()) + 1;
+    let step = count.div_ceil(MAX_SAMPLED_DAYS).max(1);
+
+    let mut positions = vec![];
+    for day in days.clone().step_by(step as usize) {
+        // Sampled day stands for the following `step` days, but not past the end.
+        let weight = step.min(days.end() + 1 - day) as f64;
+        let declination = (23.44f64).to_radians() * (2.0 * PI * (284.0 + day as f64) / 365.0).sin();
+
+        let mut hour = -12.0 + TIME_STEP / 2.0;
+        while hour < 12.0 {
+            let hour_angle = (15.0 * hour).to_radians();
+            let sin_elevation = latitude.sin() * declination.sin()
+                + latitude.cos() * declination.cos() * hour_angle.cos();
+            let elevation = sin_elevation.asin();
+            if elevation > 0.0 {
+                let cos_azimuth = (declination.sin() - sin_elevation * latitude.sin())
+                    / (elevation.cos() * latitude.cos());
+                let azimuth = cos_azimuth.clamp(-1.0, 1.0).acos();
+                positions.push(SunPosition {
+                    elevation,
+                    // Sun is in the east before noon.
+                    azimuth: if hour < 0.0 {
+                        azimuth
+                    } else {
+                        2.0 * PI - azimuth
+                    },
+                    hours: TIME_STEP * weight,
+                });
+            }
+            hour += TIME_STEP;
+        }
+    }
+
+    positions
+}
+
+/// Returns the angle of the horizon above the cell in radians,
+/// for every sector clockwise from north.
+fn horizon_angles(raster: &Raster<f32>, row: usize, col: usize) -> [f64; HORIZON_SECTORS] {
+    let mut horizon = [0.0; HORIZON_SECTORS];
+    let Some(z0) = raster.get(row, col) else {
+        return horizon;
+    };
+    let center = raster.cell_center(row, col);
+
+    for (sector, angle) in horizon.iter_mut().enumerate() {
+        let azimuth = sector as f64 / HORIZON_SECTORS as f64 * 2.0 * PI;
+        let (dx, dy) = (azimuth.sin(), azimuth.cos());
+
+        let mut distance = raster.cell_size();
+        let mut step = raster.cell_size();
+        loop {
+            let (x, y) = (center.x + dx * distance, center.y + dy * distance);
+            let Some((r, c)) = raster.cell_at(x, y) else {
+                break;
+            };
+            if let Some(z) = raster.get(r, c) {
+                *angle = f64::max(*angle, ((z - z0) as f64 / distance).atan());
+            }
+
+            distance += step;
+            step *= HORIZON_STEP_GROWTH;
+        }
+    }
+
+    horizon
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::insolation;
+
+    #[test]
+    fn south_slopes_get_more_sun_in_winter() {
+        // Valley running east to west, with slopes rising 1 m per meter.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 100.0,
+                data: (),
+            },
+            5.0,
+            10,
+            21,
+            0.0,
+        );
+        for row in 0..21 {
+            for col in 0..10 {
+                raster.set(row, col, 5.0 * (row as f32 - 10.0).abs());
+            }
+        }
+
+        let winter = insolation(&raster, 350..=360, 46.0);
+        // Northern side of the valley faces south.
+        let south_slope = winter.get(5, 5).unwrap();
+        let north_slope = winter.get(15, 5).unwrap();
+        assert!(
+            south_slope > 2.0 * north_slope,
+            "{south_slope} {north_slope}"
+        );
+
+        let flat = Raster::new(raster.origin().clone(), 5.0, 3, 3, 0.0);
+        let summer = insolation(&flat, 172..=172, 46.0).get(1, 1).unwrap();
+        let winter = insolation(&flat, 355..=355, 46.0).get(1, 1).unwrap();
+        // Around 8 kWh/m² on a clear summer day and a quarter of that in winter.
+        assert!(summer > 6.0 && summer < 10.0, "{summer}");
+        assert!(winter < summer / 2.5, "{winter}");
+    }
+}
@@ -0,0 +1,100 @@
+//! Areas flooded by water rising to a level.
+
+use std::collections::VecDeque;
+
+use crate::{
+    Point,
+    grid::{NODATA, Raster},
+};
+
+/// Computes the area flooded when water rises to `water_level` meters above the sea.
+///
+/// Water spreads from the seed points, for example from points on the river, to all
+/// connected cells lower than the water level, also diagonally. Basins not connected
+/// to any seed stay dry, even if they are below the level. Seeds outside of the
+/// raster or above the water level are ignored.
+///
+/// Returned raster contains the depth of water in meters, and [`NODATA`] for dry cells.
+pub fn inundation<T>(raster: &Raster<f32>, water_level: f32, seeds: &[Point<T>]) -> Raster<f32> {
+    let mut depth = raster.same_extent(NODATA).with_nodata(Some(NODATA));
+    let mut queue = VecDeque::new();
+
+    let flood = |row: usize, col: usize, depth: &mut Raster<f32>| {
+        let Some(z) = raster.get(row, col) else {
+            return false;
+        };
+        if z >= water_level || depth.get(row, col).is_some() {
+            return false;
+        }
+
+        depth.set(row, col, water_level - z);
+        true
+    };
+
+    for seed in seeds {
+        if let Some((row, col)) = raster.cell_at(seed.x, seed.y)
+            && flood(row, col, &mut depth)
+        {
+            queue.push_back((row, col));
+        }
+    }
+
+    while let Some((row, col)) = queue.pop_front() {
+        for dr in -1..=1 {
+            for dc in -1..=1 {
+                let (Some(r), Some(c)) = (row.checked_add_signed(dr), col.checked_add_signed(dc))
+                else {
+                    continue;
+                };
+                if flood(r, c, &mut depth) {
+                    queue.push_back((r, c));
+                }
+            }
+        }
+    }
+
+    depth
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::inundation;
+
+    #[test]
+    fn inundation_floods_connected_cells() {
+        // River at 200 m, a 210 m levee and a basin behind it at 201 m.
+        let heights = [200.0, 202.0, 210.0, 201.0, 205.0];
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            10.0,
+            heights.len(),
+            1,
+            0.0,
+        );
+        for (col, height) in heights.into_iter().enumerate() {
+            raster.set(0, col, height);
+        }
+        let river = [Point {
+            x: 0.0,
+            y: 0.0,
+            data: (),
+        }];
+
+        let flooded = inundation(&raster, 203.0, &river);
+        assert_eq!(flooded.get(0, 0), Some(3.0));
+        assert_eq!(flooded.get(0, 1), Some(1.0));
+        assert_eq!(flooded.get(0, 2), None);
+        // Basin behind the levee stays dry.
+        assert_eq!(flooded.get(0, 3), None);
+
+        let flooded = inundation(&raster, 211.0, &river);
+        assert_eq!(flooded.get(0, 3), Some(10.0));
+        assert_eq!(flooded.get(0, 4), Some(6.0));
+    }
+}
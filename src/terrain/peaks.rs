@@ -0,0 +1,187 @@
+//! Summits of the terrain with their prominence and isolation.
+
+use crate::{Point, grid::Raster};
+
+/// Summit found in the terrain.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Peak {
+    /// Center of the summit cell, with its height as data.
+    pub location: Point<f32>,
+    /// Height of the summit above its key col in meters.
+    /// The highest peak of the raster has its full height as prominence.
+    pub prominence: f32,
+    /// The highest col connecting the summit to higher terrain, with its height as data.
+    /// None for the highest peak.
+    pub key_col: Option<Point<f32>>,
+    /// Distance to the nearest higher terrain in meters. None for the highest peak.
+    pub isolation: Option<f64>,
+}
+
+/// Finds summits with at least `min_prominence` meters of prominence.
+///
+/// Cells are flooded from the highest down, joining the areas around summits.
+/// When two areas meet, the lower summit gets the height of the meeting cell as its
+/// key col. Only terrain within the raster is known, so summits near the edges
+/// can have smaller prominence and isolation than in reality.
+///
+/// Peaks are returned from the highest to the lowest.
+pub fn find_peaks(raster: &Raster<f32>, min_prominence: f32) -> Vec<Peak> {
+    let ncols = raster.ncols();
+
+    let mut cells: Vec<_> = (0..raster.nrows() * ncols)
+        .filter_map(|i| Some((i, raster.get(i / ncols, i % ncols)?)))
+        .collect();
+    cells.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    // Union-find over flooded cells, with the highest summit of every area at its root.
+    let mut parent = vec![usize::MAX; raster.nrows() * ncols];
+    let mut summit = vec![0; raster.nrows() * ncols];
+    let mut cols = vec![];
+
+    for &(i, height) in &cells {
+        parent[i] = i;
+        summit[i] = i;
+
+        let (row, col) = (i / ncols, i % ncols);
+        for (dr, dc) in NEIGHBOURS {
+            let (Some(r), Some(c)) = (row.checked_add_signed(dr), col.checked_add_signed(dc))
+            else {
+                continue;
+            };
+            if r >= raster.nrows() || c >= ncols || parent[r * ncols + c] == usize::MAX {
+                continue;
+            }
+
+            let (a, b) = (find(&mut parent, i), find(&mut parent, r * ncols + c));
+            if a == b {
+                continue;
+            }
+
+            let (high, low) = if height_of(raster, summit[a]) >= height_of(raster, summit[b]) {
+                (a, b)
+            } else {
+                (b, a)
+            };
+            // Cell joining the areas is the key col of the lower summit.
+            if height_of(raster, summit[low]) > height {
+                cols.push((summit[low], i));
+            }
+            parent[low] = high;
+        }
+    }
+
+    let mut peaks = vec![];
+    if let Some(&(highest, height)) = cells.first()
+        && height >= min_prominence
+    {
+        peaks.push(Peak {
+            location: point(raster, highest),
+            prominence: height,
+            key_col: None,
+            isolation: None,
+        });
+    }
+
+    for (top, col) in cols {
+        let prominence = height_of(raster, top) - height_of(raster, col);
+        if prominence <= 0.0 || prominence < min_prominence {
+            continue;
+        }
+
+        let location = point(raster, top);
+        // Cells are sorted by height, so higher terrain is at the start.
+        let isolation = cells
+            .iter()
+            .take_while(|&&(_, h)| h > location.data)
+            .map(|&(i, _)| location.distance(&point(raster, i)))
+            .min_by(f64::total_cmp);
+        peaks.push(Peak {
+            key_col: Some(point(raster, col)),
+            location,
+            prominence,
+            isolation,
+        });
+    }
+
+    peaks.sort_by(|a, b| b.location.data.total_cmp(&a.location.data));
+    peaks
+}
+
+/// Row and column offsets of the neighbours.
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// Returns the root of the cell's area, compressing the path to it.
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+/// Returns height of the cell at the index, which must have data.
+fn height_of(raster: &Raster<f32>, i: usize) -> f32 {
+    raster.data()[i]
+}
+
+/// Returns center of the cell at the index, with its height as data.
+fn point(raster: &Raster<f32>, i: usize) -> Point<f32> {
+    let center = raster.cell_center(i / raster.ncols(), i % raster.ncols());
+    Point {
+        x: center.x,
+        y: center.y,
+        data: height_of(raster, i),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::find_peaks;
+
+    #[test]
+    fn peaks_with_prominence() {
+        // Two summits along a ridge, 100 m and 80 m high, with a 50 m col between them.
+        let heights = [0.0, 100.0, 60.0, 50.0, 60.0, 80.0, 0.0, 10.0, 0.0];
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 0.0,
+                data: (),
+            },
+            10.0,
+            heights.len(),
+            1,
+            0.0,
+        );
+        for (col, height) in heights.into_iter().enumerate() {
+            raster.set(0, col, height);
+        }
+
+        let peaks = find_peaks(&raster, 20.0);
+        assert_eq!(peaks.len(), 2);
+
+        assert_eq!(peaks[0].location.x, 10.0);
+        assert_eq!(peaks[0].prominence, 100.0);
+        assert_eq!(peaks[0].isolation, None);
+
+        assert_eq!(peaks[1].location.x, 50.0);
+        assert_eq!(peaks[1].prominence, 30.0);
+        assert_eq!(peaks[1].key_col.as_ref().unwrap().x, 30.0);
+        assert_eq!(peaks[1].isolation, Some(40.0));
+
+        // Small bump is included with lower threshold.
+        assert_eq!(find_peaks(&raster, 5.0).len(), 3);
+    }
+}
@@ -0,0 +1,410 @@
+//! Least-cost paths over the terrain.
+
+use std::{cmp::Ordering, collections::BinaryHeap, f64::consts::SQRT_2};
+
+use crate::{
+    Point,
+    grid::{LineString, NODATA, Raster, contours_at},
+};
+
+/// Cost of moving between neighbouring cells.
+///
+/// Implemented for closures `Fn(distance, rise) -> Option<f64>`, which
+/// return None for impassable moves.
+pub trait PathCost {
+    /// Returns the cost of moving `distance` meters horizontally while climbing
+    /// `rise` meters, which is negative downhill. None if the move is impassable.
+    fn cost(&self, distance: f64, rise: f64) -> Option<f64>;
+
+    /// Returns the lowest possible cost of a meter, which guides the search towards the end.
+    ///
+    /// Higher values make the search faster, but it must not exceed the actual
+    /// cost of any move, otherwise the found path might not be the cheapest.
+    fn min_cost_per_meter(&self) -> f64 {
+        0.0
+    }
+}
+
+impl<F> PathCost for F
+where
+    F: Fn(f64, f64) -> Option<f64>,
+{
+    fn cost(&self, distance: f64, rise: f64) -> Option<f64> {
+        self(distance, rise)
+    }
+}
+
+/// Walking time in seconds, estimated with Tobler's hiking function.
+///
+/// Walking is the fastest, at 6 km/h, on a gentle downhill grade of 5 %,
+/// and slows down exponentially on steeper slopes in both directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct HikingTime;
+
+impl HikingTime {
+    /// The highest walking speed in meters per second.
+    const MAX_SPEED: f64 = 6.0 / 3.6;
+}
+
+impl PathCost for HikingTime {
+    fn cost(&self, distance: f64, rise: f64) -> Option<f64> {
+        let speed = Self::MAX_SPEED * (-3.5 * (rise / distance + 0.05).abs()).exp();
+        Some(distance.hypot(rise) / speed)
+    }
+
+    fn min_cost_per_meter(&self) -> f64 {
+        1.0 / Self::MAX_SPEED
+    }
+}
+
+/// The cheapest path between two locations.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CostPath {
+    /// Centers of the visited cells from the start to the end, with their heights as data.
+    pub path: Vec<Point<f32>>,
+    /// Total cost of the path.
+    pub cost: f64,
+}
+
+/// Finds the cheapest path between the cells containing the start and the end.
+///
+/// Path moves between the eight neighbouring cells and is searched with the A*
+/// algorithm. Use [`HikingTime`] for a slope-aware cost, or a closure for
+/// a custom one, e.g. forbidding slopes steeper than a skid trail can handle.
+///
+/// None is returned if any of the locations is outside of the raster or without data,
+/// or if there is no passable path between them.
+pub fn least_cost_path<T, U, C>(
+    raster: &Raster<f32>,
+    start: &Point<T>,
+    end: &Point<U>,
+    cost: C,
+) -> Option<CostPath>
+where
+    C: PathCost,
+{
+    let ncols = raster.ncols();
+    let (start_row, start_col) = raster.cell_at(start.x, start.y)?;
+    let (end_row, end_col) = raster.cell_at(end.x, end.y)?;
+    raster.get(start_row, start_col)?;
+    raster.get(end_row, end_col)?;
+
+    let start = start_row * ncols + start_col;
+    let end = end_row * ncols + end_col;
+    let (costs, previous) = search(raster, start, Some(end), f64::INFINITY, &cost);
+    if costs[end].is_infinite() {
+        return None;
+    }
+
+    let mut path = vec![];
+    let mut index = end;
+    loop {
+        let (row, col) = (index / ncols, index % ncols);
+        let center = raster.cell_center(row, col);
+        path.push(Point {
+            x: center.x,
+            y: center.y,
+            data: raster.get(row, col)?,
+        });
+        if index == start {
+            break;
+        }
+        index = previous[index];
+    }
+    path.reverse();
+
+    Some(CostPath {
+        path,
+        cost: costs[end],
+    })
+}
+
+/// Computes the cost of reaching every cell from the start, up to `max_cost`.
+///
+/// For example, with [`HikingTime`] and `max_cost` of 7200, the result shows where
+/// a hiker could be after two hours. Moves are the same as in [`least_cost_path`].
+/// Cells which can't be reached within the budget have [`NODATA`], so the
+/// reachable region is the cells with data. See [`isochrone_outline`] for its outline.
+///
+/// None is returned if the start is outside of the raster or without data.
+pub fn isochrone<T, C>(
+    raster: &Raster<f32>,
+    start: &Point<T>,
+    max_cost: f64,
+    cost: C,
+) -> Option<Raster<f32>>
+where
+    C: PathCost,
+{
+    let (row, col) = raster.cell_at(start.x, start.y)?;
+    raster.get(row, col)?;
+
+    let (costs, _) = search(raster, row * raster.ncols() + col, None, max_cost, &cost);
+
+    let mut result = raster.same_extent(NODATA).with_nodata(Some(NODATA));
+    for (i, cost) in costs.into_iter().enumerate() {
+        if cost <= max_cost {
+            result.set(i / raster.ncols(), i % raster.ncols(), cost as f32);
+        }
+    }
+    Some(result)
+}
+
+/// Computes the outline of the region reachable from the start within `max_cost`.
+///
+/// Outline is the line of [`isochrone`] costs equal to the budget, interpolated
+/// between the cells. All lines are closed: outer ones around the reachable region,
+/// and inner ones around unreachable holes, like walls or cliffs. Cells without data
+/// and the outside of the raster are unreachable.
+///
+/// None is returned if the start is outside of the raster or without data.
+pub fn isochrone_outline<T, C>(
+    raster: &Raster<f32>,
+    start: &Point<T>,
+    max_cost: f64,
+    cost: C,
+) -> Option<Vec<LineString>>
+where
+    C: PathCost,
+{
+    let (row, col) = raster.cell_at(start.x, start.y)?;
+    raster.get(row, col)?;
+
+    // Cells just beyond the budget keep their costs from the search, which places
+    // the outline between them and the reachable cells.
+    let (costs, _) = search(raster, row * raster.ncols() + col, None, max_cost, &cost);
+    let unreachable = (2.0 * max_cost).max(max_cost + 1.0);
+
+    // Border of unreachable cells around the raster closes the lines at its edges.
+    let cell_size = raster.cell_size();
+    let origin = Point {
+        x: raster.origin().x - cell_size,
+        y: raster.origin().y + cell_size,
+        data: (),
+    };
+    let mut padded = Raster::new(
+        origin,
+        cell_size,
+        raster.ncols() + 2,
+        raster.nrows() + 2,
+        unreachable as f32,
+    );
+    for (i, cost) in costs.into_iter().enumerate() {
+        let (row, col) = (i / raster.ncols(), i % raster.ncols());
+        padded.set(row + 1, col + 1, cost.min(unreachable) as f32);
+    }
+
+    Some(contours_at(&padded, max_cost))
+}
+
+/// Searches the cheapest paths from the start with the A* algorithm,
+/// returning costs of the cells and the previous cells on their paths.
+///
+/// Search stops once the end is reached, or when no cell is cheaper than `max_cost`.
+fn search<C>(
+    raster: &Raster<f32>,
+    start: usize,
+    end: Option<usize>,
+    max_cost: f64,
+    cost: &C,
+) -> (Vec<f64>, Vec<usize>)
+where
+    C: PathCost,
+{
+    let ncols = raster.ncols();
+    let target = end.map(|end| raster.cell_center(end / ncols, end % ncols));
+    let estimate = |row: usize, col: usize| match &target {
+        Some(target) => raster.cell_center(row, col).distance(target) * cost.min_cost_per_meter(),
+        None => 0.0,
+    };
+
+    let mut costs = vec![f64::INFINITY; raster.nrows() * ncols];
+    let mut previous = vec![usize::MAX; raster.nrows() * ncols];
+    let mut queue = BinaryHeap::new();
+
+    costs[start] = 0.0;
+    queue.push(Visit {
+        estimate: estimate(start / ncols, start % ncols),
+        cost: 0.0,
+        index: start,
+    });
+
+    while let Some(visit) = queue.pop() {
+        if Some(visit.index) == end {
+            break;
+        }
+        if visit.cost > costs[visit.index] {
+            continue;
+        }
+
+        let (row, col) = (visit.index / ncols, visit.index % ncols);
+        let Some(z) = raster.get(row, col) else {
+            continue;
+        };
+        for (dr, dc) in NEIGHBOURS {
+            let (Some(r), Some(c)) = (row.checked_add_signed(dr), col.checked_add_signed(dc))
+            else {
+                continue;
+            };
+            let Some(next_z) = raster.get(r, c) else {
+                continue;
+            };
+
+            let distance = if dr != 0 && dc != 0 {
+                raster.cell_size() * SQRT_2
+            } else {
+                raster.cell_size()
+            };
+            let Some(step) = cost.cost(distance, (next_z - z) as f64) else {
+                continue;
+            };
+
+            let next = r * ncols + c;
+            let next_cost = visit.cost + step;
+            if next_cost < costs[next] && next_cost <= max_cost {
+                costs[next] = next_cost;
+                previous[next] = visit.index;
+                queue.push(Visit {
+                    estimate: next_cost + estimate(r, c),
+                    cost: next_cost,
+                    index: next,
+                });
+            }
+        }
+    }
+
+    (costs, previous)
+}
+
+/// Row and column offsets of the neighbours.
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+];
+
+/// Cell in the priority queue of the search, ordered so that the lowest estimate is popped first.
+struct Visit {
+    estimate: f64,
+    cost: f64,
+    index: usize,
+}
+
+impl PartialEq for Visit {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Visit {}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Point, grid::Raster};
+
+    use super::{HikingTime, isochrone, isochrone_outline, least_cost_path};
+
+    #[test]
+    fn path_goes_through_the_gap() {
+        // Flat terrain with a 100 m high wall in the middle column, except in the last row.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 40.0,
+                data: (),
+            },
+            10.0,
+            5,
+            5,
+            0.0,
+        );
+        for row in 0..4 {
+            raster.set(row, 2, 100.0);
+        }
+        let start = Point {
+            x: 0.0,
+            y: 40.0,
+            data: (),
+        };
+        let end = Point {
+            x: 40.0,
+            y: 40.0,
+            data: (),
+        };
+
+        let gentle = |distance: f64, rise: f64| (rise.abs() < 10.0).then_some(distance);
+        let result = least_cost_path(&raster, &start, &end, gentle).unwrap();
+        assert!(result.path.iter().any(|p| p.x == 20.0 && p.y == 0.0));
+        assert!((result.cost - (40.0 + 4.0 * 10.0 * 2f64.sqrt())).abs() < 1e-9);
+
+        // Hikers also rather walk around than climb the wall.
+        let hike = least_cost_path(&raster, &start, &end, HikingTime).unwrap();
+        assert!(hike.path.iter().any(|p| p.x == 20.0 && p.y == 0.0));
+        assert_eq!(hike.path.first().unwrap().x, 0.0);
+        assert_eq!(hike.path.last().unwrap().x, 40.0);
+
+        let blocked = |distance: f64, rise: f64| (rise <= 0.0).then_some(distance);
+        raster.set(4, 2, 100.0);
+        assert_eq!(least_cost_path(&raster, &start, &end, blocked), None);
+    }
+
+    #[test]
+    fn isochrone_stops_at_the_wall() {
+        // Flat terrain with a 100 m high wall in the middle column.
+        let mut raster = Raster::new(
+            Point {
+                x: 0.0,
+                y: 40.0,
+                data: (),
+            },
+            10.0,
+            5,
+            5,
+            0.0,
+        );
+        for row in 0..5 {
+            raster.set(row, 2, 100.0);
+        }
+        let start = Point {
+            x: 0.0,
+            y: 40.0,
+            data: (),
+        };
+
+        // Walking on flat ground takes 0.72 s/m, so the wall is out of reach.
+        let reach = isochrone(&raster, &start, 45.0, HikingTime).unwrap();
+        assert!(reach.get(0, 1).is_some_and(|t| (t - 7.15).abs() < 0.01));
+        assert!(reach.get(4, 0).is_some());
+        assert_eq!(reach.get(0, 2), None);
+        assert_eq!(reach.get(4, 4), None);
+
+        // Single closed line around the two columns west of the wall.
+        let outline = isochrone_outline(&raster, &start, 45.0, HikingTime).unwrap();
+        assert_eq!(outline.len(), 1);
+        assert!(outline[0].is_closed());
+        let xs = outline[0].points.iter().map(|p| p.x);
+        let (min_x, max_x) = xs.fold((f64::MAX, f64::MIN), |(min, max), x| {
+            (min.min(x), max.max(x))
+        });
+        assert!(min_x > -10.0 && min_x < 0.0);
+        assert!(max_x > 10.0 && max_x < 20.0);
+    }
+}
@@ -0,0 +1,207 @@
+//! Terrain backed by a directory of tiles, which are loaded on demand.
+
+use std::{
+    collections::HashMap,
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use crate::{Area, Point, data::TileStore, interpolate::IdwSum, qtree::QuadTree};
+
+use super::{NEIGHBOURS, Terrain};
+
+/// Terrain model that loads the tiles written by
+/// [`import_data_tiled`](crate::data::import_data_tiled) when they are needed.
+///
+/// Loaded tiles are kept in a cache, until the memory budget is exceeded and
+/// the least recently used tiles are evicted. Queries transparently combine
+/// points of neighbouring tiles. Store can be shared between threads.
+pub struct TerrainStore {
+    tiles: TileStore,
+    memory_budget: usize,
+    max_distance: f64,
+    cache: Mutex<Cache>,
+}
+
+#[derive(Default)]
+struct Cache {
+    tiles: HashMap<usize, CachedTile>,
+    bytes: usize,
+    clock: u64,
+}
+
+struct CachedTile {
+    tree: Arc<QuadTree<f32>>,
+    bytes: usize,
+    last_used: u64,
+}
+
+impl TerrainStore {
+    /// Default memory budget of the cache, 256 MiB.
+    pub const DEFAULT_MEMORY_BUDGET: usize = 256 << 20;
+
+    /// Opens the directory of tiles.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, io::Error> {
+        Ok(Self {
+            tiles: TileStore::open(dir)?,
+            memory_budget: Self::DEFAULT_MEMORY_BUDGET,
+            max_distance: Terrain::DEFAULT_MAX_DISTANCE,
+            cache: Mutex::default(),
+        })
+    }
+
+    /// Sets the approximate maximum number of bytes used by the loaded tiles.
+    ///
+    /// The most recently used tile is always kept, even if it alone exceeds the budget.
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = bytes;
+        self
+    }
+
+    /// Sets the maximum distance of points used for the interpolation,
+    /// see [`Terrain::with_max_distance`].
+    pub fn with_max_distance(mut self, max_distance: f64) -> Self {
+        self.max_distance = max_distance;
+        self
+    }
+
+    /// Returns the approximate number of bytes used by the currently loaded tiles.
+    pub fn memory_usage(&self) -> usize {
+        self.cache.lock().unwrap().bytes
+    }
+
+    /// Returns the interpolated height at the given location in meters.
+    ///
+    /// Same as [`Terrain::elevation_at`], but loading the tiles can fail.
+    pub fn elevation_at(&self, x: f64, y: f64) -> Result<Option<f32>, io::Error> {
+        let point = Point { x, y, data: () };
+
+        let mut candidates = self.query(&Area::square(point.clone(), self.max_distance))?;
+        candidates.sort_by(|a, b| a.distance_sq(&point).total_cmp(&b.distance_sq(&point)));
+        candidates.truncate(NEIGHBOURS);
+
+        let mut sum = IdwSum::new(&point, 2.0, self.max_distance);
+        sum.extend(&candidates);
+        Ok(sum.value())
+    }
+
+    /// Returns all points inside the area, across all tiles it intersects.
+    pub fn query(&self, area: &Area) -> Result<Vec<Point<f32>>, io::Error> {
+        let indices: Vec<_> = self
+            .tiles
+            .tiles()
+            .enumerate()
+            .filter(|(_, tile)| tile.intersects(area))
+            .map(|(index, _)| index)
+            .collect();
+
+        let mut points = vec![];
+        for index in indices {
+            // Tree area is the bounding box of its points, which can be smaller than the tile.
+            let _ = self
+                .tile(index)?
+                .query_with(area, |p| points.push(p.clone()));
+        }
+
+        Ok(points)
+    }
+
+    /// Returns the tree of the tile, loading it if needed.
+    fn tile(&self, index: usize) -> Result<Arc<QuadTree<f32>>, io::Error> {
+        {
+            let mut cache = self.cache.lock().unwrap();
+            cache.clock += 1;
+            let clock = cache.clock;
+            if let Some(tile) = cache.tiles.get_mut(&index) {
+                tile.last_used = clock;
+                return Ok(tile.tree.clone());
+            }
+        }
+
+        // Tile is loaded without holding the lock, so that other queries are not blocked.
+        let tree = Arc::new(QuadTree::from_points(self.tiles.read_tile(index)?));
+        let bytes = tree.stats().heap_bytes;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.clock += 1;
+        let tile = CachedTile {
+            tree: tree.clone(),
+            bytes,
+            last_used: cache.clock,
+        };
+        if let Some(old) = cache.tiles.insert(index, tile) {
+            // Another thread loaded the same tile in the meantime.
+            cache.bytes -= old.bytes;
+        }
+        cache.bytes += bytes;
+
+        while cache.bytes > self.memory_budget && cache.tiles.len() > 1 {
+            let lru = cache
+                .tiles
+                .iter()
+                .min_by_key(|(_, tile)| tile.last_used)
+                .map(|(index, _)| *index)
+                .unwrap();
+            let evicted = cache.tiles.remove(&lru).unwrap();
+            cache.bytes -= evicted.bytes;
+        }
+
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point, Terrain,
+        data::{ImportOptions, import_data_tiled},
+        qtree::QuadTree,
+    };
+
+    use super::TerrainStore;
+
+    #[test]
+    fn elevation_across_tiles() {
+        let dir = std::env::temp_dir().join(format!("hribovje-store-{}", std::process::id()));
+        let input = dir.join("input");
+        let output = dir.join("tiles");
+        std::fs::create_dir_all(&input).unwrap();
+
+        // 5 m grid of a plane, split into 50 m tiles.
+        let mut data = String::new();
+        let mut points = vec![];
+        for i in 0..400 {
+            let (x, y) = ((i % 20) as f64 * 5.0, (i / 20) as f64 * 5.0);
+            data.push_str(&format!("{x} {y} {}\n", x + y));
+            points.push(Point {
+                x,
+                y,
+                data: (x + y) as f32,
+            });
+        }
+        std::fs::write(input.join("a.xyz"), data).unwrap();
+        import_data_tiled(&input, &output, 50.0, &ImportOptions::default(), |_| ()).unwrap();
+
+        let store = TerrainStore::open(&output).unwrap();
+        // Neighbours of the location are split between two tiles.
+        let terrain = Terrain::new(QuadTree::from_points(points));
+        assert_eq!(
+            store.elevation_at(48.0, 51.0).unwrap(),
+            terrain.elevation_at(48.0, 51.0)
+        );
+        assert_eq!(store.elevation_at(500.0, 500.0).unwrap(), None);
+
+        // Budget smaller than a single tile keeps only the last used tile.
+        let store = TerrainStore::open(&output).unwrap().with_memory_budget(1);
+        for (x, y) in [(10.0, 10.0), (60.0, 60.0)] {
+            store.elevation_at(x, y).unwrap();
+            let cache = store.cache.lock().unwrap();
+            assert_eq!(cache.tiles.len(), 1);
+            let tile = cache.tiles.values().next().unwrap();
+            assert!(tile.bytes > 0);
+            assert_eq!(cache.bytes, tile.tree.stats().heap_bytes);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,445 @@
+//! Web map tiles in the XYZ scheme, as used by slippy maps.
+//!
+//! Tiles are 256×256 pixels in Web Mercator projection ([EPSG:3857](https://epsg.io/3857)).
+//! Rasters are reprojected from D96/TM by sampling the nearest cell for every pixel.
+//! Tiles can be rendered as images with color ramps, or with heights encoded into the colors
+//! for client side 3D terrain, see [`ElevationEncoding`].
+
+use std::f64::consts::PI;
+
+use crate::{
+    Point,
+    grid::{
+        Raster,
+        render::{RenderOptions, Stretch},
+    },
+};
+
+/// Width and height of a tile in pixels.
+pub const TILE_SIZE: usize = 256;
+
+/// The error type of writing tiles.
+#[cfg(feature = "png")]
+#[derive(Debug, thiserror::Error)]
+pub enum TileError {
+    #[error("zoom level {0} is above the maximum of {max}", max = TileId::MAX_ZOOM)]
+    InvalidZoom(u8),
+
+    #[error("png error")]
+    Png(#[from] png::EncodingError),
+}
+
+/// Radius of the sphere used by Web Mercator in meters.
+const RADIUS: f64 = 6378137.0;
+
+/// Identifier of a tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TileId {
+    /// Zoom level, where zoom 0 has a single tile covering the whole world.
+    pub z: u8,
+    /// Column, counted from the west.
+    pub x: u32,
+    /// Row, counted from the north.
+    pub y: u32,
+}
+
+impl TileId {
+    /// Highest supported zoom level, which is beyond the zoom levels of web maps.
+    pub const MAX_ZOOM: u8 = 24;
+
+    /// Returns the tile containing the WGS84 location in degrees.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `z` is above [`TileId::MAX_ZOOM`].
+    pub fn containing(lat: f64, lon: f64, z: u8) -> Self {
+        assert!(
+            z <= Self::MAX_ZOOM,
+            "zoom level must be at most {}",
+            Self::MAX_ZOOM
+        );
+
+        let (x, y) = to_mercator(lat, lon);
+        let (x, y) = to_tile_fraction(x, y, z);
+        let max = (1u32 << z) - 1;
+
+        Self {
+            z,
+            x: (x.floor().max(0.0) as u32).min(max),
+            y: (y.floor().max(0.0) as u32).min(max),
+        }
+    }
+
+    /// Returns Web Mercator coordinates of the center of the pixel in meters.
+    fn pixel_center(&self, px: usize, py: usize) -> (f64, f64) {
+        let size = 2.0 * PI * RADIUS / (1u64 << self.z) as f64;
+        let pixel = size / TILE_SIZE as f64;
+
+        (
+            -PI * RADIUS + self.x as f64 * size + (px as f64 + 0.5) * pixel,
+            PI * RADIUS - self.y as f64 * size - (py as f64 + 0.5) * pixel,
+        )
+    }
+
+    /// Returns values of the pixels in row-major order, sampled from the nearest cell of the raster.
+    pub(crate) fn samples<'a, T>(
+        &'a self,
+        raster: &'a Raster<T>,
+    ) -> impl Iterator<Item = Option<T>> + 'a
+    where
+        T: Copy + PartialEq,
+    {
+        (0..TILE_SIZE * TILE_SIZE).map(move |i| {
+            let (x, y) = self.pixel_center(i % TILE_SIZE, i / TILE_SIZE);
+            let (lat, lon) = from_mercator(x, y);
+            let point = Point::from_wgs84(lat, lon);
+            raster.value_at(point.x, point.y)
+        })
+    }
+}
+
+/// Packing of heights into RGB colors of elevation tiles.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ElevationEncoding {
+    /// Mapbox Terrain-RGB: `height = -10000 + (R * 256 * 256 + G * 256 + B) * 0.1`.
+    TerrainRgb,
+    /// Terrarium: `height = (R * 256 + G + B / 256) - 32768`.
+    Terrarium,
+}
+
+impl ElevationEncoding {
+    /// Encodes the height in meters into RGB color.
+    pub fn encode(&self, height: f32) -> [u8; 3] {
+        match self {
+            ElevationEncoding::TerrainRgb => {
+                let v = ((height as f64 + 10000.0) * 10.0)
+                    .round()
+                    .clamp(0.0, 16777215.0) as u32;
+                [(v >> 16) as u8, (v >> 8) as u8, v as u8]
+            }
+            ElevationEncoding::Terrarium => {
+                let v = (height as f64 + 32768.0).clamp(0.0, 65535.996);
+                [
+                    (v / 256.0).floor() as u8,
+                    (v.floor() % 256.0) as u8,
+                    (v.fract() * 256.0).floor() as u8,
+                ]
+            }
+        }
+    }
+
+    /// Decodes the height in meters from RGB color.
+    pub fn decode(&self, [r, g, b]: [u8; 3]) -> f32 {
+        let (r, g, b) = (r as f64, g as f64, b as f64);
+        let height = match self {
+            ElevationEncoding::TerrainRgb => -10000.0 + (r * 65536.0 + g * 256.0 + b) * 0.1,
+            ElevationEncoding::Terrarium => r * 256.0 + g + b / 256.0 - 32768.0,
+        };
+
+        height as f32
+    }
+}
+
+/// Returns the tiles that cover the raster at the zoom level.
+///
+/// # Panics
+///
+/// Panics if `z` is above [`TileId::MAX_ZOOM`].
+pub fn tiles_covering<T>(raster: &Raster<T>, z: u8) -> Vec<TileId>
+where
+    T: Copy + PartialEq,
+{
+    if raster.ncols() == 0 || raster.nrows() == 0 {
+        return vec![];
+    }
+
+    // Straight lines of the projection are curved in Web Mercator, so points
+    // along the edges are checked as well as the corners.
+    let (last_row, last_col) = (raster.nrows() - 1, raster.ncols() - 1);
+    let edges = (0..=8).flat_map(|i| {
+        let (r, c) = (last_row * i / 8, last_col * i / 8);
+        [(0, c), (last_row, c), (r, 0), (r, last_col)]
+    });
+
+    let half = raster.cell_size() / 2.0;
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for (row, col) in edges {
+        let center = raster.cell_center(row, col);
+        for (dx, dy) in [(-half, -half), (-half, half), (half, -half), (half, half)] {
+            let (lat, lon) = Point {
+                x: center.x + dx,
+                y: center.y + dy,
+                data: (),
+            }
+            .to_wgs84();
+            let tile = TileId::containing(lat, lon, z);
+            (min_x, min_y) = (min_x.min(tile.x), min_y.min(tile.y));
+            (max_x, max_y) = (max_x.max(tile.x), max_y.max(tile.y));
+        }
+    }
+
+    (min_y..=max_y)
+        .flat_map(|y| (min_x..=max_x).map(move |x| TileId { z, x, y }))
+        .collect()
+}
+
+/// Renders the tile into RGBA pixels, with the colors of the options.
+///
+/// Values are stretched over the whole raster, so that neighbouring tiles match.
+/// Returns None if the tile doesn't contain any data.
+pub fn render_tile<T>(raster: &Raster<T>, tile: TileId, options: &RenderOptions) -> Option<Vec<u8>>
+where
+    T: Copy + PartialEq + Into<f64>,
+{
+    render_with(raster, tile, &Stretch::new(raster, options))
+}
+
+fn render_with<T>(raster: &Raster<T>, tile: TileId, stretch: &Stretch) -> Option<Vec<u8>>
+where
+    T: Copy + PartialEq + Into<f64>,
+{
+    let mut empty = true;
+    let pixels = tile
+        .samples(raster)
+        .flat_map(|value| {
+            empty &= value.is_none();
+            stretch.pixel(value.map(Into::into))
+        })
+        .collect();
+
+    (!empty).then_some(pixels)
+}
+
+/// Renders the tile with heights encoded into RGBA pixels.
+///
+/// Pixels without data are encoded as height 0, since clients
+/// ignore the alpha channel when decoding the heights.
+/// Returns None if the tile doesn't contain any data.
+pub fn render_elevation_tile(
+    raster: &Raster<f32>,
+    tile: TileId,
+    encoding: ElevationEncoding,
+) -> Option<Vec<u8>> {
+    let mut empty = true;
+    let pixels = tile
+        .samples(raster)
+        .flat_map(|value| {
+            empty &= value.is_none();
+            let [r, g, b] = encoding.encode(value.unwrap_or(0.0));
+            [r, g, b, 255]
+        })
+        .collect();
+
+    (!empty).then_some(pixels)
+}
+
+/// Renders elevation tiles of the zoom levels and writes them into `dir/{z}/{x}/{y}.png`.
+///
+/// Tiles without data are not written. Returns the number of written tiles.
+/// Error is returned before writing any tile if a zoom level is above [`TileId::MAX_ZOOM`].
+#[cfg(feature = "png")]
+pub fn write_elevation_tiles(
+    raster: &Raster<f32>,
+    zooms: std::ops::RangeInclusive<u8>,
+    dir: impl AsRef<std::path::Path>,
+    encoding: ElevationEncoding,
+) -> Result<usize, TileError> {
+    check_zooms(&zooms)?;
+
+    let mut written = 0;
+    for z in zooms {
+        for tile in tiles_covering(raster, z) {
+            let Some(pixels) = render_elevation_tile(raster, tile, encoding) else {
+                continue;
+            };
+
+            write_tile_png(dir.as_ref(), tile, &pixels)?;
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+/// Renders tiles of the zoom levels and writes them into `dir/{z}/{x}/{y}.png`.
+///
+/// Tiles without data are not written. Returns the number of written tiles.
+/// Error is returned before writing any tile if a zoom level is above [`TileId::MAX_ZOOM`].
+#[cfg(feature = "png")]
+pub fn write_tiles<T>(
+    raster: &Raster<T>,
+    zooms: std::ops::RangeInclusive<u8>,
+    dir: impl AsRef<std::path::Path>,
+    options: &RenderOptions,
+) -> Result<usize, TileError>
+where
+    T: Copy + PartialEq + Into<f64>,
+{
+    check_zooms(&zooms)?;
+
+    let stretch = Stretch::new(raster, options);
+
+    let mut written = 0;
+    for z in zooms {
+        for tile in tiles_covering(raster, z) {
+            let Some(pixels) = render_with(raster, tile, &stretch) else {
+                continue;
+            };
+
+            write_tile_png(dir.as_ref(), tile, &pixels)?;
+            written += 1;
+        }
+    }
+
+    Ok(written)
+}
+
+#[cfg(feature = "png")]
+fn check_zooms(zooms: &std::ops::RangeInclusive<u8>) -> Result<(), TileError> {
+    match zooms.clone().find(|&z| z > TileId::MAX_ZOOM) {
+        Some(z) => Err(TileError::InvalidZoom(z)),
+        None => Ok(()),
+    }
+}
+
+/// Writes RGBA pixels of the tile into `dir/{z}/{x}/{y}.png`.
+#[cfg(feature = "png")]
+fn write_tile_png(
+    dir: &std::path::Path,
+    tile: TileId,
+    pixels: &[u8],
+) -> Result<(), png::EncodingError> {
+    let dir = dir.join(tile.z.to_string()).join(tile.x.to_string());
+    std::fs::create_dir_all(&dir)?;
+    let file = std::fs::File::create(dir.join(format!("{}.png", tile.y)))?;
+
+    let mut encoder = png::Encoder::new(
+        std::io::BufWriter::new(file),
+        TILE_SIZE as u32,
+        TILE_SIZE as u32,
+    );
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(pixels)?;
+    writer.finish()
+}
+
+/// Projects WGS84 degrees to Web Mercator meters.
+fn to_mercator(lat: f64, lon: f64) -> (f64, f64) {
+    let lat = lat.clamp(-85.051_128_78, 85.051_128_78);
+    (
+        RADIUS * lon.to_radians(),
+        RADIUS * (PI / 4.0 + lat.to_radians() / 2.0).tan().ln(),
+    )
+}
+
+/// Converts Web Mercator meters to WGS84 `(lat, lon)` in degrees.
+fn from_mercator(x: f64, y: f64) -> (f64, f64) {
+    let lat = 2.0 * (y / RADIUS).exp().atan() - PI / 2.0;
+    (lat.to_degrees(), (x / RADIUS).to_degrees())
+}
+
+/// Converts Web Mercator meters to fractional tile coordinates.
+fn to_tile_fraction(x: f64, y: f64, z: u8) -> (f64, f64) {
+    let n = (1u64 << z) as f64;
+    (
+        (x / (PI * RADIUS) + 1.0) / 2.0 * n,
+        (1.0 - y / (PI * RADIUS)) / 2.0 * n,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Point,
+        grid::{Raster, render::RenderOptions},
+    };
+
+    use super::{ElevationEncoding, TileId, render_elevation_tile, render_tile, tiles_covering};
+
+    #[test]
+    fn elevation_encoding_roundtrip() {
+        let rgb = ElevationEncoding::TerrainRgb;
+        assert_eq!(rgb.encode(0.0), [1, 134, 160]);
+        assert_eq!(rgb.decode([1, 134, 160]), 0.0);
+
+        let terrarium = ElevationEncoding::Terrarium;
+        assert_eq!(terrarium.encode(0.0), [128, 0, 0]);
+        assert_eq!(terrarium.encode(2864.5), [139, 48, 128]);
+
+        for encoding in [rgb, terrarium] {
+            for height in [-12.3, 0.0, 295.7, 2864.0] {
+                let got = encoding.decode(encoding.encode(height));
+                assert!((got - height).abs() <= 0.05, "{encoding:?} {height} {got}");
+            }
+        }
+
+        let center = Point::from_wgs84(46.0511, 14.5051);
+        let raster = Raster::new(center, 10.0, 10, 10, 295.7f32);
+        let tile = TileId::containing(46.0511, 14.5051, 16);
+        let pixels = render_elevation_tile(&raster, tile, rgb).unwrap();
+        assert!(pixels.chunks(4).any(|p| p[..3] == rgb.encode(295.7)));
+        assert!(pixels.chunks(4).any(|p| p[..3] == rgb.encode(0.0)));
+    }
+
+    #[test]
+    fn tiles_of_raster() {
+        // Ljubljana is in tile 8852/5825 at zoom 14.
+        assert_eq!(
+            TileId::containing(46.0511, 14.5051, 14),
+            TileId {
+                z: 14,
+                x: 8852,
+                y: 5825
+            }
+        );
+        assert_eq!(TileId::containing(0.0, 0.0, 0), TileId { z: 0, x: 0, y: 0 });
+        // South east corner of the world is the last tile of the highest zoom.
+        let max = TileId::containing(-90.0, 180.0, TileId::MAX_ZOOM);
+        assert_eq!((max.x, max.y), ((1 << 24) - 1, (1 << 24) - 1));
+
+        // 1 km square with the center in Ljubljana.
+        let center = Point::from_wgs84(46.0511, 14.5051);
+        let raster = Raster::new(
+            Point {
+                x: center.x - 500.0,
+                y: center.y + 500.0,
+                data: (),
+            },
+            10.0,
+            101,
+            101,
+            300.0f32,
+        );
+
+        let tiles = tiles_covering(&raster, 14);
+        assert_eq!(tiles.len(), 2, "{tiles:?}");
+        assert!(tiles.contains(&TileId::containing(46.0511, 14.5051, 14)));
+        assert_eq!(tiles_covering(&raster, 0), [TileId { z: 0, x: 0, y: 0 }]);
+
+        let pixels = render_tile(
+            &raster,
+            TileId::containing(46.0511, 14.5051, 14),
+            &RenderOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(pixels.len(), 256 * 256 * 4);
+        assert!(pixels.chunks(4).any(|p| p[3] == 255));
+        assert!(pixels.chunks(4).any(|p| p[3] == 0));
+
+        let far = TileId::containing(40.0, 10.0, 14);
+        assert_eq!(render_tile(&raster, far, &RenderOptions::default()), None);
+
+        #[cfg(feature = "png")]
+        {
+            let dir = std::env::temp_dir().join(format!("hribovje-tiles-{}", std::process::id()));
+            let options = RenderOptions::default();
+            let err = super::write_tiles(&raster, 14..=30, &dir, &options).unwrap_err();
+            assert!(matches!(err, super::TileError::InvalidZoom(25)));
+            assert!(!dir.exists());
+        }
+    }
+}
@@ -0,0 +1,388 @@
+//! Triangulated irregular network, built with Delaunay triangulation.
+
+use crate::Point;
+
+/// Marks a missing neighbour.
+const NONE: u32 = u32::MAX;
+
+/// Points closer than this are considered duplicates.
+const EPSILON: f64 = 1e-6;
+
+/// Triangulated irregular network (TIN).
+///
+/// Points are triangulated with the Bowyer-Watson algorithm, which produces
+/// the Delaunay triangulation. Heights between the points are interpolated
+/// linearly over the triangles, which represents the surface more accurately
+/// than the nearest points, especially for irregularly spaced points,
+/// such as LiDAR ground points.
+pub struct Tin {
+    points: Vec<Point<f32>>,
+    /// Indices of the points in counter clockwise order.
+    triangles: Vec<[u32; 3]>,
+    /// Index of the neighbouring triangle across the edge opposite to each vertex.
+    neighbours: Vec<[u32; 3]>,
+}
+
+impl Tin {
+    /// Triangulates the points, which contain height as data.
+    ///
+    /// Duplicated points are kept in [`Tin::points`], but only the first
+    /// of them is used in the triangulation.
+    pub fn new(points: Vec<Point<f32>>) -> Self {
+        let mut builder = Builder::new(&points);
+
+        // Inserting points in spatial order keeps the walks to the containing triangle short.
+        let mut order: Vec<_> = (0..points.len() as u32).collect();
+        order.sort_by_key(|&i| builder.z_order(i));
+        for i in order {
+            builder.insert(i);
+        }
+
+        let (triangles, neighbours) = builder.finish();
+        Self {
+            points,
+            triangles,
+            neighbours,
+        }
+    }
+
+    pub fn points(&self) -> &[Point<f32>] {
+        &self.points
+    }
+
+    /// Returns triangles as indices into [`Tin::points`], in counter clockwise order.
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    /// Returns the height interpolated over the triangle containing the location.
+    ///
+    /// None is returned if the location is outside of the convex hull of the points.
+    pub fn elevation_at(&self, x: f64, y: f64) -> Option<f32> {
+        let p = (x, y);
+        let coords = |i: u32| {
+            let p = &self.points[i as usize];
+            (p.x, p.y)
+        };
+        let t = locate(&self.triangles, &self.neighbours, coords, 0, p)?;
+
+        let [a, b, c] = self.triangles[t as usize];
+        let (pa, pb, pc) = (coords(a), coords(b), coords(c));
+        let area = orient(pa, pb, pc);
+        let wa = orient(pb, pc, p) / area;
+        let wb = orient(pc, pa, p) / area;
+        let wc = 1.0 - wa - wb;
+
+        let height = |i: u32| self.points[i as usize].data as f64;
+        Some((wa * height(a) + wb * height(b) + wc * height(c)) as f32)
+    }
+}
+
+/// Incremental Bowyer-Watson triangulation.
+///
+/// Triangulation starts with a super triangle containing all points,
+/// whose vertices are appended after the points.
+struct Builder {
+    /// Coordinates relative to the center of the points, to reduce rounding errors.
+    coords: Vec<(f64, f64)>,
+    point_count: u32,
+    triangles: Vec<[u32; 3]>,
+    neighbours: Vec<[u32; 3]>,
+    alive: Vec<bool>,
+    /// Number of the insertion, in which the triangle was added to the cavity.
+    marks: Vec<u32>,
+    insertion: u32,
+    last: u32,
+    bounds: (f64, f64, f64, f64),
+}
+
+impl Builder {
+    fn new(points: &[Point<f32>]) -> Self {
+        let (min_x, min_y, max_x, max_y) = points.iter().fold(
+            (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+            |(min_x, min_y, max_x, max_y), p| {
+                (
+                    min_x.min(p.x),
+                    min_y.min(p.y),
+                    max_x.max(p.x),
+                    max_y.max(p.y),
+                )
+            },
+        );
+        let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+        let size = (max_x - min_x).max(max_y - min_y).max(1.0);
+
+        let mut coords: Vec<_> = points.iter().map(|p| (p.x - cx, p.y - cy)).collect();
+        let n = coords.len() as u32;
+        coords.extend([
+            (-20.0 * size, -10.0 * size),
+            (20.0 * size, -10.0 * size),
+            (0.0, 20.0 * size),
+        ]);
+
+        Self {
+            coords,
+            point_count: n,
+            triangles: vec![[n, n + 1, n + 2]],
+            neighbours: vec![[NONE; 3]],
+            alive: vec![true],
+            marks: vec![0],
+            insertion: 0,
+            last: 0,
+            bounds: (min_x - cx, min_y - cy, size, size),
+        }
+    }
+
+    /// Returns the position of the point on the Z-order curve.
+    fn z_order(&self, i: u32) -> u64 {
+        let (x, y) = self.coords[i as usize];
+        let (min_x, min_y, w, h) = self.bounds;
+        let scale = |v: f64, min: f64, size: f64| ((v - min) / size * 65535.0) as u64;
+        let (x, y) = (scale(x, min_x, w), scale(y, min_y, h));
+
+        (0..16).fold(0, |z, bit| {
+            z | ((x >> bit) & 1) << (2 * bit) | ((y >> bit) & 1) << (2 * bit + 1)
+        })
+    }
+
+    fn insert(&mut self, i: u32) {
+        let p = self.coords[i as usize];
+        let coords = &self.coords;
+        let Some(start) = locate(
+            &self.triangles,
+            &self.neighbours,
+            |v| coords[v as usize],
+            self.last,
+            p,
+        ) else {
+            return;
+        };
+        if self.triangles[start as usize]
+            .iter()
+            .any(|&v| distance_sq(self.coords[v as usize], p) < EPSILON * EPSILON)
+        {
+            return;
+        }
+
+        // Cavity consists of connected triangles, whose circumcircle contains the point.
+        self.insertion += 1;
+        let mut cavity = vec![start];
+        self.marks[start as usize] = self.insertion;
+        let mut idx = 0;
+        while idx < cavity.len() {
+            let t = cavity[idx];
+            idx += 1;
+            for n in self.neighbours[t as usize] {
+                if n == NONE || self.marks[n as usize] == self.insertion {
+                    continue;
+                }
+                if self.in_circumcircle(n, p) {
+                    self.marks[n as usize] = self.insertion;
+                    cavity.push(n);
+                }
+            }
+        }
+
+        // Boundary of the cavity is connected to the point with new triangles.
+        let mut created: Vec<(u32, u32, u32)> = vec![];
+        for &t in &cavity {
+            self.alive[t as usize] = false;
+            let vertices = self.triangles[t as usize];
+            for e in 0..3 {
+                let n = self.neighbours[t as usize][e];
+                if n != NONE && self.marks[n as usize] == self.insertion {
+                    continue;
+                }
+
+                let (a, b) = (vertices[(e + 1) % 3], vertices[(e + 2) % 3]);
+                let new = self.triangles.len() as u32;
+                self.triangles.push([a, b, i]);
+                self.neighbours.push([NONE, NONE, n]);
+                self.alive.push(true);
+                self.marks.push(0);
+                if n != NONE {
+                    let slot = self.neighbours[n as usize]
+                        .iter()
+                        .position(|&x| x == t)
+                        .unwrap();
+                    self.neighbours[n as usize][slot] = new;
+                }
+                created.push((a, b, new));
+            }
+        }
+
+        // New triangles share edges with the point, and are linked to each other.
+        for &(a, b, t) in &created {
+            let next = created.iter().find(|(start, ..)| *start == b).unwrap().2;
+            let prev = created.iter().find(|(_, end, _)| *end == a).unwrap().2;
+            self.neighbours[t as usize][0] = next;
+            self.neighbours[t as usize][1] = prev;
+        }
+
+        self.last = created[0].2;
+    }
+
+    fn in_circumcircle(&self, t: u32, d: (f64, f64)) -> bool {
+        let [a, b, c] = self.triangles[t as usize].map(|v| self.coords[v as usize]);
+        let row = |(x, y): (f64, f64)| {
+            let (dx, dy) = (x - d.0, y - d.1);
+            (dx, dy, dx * dx + dy * dy)
+        };
+        let (ax, ay, aw) = row(a);
+        let (bx, by, bw) = row(b);
+        let (cx, cy, cw) = row(c);
+
+        ax * (by * cw - bw * cy) - ay * (bx * cw - bw * cx) + aw * (bx * cy - by * cx) > 0.0
+    }
+
+    /// Removes the super triangle and the replaced triangles.
+    fn finish(self) -> (Vec<[u32; 3]>, Vec<[u32; 3]>) {
+        let mut remap = vec![NONE; self.triangles.len()];
+        let mut triangles = vec![];
+        for (t, vertices) in self.triangles.iter().enumerate() {
+            if self.alive[t] && vertices.iter().all(|&v| v < self.point_count) {
+                remap[t] = triangles.len() as u32;
+                triangles.push(*vertices);
+            }
+        }
+
+        let neighbours = (0..self.triangles.len())
+            .filter(|&t| remap[t] != NONE)
+            .map(|t| self.neighbours[t].map(|n| if n == NONE { NONE } else { remap[n as usize] }))
+            .collect();
+
+        (triangles, neighbours)
+    }
+}
+
+/// Finds the triangle containing the point, by walking from the start triangle
+/// towards the point. Returns None if the point is outside of the triangulation.
+fn locate<F>(
+    triangles: &[[u32; 3]],
+    neighbours: &[[u32; 3]],
+    coords: F,
+    start: u32,
+    p: (f64, f64),
+) -> Option<u32>
+where
+    F: Fn(u32) -> (f64, f64),
+{
+    if triangles.is_empty() {
+        return None;
+    }
+
+    let mut t = start;
+    // Walks over the Delaunay triangulation always terminate,
+    // but the limit protects against rounding errors.
+    for _ in 0..triangles.len() + 16 {
+        let vertices = triangles[t as usize];
+        let edge = (0..3).find(|&e| {
+            let (a, b) = (vertices[(e + 1) % 3], vertices[(e + 2) % 3]);
+            orient(coords(a), coords(b), p) < 0.0
+        });
+
+        match edge {
+            None => return Some(t),
+            Some(e) if neighbours[t as usize][e] == NONE => return None,
+            Some(e) => t = neighbours[t as usize][e],
+        }
+    }
+
+    (0..triangles.len() as u32).find(|&t| {
+        let [a, b, c] = triangles[t as usize].map(&coords);
+        orient(a, b, p) >= 0.0 && orient(b, c, p) >= 0.0 && orient(c, a, p) >= 0.0
+    })
+}
+
+/// Returns twice the signed area of the triangle, positive if it is counter clockwise.
+fn orient(a: (f64, f64), b: (f64, f64), c: (f64, f64)) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+
+fn distance_sq(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Point;
+
+    use super::Tin;
+
+    /// Deterministic pseudo random points.
+    fn random_points(n: usize, seed: u64) -> Vec<Point<f32>> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 40) as f64 / (1u64 << 24) as f64
+        };
+
+        (0..n)
+            .map(|_| {
+                let (x, y) = (400000.0 + next() * 100.0, 100000.0 + next() * 100.0);
+                Point {
+                    x,
+                    y,
+                    data: (0.5 * (x - 400000.0) + 0.25 * (y - 100000.0)) as f32,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn triangulation_is_delaunay() {
+        let points = random_points(300, 3);
+        let tin = Tin::new(points.clone());
+
+        // Number of triangles is given by the number of points on the convex hull.
+        let hull = tin.triangles().len().abs_diff(2 * points.len() - 2);
+        assert!((3..40).contains(&hull), "{hull}");
+
+        // Coordinates are moved close to zero, so that the circumcircles are precise.
+        let points: Vec<_> = points
+            .iter()
+            .map(|p| Point {
+                x: p.x - 400000.0,
+                y: p.y - 100000.0,
+                data: p.data,
+            })
+            .collect();
+        for [a, b, c] in tin.triangles() {
+            let [a, b, c] = [a, b, c].map(|&i| &points[i as usize]);
+            // Circumcircle of the triangle.
+            let d = 2.0 * (a.x * (b.y - c.y) + b.x * (c.y - a.y) + c.x * (a.y - b.y));
+            let sq = |p: &Point<f32>| p.x * p.x + p.y * p.y;
+            let center = Point {
+                x: (sq(a) * (b.y - c.y) + sq(b) * (c.y - a.y) + sq(c) * (a.y - b.y)) / d,
+                y: (sq(a) * (c.x - b.x) + sq(b) * (a.x - c.x) + sq(c) * (b.x - a.x)) / d,
+                data: (),
+            };
+            let radius = center.distance(a);
+            assert!(points.iter().all(|p| center.distance(p) > radius - 1e-6));
+        }
+
+        // Heights of a plane are interpolated exactly.
+        let got = tin.elevation_at(400050.0, 100050.0).unwrap();
+        assert!((got - 37.5).abs() < 1e-3, "{got}");
+        assert_eq!(tin.elevation_at(399000.0, 100050.0), None);
+    }
+
+    #[test]
+    fn triangulation_of_grid() {
+        let mut points: Vec<_> = (0..9)
+            .map(|i| Point {
+                x: (i % 3) as f64,
+                y: (i / 3) as f64,
+                data: 1.0,
+            })
+            .collect();
+        points.push(points[4].clone());
+
+        let tin = Tin::new(points);
+        assert_eq!(tin.triangles().len(), 8);
+        assert_eq!(tin.elevation_at(0.5, 1.5), Some(1.0));
+        assert!(Tin::new(vec![]).triangles().is_empty());
+    }
+}